@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition};
+use thiserror::Error;
+
+use crate::schema_util::{as_option, references};
+
+pub mod python;
+pub mod rust;
+pub mod typescript;
+
+#[derive(Error, Debug)]
+pub enum CodegenError {
+    #[error("Unknown declaration: {0}")]
+    UnknownDeclaration(Declaration),
+    #[error("Recursive type {0} cannot be expressed in the target language")]
+    Recursive(Declaration),
+}
+
+/// Assigns every definition in a schema a unique identifier that is valid in
+/// most languages, e.g. `Tuple<u32, u64>` becomes `TupleU32U64`.
+pub struct TypeNames<'a>(HashMap<&'a Declaration, String>);
+
+impl<'a> TypeNames<'a> {
+    pub fn new(schema: &'a BorshSchemaContainer) -> Self {
+        let mut declarations = schema.definitions.keys().collect::<Vec<_>>();
+        declarations.sort();
+
+        let mut taken = HashSet::new();
+        let mut names = HashMap::new();
+        for declaration in declarations {
+            let base = to_pascal_case(declaration);
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !taken.insert(name.clone()) {
+                name = format!("{base}{suffix}");
+                suffix += 1;
+            }
+            names.insert(declaration, name);
+        }
+
+        Self(names)
+    }
+
+    pub fn get(&self, declaration: &Declaration) -> Option<&str> {
+        self.0.get(declaration).map(String::as_str)
+    }
+}
+
+/// Joins the alphanumeric runs of a declaration, capitalizing each, e.g.
+/// `HashMap<string, u8>` becomes `HashMapStringU8`.
+pub fn to_pascal_case(declaration: &str) -> String {
+    let mut name = String::with_capacity(declaration.len());
+    for part in declaration.split(|c: char| !c.is_ascii_alphanumeric() && c != '_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.extend(chars);
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, 'T');
+    }
+    name
+}
+
+/// Structs that are only ever used as the payload of an enum variant, which
+/// is what the derive macro generates for every enum variant.
+pub fn variant_only_structs(schema: &BorshSchemaContainer) -> HashSet<&Declaration> {
+    let mut payloads = HashSet::new();
+    let mut elsewhere = HashSet::new();
+    for definition in schema.definitions.values() {
+        match definition {
+            Definition::Enum { variants } if as_option(definition).is_none() => {
+                payloads.extend(variants.iter().map(|(_, d)| d));
+            }
+            _ => elsewhere.extend(references(definition)),
+        }
+    }
+    elsewhere.insert(&schema.declaration);
+
+    payloads
+        .into_iter()
+        .filter(|d| {
+            !elsewhere.contains(d)
+                && matches!(schema.definitions.get(*d), Some(Definition::Struct { .. }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_pascal_case;
+
+    #[test]
+    fn test() {
+        assert_eq!(to_pascal_case("Tuple<u32, u64>"), "TupleU32U64");
+        assert_eq!(to_pascal_case("Array<u8, 32>"), "ArrayU832");
+        assert_eq!(to_pascal_case("Wrapper"), "Wrapper");
+    }
+}
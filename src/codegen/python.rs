@@ -0,0 +1,348 @@
+//! Python definitions for
+//! [borsh-construct](https://github.com/near/borsh-construct-py).
+//!
+//! Every struct and enum is bound to a module-level name, in dependency
+//! order. References to a type that has not been bound yet, which only
+//! happens for recursive types, are wrapped in `LazyBound`.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Write,
+};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::schema_util::{as_map, as_option, dependency_order};
+
+use super::{to_pascal_case, variant_only_structs, CodegenError, TypeNames};
+
+const RESERVED: &[&str] = &["False", "None", "True"];
+
+pub fn generate(schema: &BorshSchemaContainer) -> Result<String, CodegenError> {
+    let mut generator = Generator {
+        schema,
+        names: TypeNames::new(schema),
+        variant_only: variant_only_structs(schema),
+        bound: HashSet::new(),
+        imports: BTreeSet::new(),
+    };
+
+    let mut body = String::new();
+    let mut root_emitted = false;
+    for declaration in dependency_order(schema) {
+        if generator.variant_only.contains(declaration) {
+            continue;
+        }
+        if let Some(name) = generator.named(declaration) {
+            let expression = generator.named_expression(declaration)?;
+            writeln!(body, "\n{name} = {expression}").unwrap();
+            generator.bound.insert(declaration);
+            root_emitted |= declaration == &schema.declaration;
+        }
+    }
+
+    if !root_emitted {
+        let expression = generator.expression(&schema.declaration)?;
+        writeln!(
+            body,
+            "\n{} = {expression}",
+            python_name(&to_pascal_case(&schema.declaration)),
+        )
+        .unwrap();
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "# Generated by borsh-cli from schema `{}`. Do not edit.",
+        schema.declaration,
+    )
+    .unwrap();
+    if generator.imports.remove("LazyBound") {
+        writeln!(out, "from construct import LazyBound").unwrap();
+    }
+    writeln!(
+        out,
+        "from borsh_construct import {}",
+        generator
+            .imports
+            .iter()
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+    .unwrap();
+    out.push_str(&body);
+
+    Ok(out)
+}
+
+struct Generator<'a> {
+    schema: &'a BorshSchemaContainer,
+    names: TypeNames<'a>,
+    variant_only: HashSet<&'a Declaration>,
+    bound: HashSet<&'a Declaration>,
+    imports: BTreeSet<&'static str>,
+}
+
+impl Generator<'_> {
+    fn named(&self, declaration: &Declaration) -> Option<String> {
+        match self.schema.definitions.get(declaration)? {
+            Definition::Struct { .. } => self.names.get(declaration).map(python_name),
+            d @ Definition::Enum { .. } if as_option(d).is_none() => {
+                self.names.get(declaration).map(python_name)
+            }
+            _ => None,
+        }
+    }
+
+    fn named_expression(&mut self, declaration: &Declaration) -> Result<String, CodegenError> {
+        match &self.schema.definitions[declaration] {
+            Definition::Struct { fields } => self.struct_expression(fields),
+            Definition::Enum { variants } => {
+                self.imports.insert("Enum");
+                let mut lines = Vec::with_capacity(variants.len() + 1);
+                for (variant, variant_declaration) in variants {
+                    lines.push(self.variant_expression(variant, variant_declaration)?);
+                }
+                lines.push(format!(
+                    "enum_name={}",
+                    string_literal(self.names.get(declaration).unwrap())
+                ));
+                Ok(format!("Enum(\n    {},\n)", lines.join(",\n    ")))
+            }
+            _ => unreachable!("only structs and enums are named"),
+        }
+    }
+
+    fn struct_expression(&mut self, fields: &Fields) -> Result<String, CodegenError> {
+        Ok(match fields {
+            Fields::NamedFields(fields) => {
+                self.imports.insert("CStruct");
+                let mut lines = Vec::with_capacity(fields.len());
+                for (field, declaration) in fields {
+                    lines.push(format!(
+                        "{} / {}",
+                        string_literal(field),
+                        self.expression(declaration)?,
+                    ));
+                }
+                format!("CStruct(\n    {},\n)", lines.join(",\n    "))
+            }
+            Fields::UnnamedFields(fields) => self.tuple_expression(fields)?,
+            Fields::Empty => {
+                self.imports.insert("CStruct");
+                "CStruct()".to_string()
+            }
+        })
+    }
+
+    fn variant_expression(
+        &mut self,
+        variant: &str,
+        declaration: &Declaration,
+    ) -> Result<String, CodegenError> {
+        let payload = match self.schema.definitions.get(declaration) {
+            Some(Definition::Struct {
+                fields: Fields::Empty,
+            }) if self.variant_only.contains(declaration) => None,
+            Some(Definition::Struct { fields }) if self.variant_only.contains(declaration) => {
+                // Nested one level deeper than a top-level struct
+                Some(self.struct_expression(fields)?.replace("\n", "\n    "))
+            }
+            _ if declaration == "nil" => None,
+            _ => {
+                self.imports.insert("TupleStruct");
+                Some(format!("TupleStruct({})", self.expression(declaration)?))
+            }
+        };
+
+        Ok(match payload {
+            Some(payload) => format!("{} / {payload}", string_literal(variant)),
+            None => string_literal(variant),
+        })
+    }
+
+    fn tuple_expression(&mut self, elements: &[Declaration]) -> Result<String, CodegenError> {
+        self.imports.insert("TupleStruct");
+        let mut items = Vec::with_capacity(elements.len());
+        for element in elements {
+            items.push(self.expression(element)?);
+        }
+        Ok(format!("TupleStruct({})", items.join(", ")))
+    }
+
+    fn expression(&mut self, declaration: &Declaration) -> Result<String, CodegenError> {
+        let primitive = match &declaration[..] {
+            "u8" => "U8",
+            "u16" => "U16",
+            "u32" => "U32",
+            "u64" => "U64",
+            "u128" => "U128",
+            "i8" => "I8",
+            "i16" => "I16",
+            "i32" => "I32",
+            "i64" => "I64",
+            "i128" => "I128",
+            "f32" => "F32",
+            "f64" => "F64",
+            "string" => "String",
+            "bool" => "Bool",
+            "nil" => {
+                self.imports.insert("CStruct");
+                return Ok("CStruct()".to_string());
+            }
+            _ => "",
+        };
+        if !primitive.is_empty() {
+            self.imports.insert(primitive);
+            return Ok(primitive.to_string());
+        }
+
+        if let Some(name) = self.named(declaration) {
+            return Ok(if self.bound.contains(declaration) {
+                name
+            } else {
+                self.imports.insert("LazyBound");
+                format!("LazyBound(lambda: {name})")
+            });
+        }
+        if let Some((key, value)) = as_map(self.schema, declaration) {
+            self.imports.insert("HashMap");
+            return Ok(format!(
+                "HashMap({}, {})",
+                self.expression(key)?,
+                self.expression(value)?,
+            ));
+        }
+
+        let definition = self
+            .schema
+            .definitions
+            .get(declaration)
+            .ok_or_else(|| CodegenError::UnknownDeclaration(declaration.clone()))?;
+        Ok(match definition {
+            Definition::Array { length, elements } => {
+                format!("{}[{length}]", self.expression(elements)?)
+            }
+            Definition::Sequence { elements } => {
+                self.imports.insert("Vec");
+                format!("Vec({})", self.expression(elements)?)
+            }
+            Definition::Tuple { elements } => self.tuple_expression(elements)?,
+            definition => match as_option(definition) {
+                Some(inner) => {
+                    self.imports.insert("Option");
+                    format!("Option({})", self.expression(inner)?)
+                }
+                None => unreachable!("structs and enums are named"),
+            },
+        })
+    }
+}
+
+fn python_name(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+fn string_literal(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{
+        schema::{BorshSchemaContainer, Definition, Fields},
+        BorshSchema,
+    };
+
+    use super::generate;
+    use fixtures::Parent;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use std::collections::HashMap;
+
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Parent {
+            pair: (u32, u64),
+            names: Vec<String>,
+            bytes: [u8; 4],
+            lookup: HashMap<String, u8>,
+            child: Option<Child>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Child {
+            Alpha { field: u32 },
+            Beta(u8, bool),
+            Gamma,
+        }
+    }
+
+    #[test]
+    fn test() {
+        let output = generate(&Parent::schema_container()).unwrap();
+
+        assert!(output.contains(
+            "from borsh_construct import Bool, CStruct, Enum, HashMap, Option, String, \
+             TupleStruct, U32, U64, U8, Vec"
+        ));
+        assert!(output.contains(
+            "Child = Enum(
+    \"Alpha\" / CStruct(
+        \"field\" / U32,
+    ),
+    \"Beta\" / TupleStruct(U8, Bool),
+    \"Gamma\",
+    enum_name=\"Child\",
+)"
+        ));
+        assert!(output.contains(
+            "Parent = CStruct(
+    \"pair\" / TupleStruct(U32, U64),
+    \"names\" / Vec(String),
+    \"bytes\" / U8[4],
+    \"lookup\" / HashMap(String, U8),
+    \"child\" / Option(Child),
+)"
+        ));
+    }
+
+    #[test]
+    fn recursive() {
+        let schema = BorshSchemaContainer {
+            declaration: "Node".to_string(),
+            definitions: [
+                (
+                    "Node".to_string(),
+                    Definition::Struct {
+                        fields: Fields::NamedFields(vec![(
+                            "children".to_string(),
+                            "Vec<Node>".to_string(),
+                        )]),
+                    },
+                ),
+                (
+                    "Vec<Node>".to_string(),
+                    Definition::Sequence {
+                        elements: "Node".to_string(),
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let output = generate(&schema).unwrap();
+
+        assert!(output.contains("from construct import LazyBound"));
+        assert!(output.contains("\"children\" / Vec(LazyBound(lambda: Node)),"));
+    }
+}
@@ -0,0 +1,503 @@
+//! Rust structs and enums deriving the Borsh traits.
+//!
+//! Generic-looking declarations such as `Vec<string>` or `Tuple<u32, u64>`
+//! are mapped back to the Rust types that produce them, and the per-variant
+//! structs generated for enums are folded back into the enum.
+//!
+//! Instances of generic types cannot be mapped back to the generic type, as
+//! the schema does not say which fields use the type parameters. They become
+//! concrete types instead, e.g. `Wrapper<u64>` becomes `struct WrapperU64`,
+//! with the same layout but a different declaration in its
+//! `schema_container()`. Names that cannot be raw identifiers, like `self`,
+//! get a trailing underscore, which likewise only changes the schema's names.
+
+use std::{collections::HashSet, fmt::Write};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::schema_util::{
+    as_map, as_option, as_result, dependency_order, recursive_declarations, unreachable_definitions,
+};
+
+use super::{to_pascal_case, variant_only_structs, CodegenError, TypeNames};
+
+const DERIVE: &str = "#[derive(BorshSerialize, BorshDeserialize, BorshSchema)]";
+
+/// Strict and reserved keywords, which are written as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Keywords that cannot be raw identifiers, which get a trailing underscore.
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+pub fn generate(schema: &BorshSchemaContainer) -> Result<String, CodegenError> {
+    let generator = Generator::new(schema, false);
+
+    let mut body = String::new();
+    generator.write_definitions(&mut body, dependency_order(schema))?;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by borsh-cli from schema `{}`. Do not edit.",
+        schema.declaration,
+    )
+    .unwrap();
+    if body.contains("HashMap<") {
+        writeln!(out, "use std::collections::HashMap;\n").unwrap();
+    }
+    writeln!(
+        out,
+        "use borsh::{{BorshDeserialize, BorshSchema, BorshSerialize}};"
+    )
+    .unwrap();
+    out.push_str(&body);
+
+    Ok(out)
+}
+
+/// Plain Rust-like declarations of every definition in the schema, for
+/// display rather than compilation: no derives, no visibility, and
+/// definitions that are unreachable from the root are included at the end.
+pub fn declarations(schema: &BorshSchemaContainer) -> Result<String, CodegenError> {
+    let generator = Generator::new(schema, true);
+
+    let mut out = String::new();
+    writeln!(out, "// Root: {}", schema.declaration).unwrap();
+    generator.write_definitions(&mut out, dependency_order(schema))?;
+
+    let unreachable = unreachable_definitions(schema);
+    if !unreachable.is_empty() {
+        writeln!(out, "\n// Unreachable from the root").unwrap();
+        for declaration in unreachable {
+            if generator.variant_only.contains(declaration) {
+                continue;
+            }
+            match generator.named(declaration) {
+                Some(name) => {
+                    out.push('\n');
+                    generator.write_named(&mut out, declaration, name)?;
+                }
+                None => writeln!(
+                    out,
+                    "\ntype {} = {};",
+                    to_pascal_case(declaration),
+                    generator.type_expression(declaration, None)?,
+                )
+                .unwrap(),
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+struct Generator<'a> {
+    schema: &'a BorshSchemaContainer,
+    names: TypeNames<'a>,
+    recursive: HashSet<&'a Declaration>,
+    variant_only: HashSet<&'a Declaration>,
+    /// Omit derives and visibility
+    plain: bool,
+}
+
+impl<'a> Generator<'a> {
+    fn new(schema: &'a BorshSchemaContainer, plain: bool) -> Self {
+        Self {
+            schema,
+            names: TypeNames::new(schema),
+            recursive: recursive_declarations(schema),
+            variant_only: variant_only_structs(schema),
+            plain,
+        }
+    }
+
+    fn visibility(&self) -> &'static str {
+        if self.plain {
+            ""
+        } else {
+            "pub "
+        }
+    }
+
+    /// Writes the named types among `declarations`, followed by an alias for
+    /// the root declaration if it is not itself a named type.
+    fn write_definitions(
+        &self,
+        out: &mut String,
+        declarations: Vec<&Declaration>,
+    ) -> Result<(), CodegenError> {
+        let mut root_emitted = false;
+        for declaration in declarations {
+            if self.variant_only.contains(declaration) {
+                continue;
+            }
+            if let Some(name) = self.named(declaration) {
+                out.push('\n');
+                self.write_named(out, declaration, name)?;
+                root_emitted |= declaration == &self.schema.declaration;
+            }
+        }
+
+        if !root_emitted {
+            writeln!(
+                out,
+                "\n{}type {} = {};",
+                self.visibility(),
+                to_pascal_case(&self.schema.declaration),
+                self.type_expression(&self.schema.declaration, None)?,
+            )
+            .unwrap();
+        }
+        Ok(())
+    }
+
+    fn named(&self, declaration: &Declaration) -> Option<&str> {
+        match self.schema.definitions.get(declaration)? {
+            Definition::Struct { .. } => self.names.get(declaration),
+            d @ Definition::Enum { .. }
+                if as_option(d).is_none() && as_result(declaration, d).is_none() =>
+            {
+                self.names.get(declaration)
+            }
+            _ => None,
+        }
+    }
+
+    fn write_named(
+        &self,
+        out: &mut String,
+        declaration: &Declaration,
+        name: &str,
+    ) -> Result<(), CodegenError> {
+        let owner = Some(declaration);
+        let vis = self.visibility();
+        if !self.plain {
+            writeln!(out, "{DERIVE}").unwrap();
+        }
+        match &self.schema.definitions[declaration] {
+            Definition::Struct { fields } => match fields {
+                Fields::NamedFields(fields) => {
+                    writeln!(out, "{vis}struct {name} {{").unwrap();
+                    for (field, declaration) in fields {
+                        writeln!(
+                            out,
+                            "    {vis}{}: {},",
+                            identifier(field),
+                            self.type_expression(declaration, owner)?,
+                        )
+                        .unwrap();
+                    }
+                    writeln!(out, "}}").unwrap();
+                }
+                Fields::UnnamedFields(fields) => {
+                    let mut types = Vec::with_capacity(fields.len());
+                    for declaration in fields {
+                        types.push(format!(
+                            "{vis}{}",
+                            self.type_expression(declaration, owner)?
+                        ));
+                    }
+                    writeln!(out, "{vis}struct {name}({});", types.join(", ")).unwrap();
+                }
+                Fields::Empty => writeln!(out, "{vis}struct {name};").unwrap(),
+            },
+            Definition::Enum { variants } => {
+                writeln!(out, "{vis}enum {name} {{").unwrap();
+                for (variant, declaration) in variants {
+                    self.write_variant(out, variant, declaration, owner)?;
+                }
+                writeln!(out, "}}").unwrap();
+            }
+            _ => unreachable!("only structs and enums are named"),
+        }
+        Ok(())
+    }
+
+    fn write_variant(
+        &self,
+        out: &mut String,
+        variant: &str,
+        declaration: &Declaration,
+        owner: Option<&Declaration>,
+    ) -> Result<(), CodegenError> {
+        let variant = identifier(variant);
+        let fields = match self.schema.definitions.get(declaration) {
+            Some(Definition::Struct { fields }) if self.variant_only.contains(declaration) => {
+                fields
+            }
+            _ if declaration == "nil" => &Fields::Empty,
+            _ => {
+                writeln!(
+                    out,
+                    "    {variant}({}),",
+                    self.type_expression(declaration, owner)?,
+                )
+                .unwrap();
+                return Ok(());
+            }
+        };
+
+        match fields {
+            Fields::NamedFields(fields) => {
+                writeln!(out, "    {variant} {{").unwrap();
+                for (field, declaration) in fields {
+                    writeln!(
+                        out,
+                        "        {}: {},",
+                        identifier(field),
+                        self.type_expression(declaration, owner)?,
+                    )
+                    .unwrap();
+                }
+                writeln!(out, "    }},").unwrap();
+            }
+            Fields::UnnamedFields(fields) => {
+                let mut types = Vec::with_capacity(fields.len());
+                for declaration in fields {
+                    types.push(self.type_expression(declaration, owner)?);
+                }
+                writeln!(out, "    {variant}({}),", types.join(", ")).unwrap();
+            }
+            Fields::Empty => writeln!(out, "    {variant},").unwrap(),
+        }
+        Ok(())
+    }
+
+    /// Rust type for a declaration. References from inside a recursive type
+    /// back into its cycle are boxed, unless already behind a heap allocation.
+    fn type_expression(
+        &self,
+        declaration: &Declaration,
+        owner: Option<&Declaration>,
+    ) -> Result<String, CodegenError> {
+        Ok(match &declaration[..] {
+            "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128"
+            | "f32" | "f64" | "bool" => declaration.to_string(),
+            "string" => "String".to_string(),
+            "nil" => "()".to_string(),
+            _ => {
+                if let Some(name) = self.named(declaration) {
+                    let needs_box = owner.is_some_and(|owner| {
+                        self.recursive.contains(owner) && self.recursive.contains(declaration)
+                    });
+                    return Ok(if needs_box {
+                        format!("Box<{name}>")
+                    } else {
+                        name.to_string()
+                    });
+                }
+                if let Some((key, value)) = as_map(self.schema, declaration) {
+                    return Ok(format!(
+                        "HashMap<{}, {}>",
+                        self.type_expression(key, None)?,
+                        self.type_expression(value, None)?,
+                    ));
+                }
+                let definition = self
+                    .schema
+                    .definitions
+                    .get(declaration)
+                    .ok_or_else(|| CodegenError::UnknownDeclaration(declaration.clone()))?;
+                match definition {
+                    Definition::Array { length, elements } => {
+                        format!("[{}; {length}]", self.type_expression(elements, owner)?)
+                    }
+                    Definition::Sequence { elements } => {
+                        format!("Vec<{}>", self.type_expression(elements, None)?)
+                    }
+                    Definition::Tuple { elements } => {
+                        let mut types = Vec::with_capacity(elements.len());
+                        for element in elements {
+                            types.push(self.type_expression(element, owner)?);
+                        }
+                        format!("({})", types.join(", "))
+                    }
+                    definition => {
+                        if let Some(inner) = as_option(definition) {
+                            format!("Option<{}>", self.type_expression(inner, owner)?)
+                        } else if let Some((ok, err)) = as_result(declaration, definition) {
+                            format!(
+                                "Result<{}, {}>",
+                                self.type_expression(ok, owner)?,
+                                self.type_expression(err, owner)?,
+                            )
+                        } else {
+                            unreachable!("structs and enums are named")
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else if NON_RAW_KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{
+        schema::{BorshSchemaContainer, Definition, Fields},
+        BorshSchema,
+    };
+
+    use super::generate;
+    use fixtures::{Outcome, Parent};
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use std::collections::HashMap;
+
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Parent {
+            pair: (u32, u64),
+            names: Vec<String>,
+            bytes: [u8; 4],
+            lookup: HashMap<String, u8>,
+            child: Option<Child>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Child {
+            Alpha { field: u32 },
+            Beta(u8, bool),
+            Gamma,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Outcome {
+            result: Result<u8, String>,
+            status: Status,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Ok(u8),
+            Err(String),
+        }
+    }
+
+    #[test]
+    fn test() {
+        let output = generate(&Parent::schema_container()).unwrap();
+
+        assert!(output.contains("use std::collections::HashMap;"));
+        assert!(output.contains(
+            "#[derive(BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct Parent {
+    pub pair: (u32, u64),
+    pub names: Vec<String>,
+    pub bytes: [u8; 4],
+    pub lookup: HashMap<String, u8>,
+    pub child: Option<Child>,
+}"
+        ));
+        assert!(output.contains(
+            "pub enum Child {
+    Alpha {
+        field: u32,
+    },
+    Beta(u8, bool),
+    Gamma,
+}"
+        ));
+        assert!(!output.contains("ChildAlpha"));
+    }
+
+    #[test]
+    fn results() {
+        let output = generate(&Outcome::schema_container()).unwrap();
+
+        assert!(output.contains("pub result: Result<u8, String>,"));
+        assert!(output.contains("pub status: Status,"));
+        assert!(output.contains(
+            "pub enum Status {
+    Ok(u8),
+    Err(String),
+}"
+        ));
+    }
+
+    #[test]
+    fn keywords() {
+        let schema = BorshSchemaContainer {
+            declaration: "Keywords".to_string(),
+            definitions: [(
+                "Keywords".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(
+                        ["type", "try", "yield", "self", "value"]
+                            .into_iter()
+                            .map(|name| (name.to_string(), "u8".to_string()))
+                            .collect(),
+                    ),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+
+        let output = generate(&schema).unwrap();
+
+        assert!(output.contains("    pub r#type: u8,"));
+        assert!(output.contains("    pub r#try: u8,"));
+        assert!(output.contains("    pub r#yield: u8,"));
+        assert!(output.contains("    pub self_: u8,"));
+        assert!(output.contains("    pub value: u8,"));
+    }
+
+    #[test]
+    fn recursive() {
+        let schema = BorshSchemaContainer {
+            declaration: "Node".to_string(),
+            definitions: [
+                (
+                    "Node".to_string(),
+                    Definition::Struct {
+                        fields: Fields::NamedFields(vec![
+                            ("next".to_string(), "Option<Node>".to_string()),
+                            ("children".to_string(), "Vec<Node>".to_string()),
+                        ]),
+                    },
+                ),
+                (
+                    "Option<Node>".to_string(),
+                    Definition::Enum {
+                        variants: vec![
+                            ("None".to_string(), "nil".to_string()),
+                            ("Some".to_string(), "Node".to_string()),
+                        ],
+                    },
+                ),
+                (
+                    "Vec<Node>".to_string(),
+                    Definition::Sequence {
+                        elements: "Node".to_string(),
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let output = generate(&schema).unwrap();
+
+        assert!(output.contains("    pub next: Option<Box<Node>>,"));
+        assert!(output.contains("    pub children: Vec<Node>,"));
+    }
+}
@@ -0,0 +1,447 @@
+//! TypeScript interfaces plus the matching schema objects for
+//! [borsh-js](https://github.com/near/borsh-js) 1.x.
+//!
+//! Structs and enums become named types; sequences, arrays, tuples, options
+//! and maps are written inline. The schema objects of recursive types are
+//! declared empty up front and filled in once everything they refer to has
+//! been declared.
+
+use std::{collections::HashSet, fmt::Write};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::schema_util::{
+    as_map, as_option, clone_schema, dependency_order, recursive_declarations,
+};
+
+use super::{to_pascal_case, CodegenError, TypeNames};
+
+pub fn generate(schema: &BorshSchemaContainer) -> Result<String, CodegenError> {
+    let names = TypeNames::new(schema);
+    let mut generator = Generator {
+        schema,
+        names,
+        recursive: HashSet::new(),
+    };
+    let order = dependency_order(schema);
+
+    // Inline types are written out in full, so only cycles through a named
+    // type can be expressed
+    let mut inline = clone_schema(schema);
+    inline
+        .definitions
+        .retain(|declaration, _| generator.named(declaration).is_none());
+    if let Some(declaration) = recursive_declarations(&inline).into_iter().min() {
+        return Err(CodegenError::Recursive(declaration.clone()));
+    }
+    let recursive = recursive_declarations(schema);
+    generator.recursive = order
+        .iter()
+        .copied()
+        .filter(|d| recursive.contains(d) && generator.named(d).is_some())
+        .collect();
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by borsh-cli from schema `{}`. Do not edit.",
+        schema.declaration,
+    )
+    .unwrap();
+    writeln!(out, "import type {{ Schema }} from 'borsh';").unwrap();
+
+    let recursive = order
+        .iter()
+        .filter(|d| generator.recursive.contains(*d))
+        .filter_map(|d| generator.named(d))
+        .collect::<Vec<_>>();
+    if !recursive.is_empty() {
+        out.push('\n');
+        writeln!(
+            out,
+            "// Recursive schemas, filled in below. borsh-js cannot validate cyclic"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "// schemas, so pass `validate = false` to `serialize` and `deserialize`."
+        )
+        .unwrap();
+        for name in recursive {
+            writeln!(out, "export const {name}Schema = {{}} as Schema;").unwrap();
+        }
+    }
+
+    let mut root_emitted = false;
+    for declaration in order {
+        if let Some(name) = generator.named(declaration) {
+            out.push('\n');
+            generator.write_named(
+                &mut out,
+                name,
+                &schema.definitions[declaration],
+                generator.recursive.contains(declaration),
+            )?;
+            root_emitted |= declaration == &schema.declaration;
+        }
+    }
+
+    if !root_emitted {
+        let name = to_pascal_case(&schema.declaration);
+        out.push('\n');
+        writeln!(
+            out,
+            "export type {name} = {};",
+            generator.type_expression(&schema.declaration)?,
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "export const {name}Schema: Schema = {};",
+            generator.schema_expression(&schema.declaration)?,
+        )
+        .unwrap();
+    }
+
+    Ok(out)
+}
+
+struct Generator<'a> {
+    schema: &'a BorshSchemaContainer,
+    names: TypeNames<'a>,
+    /// Named types whose schema objects are declared up front.
+    recursive: HashSet<&'a Declaration>,
+}
+
+impl Generator<'_> {
+    /// Structs and enums other than `Option<T>` get a named type. Everything
+    /// else is written inline.
+    fn named(&self, declaration: &Declaration) -> Option<&str> {
+        match self.schema.definitions.get(declaration)? {
+            Definition::Struct { .. } => self.names.get(declaration),
+            d @ Definition::Enum { .. } if as_option(d).is_none() => self.names.get(declaration),
+            _ => None,
+        }
+    }
+
+    fn write_named(
+        &self,
+        out: &mut String,
+        name: &str,
+        definition: &Definition,
+        recursive: bool,
+    ) -> Result<(), CodegenError> {
+        let (open, close) = match recursive {
+            true => (format!("Object.assign({name}Schema, {{"), "});"),
+            false => (format!("export const {name}Schema: Schema = {{"), "};"),
+        };
+        match definition {
+            Definition::Struct {
+                fields: Fields::Empty,
+            } => {
+                writeln!(out, "export type {name} = Record<string, never>;").unwrap();
+                writeln!(
+                    out,
+                    "export const {name}Schema: Schema = {{ struct: {{}} }};"
+                )
+                .unwrap();
+            }
+            Definition::Struct { fields } => {
+                let fields = keyed_fields(fields);
+                writeln!(out, "export interface {name} {{").unwrap();
+                for (key, declaration) in &fields {
+                    writeln!(out, "  {}: {};", key, self.type_expression(declaration)?).unwrap();
+                }
+                writeln!(out, "}}").unwrap();
+                writeln!(out, "{open}").unwrap();
+                writeln!(out, "  struct: {{").unwrap();
+                for (key, declaration) in &fields {
+                    writeln!(
+                        out,
+                        "    {}: {},",
+                        key,
+                        self.schema_expression(declaration)?
+                    )
+                    .unwrap();
+                }
+                writeln!(out, "  }},").unwrap();
+                writeln!(out, "{close}").unwrap();
+            }
+            Definition::Enum { variants } => {
+                if variants.is_empty() {
+                    writeln!(out, "export type {name} = never;").unwrap();
+                } else {
+                    writeln!(out, "export type {name} =").unwrap();
+                    for (variant, declaration) in variants {
+                        writeln!(
+                            out,
+                            "  | {{ {}: {} }}",
+                            property_key(variant),
+                            self.type_expression(declaration)?,
+                        )
+                        .unwrap();
+                    }
+                    out.pop();
+                    writeln!(out, ";").unwrap();
+                }
+                writeln!(out, "{open}").unwrap();
+                writeln!(out, "  enum: [").unwrap();
+                for (variant, declaration) in variants {
+                    writeln!(
+                        out,
+                        "    {{ struct: {{ {}: {} }} }},",
+                        property_key(variant),
+                        self.schema_expression(declaration)?,
+                    )
+                    .unwrap();
+                }
+                writeln!(out, "  ],").unwrap();
+                writeln!(out, "{close}").unwrap();
+            }
+            _ => unreachable!("only structs and enums are named"),
+        }
+        Ok(())
+    }
+
+    fn type_expression(&self, declaration: &Declaration) -> Result<String, CodegenError> {
+        Ok(match &declaration[..] {
+            "u8" | "u16" | "u32" | "i8" | "i16" | "i32" | "f32" | "f64" => "number".to_string(),
+            "u64" | "u128" | "i64" | "i128" => "bigint".to_string(),
+            "string" => "string".to_string(),
+            "bool" => "boolean".to_string(),
+            "nil" => "Record<string, never>".to_string(),
+            _ => {
+                if let Some(name) = self.named(declaration) {
+                    return Ok(name.to_string());
+                }
+                if let Some((key, value)) = as_map(self.schema, declaration) {
+                    return Ok(format!(
+                        "Map<{}, {}>",
+                        self.type_expression(key)?,
+                        self.type_expression(value)?,
+                    ));
+                }
+                match self.definition(declaration)? {
+                    Definition::Array { elements, .. } | Definition::Sequence { elements } => {
+                        let element = self.type_expression(elements)?;
+                        if element.contains('|') {
+                            format!("({element})[]")
+                        } else {
+                            format!("{element}[]")
+                        }
+                    }
+                    Definition::Tuple { elements } => {
+                        // borsh-js accesses tuple elements by index, so
+                        // arrays encode as the struct schema below
+                        let mut entries = Vec::with_capacity(elements.len());
+                        for element in elements {
+                            entries.push(self.type_expression(element)?);
+                        }
+                        format!("[{}]", entries.join(", "))
+                    }
+                    definition => match as_option(definition) {
+                        Some(inner) => format!("{} | null", self.type_expression(inner)?),
+                        None => unreachable!("structs and enums are named"),
+                    },
+                }
+            }
+        })
+    }
+
+    fn schema_expression(&self, declaration: &Declaration) -> Result<String, CodegenError> {
+        Ok(match &declaration[..] {
+            "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128"
+            | "f32" | "f64" | "string" | "bool" => format!("'{declaration}'"),
+            "nil" => "{ struct: {} }".to_string(),
+            _ => {
+                if let Some(name) = self.named(declaration) {
+                    return Ok(format!("{name}Schema"));
+                }
+                if let Some((key, value)) = as_map(self.schema, declaration) {
+                    return Ok(format!(
+                        "{{ map: {{ key: {}, value: {} }} }}",
+                        self.schema_expression(key)?,
+                        self.schema_expression(value)?,
+                    ));
+                }
+                match self.definition(declaration)? {
+                    Definition::Array { length, elements } => format!(
+                        "{{ array: {{ type: {}, len: {length} }} }}",
+                        self.schema_expression(elements)?,
+                    ),
+                    Definition::Sequence { elements } => format!(
+                        "{{ array: {{ type: {} }} }}",
+                        self.schema_expression(elements)?,
+                    ),
+                    Definition::Tuple { elements } => {
+                        let mut entries = Vec::with_capacity(elements.len());
+                        for (i, element) in elements.iter().enumerate() {
+                            entries.push(format!("{i}: {}", self.schema_expression(element)?));
+                        }
+                        format!("{{ struct: {{ {} }} }}", entries.join(", "))
+                    }
+                    definition => match as_option(definition) {
+                        Some(inner) => format!("{{ option: {} }}", self.schema_expression(inner)?),
+                        None => unreachable!("structs and enums are named"),
+                    },
+                }
+            }
+        })
+    }
+
+    fn definition(&self, declaration: &Declaration) -> Result<&Definition, CodegenError> {
+        self.schema
+            .definitions
+            .get(declaration)
+            .ok_or_else(|| CodegenError::UnknownDeclaration(declaration.clone()))
+    }
+}
+
+/// Struct fields as object keys. Unnamed fields are keyed by index, which is
+/// how borsh-js represents them.
+fn keyed_fields(fields: &Fields) -> Vec<(String, &Declaration)> {
+    match fields {
+        Fields::NamedFields(fields) => fields
+            .iter()
+            .map(|(name, declaration)| (property_key(name), declaration))
+            .collect(),
+        Fields::UnnamedFields(fields) => fields
+            .iter()
+            .enumerate()
+            .map(|(i, declaration)| (i.to_string(), declaration))
+            .collect(),
+        Fields::Empty => vec![],
+    }
+}
+
+fn property_key(name: &str) -> String {
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{
+        schema::{BorshSchemaContainer, Definition},
+        BorshSchema,
+    };
+
+    use super::generate;
+    use fixtures::Parent;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use std::collections::HashMap;
+
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Parent {
+            pair: (u32, u64),
+            names: Vec<String>,
+            bytes: [u8; 4],
+            lookup: HashMap<String, u8>,
+            child: Option<Child>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Child {
+            Alpha { field: u32 },
+            Gamma,
+        }
+    }
+
+    #[test]
+    fn test() {
+        let output = generate(&Parent::schema_container()).unwrap();
+
+        assert!(output.contains(
+            "export interface Parent {
+  pair: [number, bigint];
+  names: string[];
+  bytes: number[];
+  lookup: Map<string, number>;
+  child: Child | null;
+}"
+        ));
+        assert!(output.contains(
+            "    pair: { struct: { 0: 'u32', 1: 'u64' } },
+    names: { array: { type: 'string' } },
+    bytes: { array: { type: 'u8', len: 4 } },
+    lookup: { map: { key: 'string', value: 'u8' } },
+    child: { option: ChildSchema },"
+        ));
+        assert!(output.contains(
+            "export type Child =
+  | { Alpha: ChildAlpha }
+  | { Gamma: ChildGamma };"
+        ));
+        assert!(output.contains("export type ChildGamma = Record<string, never>;"));
+        // Dependencies are declared before use
+        assert!(
+            output.find("const ChildSchema").unwrap() < output.find("const ParentSchema").unwrap()
+        );
+    }
+
+    #[test]
+    fn recursive() {
+        // The derive cannot build recursive schemas
+        let schema = BorshSchemaContainer {
+            declaration: "Tree".to_string(),
+            definitions: [
+                (
+                    "Tree".to_string(),
+                    Definition::Enum {
+                        variants: vec![
+                            ("Leaf".to_string(), "u8".to_string()),
+                            ("Node".to_string(), "Vec<Tree>".to_string()),
+                        ],
+                    },
+                ),
+                (
+                    "Vec<Tree>".to_string(),
+                    Definition::Sequence {
+                        elements: "Tree".to_string(),
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let output = generate(&schema).unwrap();
+
+        assert!(output.contains("export const TreeSchema = {} as Schema;"));
+        assert!(output.contains(
+            "export type Tree =
+  | { Leaf: number }
+  | { Node: Tree[] };
+Object.assign(TreeSchema, {
+  enum: [
+    { struct: { Leaf: 'u8' } },
+    { struct: { Node: { array: { type: TreeSchema } } } },
+  ],
+});"
+        ));
+
+        // Cycles without a named type would be written out forever
+        let mut schema = schema;
+        schema.definitions.insert(
+            "Tree".to_string(),
+            Definition::Sequence {
+                elements: "Vec<Tree>".to_string(),
+            },
+        );
+        assert!(generate(&schema).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    fs::OpenOptions,
+    io::{Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::{dynamic_schema::serialize_with_schema, records::Records};
+
+use super::{
+    get_input_bytes, get_input_schema, has_other_header, output_bytes, output_writer,
+    split_schema_header, Execute, IOError,
+};
+
+#[derive(Args, Debug)]
+/// Append an element to a top-level sequence or record stream.
+///
+/// The JSON item is encoded with the element schema and appended, and the
+/// length prefix of the sequence is updated. The data file is modified in
+/// place unless an output file is given, without rewriting the existing
+/// elements.
+pub struct AppendArgs {
+    /// Borsh data to append to.
+    pub data_path: PathBuf,
+
+    /// Read the JSON item from this file, otherwise from stdin.
+    pub item_path: Option<PathBuf>,
+
+    /// Write the result to this file (`-` for stdout) instead of modifying
+    /// the data file.
+    pub output_path: Option<PathBuf>,
+
+    /// Schema of the data. Defaults to the schema embedded in the data.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the data as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+}
+
+pub struct Append<'a> {
+    pub data: Vec<u8>,
+    pub item: serde_json::Value,
+    /// Where to write the whole result, otherwise the data file is modified
+    /// in place.
+    pub output: Option<Box<dyn Write + 'a>>,
+    pub data_path: PathBuf,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+}
+
+impl TryFrom<&'_ AppendArgs> for Append<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        AppendArgs {
+            data_path,
+            item_path,
+            output_path,
+            schema,
+            stream,
+        }: &'_ AppendArgs,
+    ) -> Result<Self, Self::Error> {
+        let output = match output_path {
+            None => None,
+            Some(path) if path.as_os_str() == "-" => Some(output_writer(None)?),
+            Some(path) => Some(output_writer(Some(path))?),
+        };
+        Ok(Self {
+            data: get_input_bytes(Some(data_path))?,
+            item: serde_json::from_slice(&get_input_bytes(item_path.as_ref())?)
+                .map_err(|_| IOError::DeserializeJson)?,
+            output,
+            data_path: data_path.clone(),
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+        })
+    }
+}
+
+/// Changes that append an item to data.
+struct Edit {
+    /// Offset and new value of the length prefix.
+    prefix: Option<(usize, u32)>,
+    bytes: Vec<u8>,
+}
+
+fn edit(
+    data: &[u8],
+    item: &serde_json::Value,
+    schema: Option<BorshSchemaContainer>,
+    stream: bool,
+) -> Result<Edit, IOError> {
+    let (schema, has_header, elements) = split_schema_header(data, schema)?;
+    if !has_header && has_other_header(data) {
+        return Err(IOError::Append(
+            "the data has an embedded schema header that differs from --schema".to_string(),
+        ));
+    }
+    let records = Records::new(&schema, stream).map_err(IOError::NotASequence)?;
+    // Appending to data that does not match the schema would write a length
+    // prefix at the wrong place
+    records
+        .index(elements, 0)
+        .map_err(|e| IOError::Append(format!("the data does not match the schema: {e}")))?;
+
+    let (length, _) = records
+        .split_prefix(elements)
+        .map_err(|_| IOError::DeserializeBorsh("sequence length"))?;
+    let prefix = length
+        .map(|length| {
+            let length = length
+                .checked_add(1)
+                .ok_or_else(|| IOError::Append("the sequence is full".to_string()))?;
+            Ok::<_, IOError>((data.len() - elements.len(), length))
+        })
+        .transpose()?;
+
+    let mut bytes = vec![];
+    serialize_with_schema(&mut bytes, item, &records.schema)
+        .map_err(|e| IOError::Append(e.to_string()))?;
+    Ok(Edit { prefix, bytes })
+}
+
+impl Execute for Append<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let Edit { prefix, bytes } = edit(&self.data, &self.item, self.schema.take(), self.stream)?;
+
+        if let Some(output) = &mut self.output {
+            if let Some((offset, length)) = prefix {
+                self.data[offset..offset + 4].copy_from_slice(&length.to_le_bytes());
+            }
+            output_bytes(&mut *output, &self.data)?;
+            return output_bytes(output, &bytes);
+        }
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(&self.data_path)
+            .map_err(|_| IOError::CreateOutputFile(self.data_path.display().to_string()))?;
+        // Append the item before counting it, so that an interrupted write
+        // leaves trailing bytes rather than a length past the end
+        file.seek(SeekFrom::End(0))
+            .and_then(|_| file.write_all(&bytes))
+            .map_err(|_| IOError::WriteBytes)?;
+        if let Some((offset, length)) = prefix {
+            file.seek(SeekFrom::Start(offset as u64))
+                .and_then(|_| file.write_all(&length.to_le_bytes()))
+                .map_err(|_| IOError::WriteBytes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{schema::BorshSchemaContainer, BorshSchema, BorshSerialize};
+    use serde_json::json;
+
+    use crate::command::Execute;
+
+    use super::Append;
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Entry {
+        id: u32,
+        name: String,
+    }
+
+    fn append(
+        data: Vec<u8>,
+        item: serde_json::Value,
+        schema: Option<BorshSchemaContainer>,
+        stream: bool,
+    ) -> Result<Vec<u8>, String> {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Append {
+            data,
+            item,
+            output: Some(Box::new(writer)),
+            data_path: "unused".into(),
+            schema,
+            stream,
+        };
+
+        let result = p.execute();
+        drop(p);
+
+        result.map(|_| output_vector).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test() {
+        let entry = |id: u32| Entry {
+            id,
+            name: id.to_string(),
+        };
+        let item = json!({ "id": 2, "name": "2" });
+
+        let data = borsh::try_to_vec_with_schema(&vec![entry(1)]).unwrap();
+        assert_eq!(
+            append(data.clone(), item.clone(), None, false),
+            Ok(borsh::try_to_vec_with_schema(&vec![entry(1), entry(2)]).unwrap()),
+        );
+        assert_eq!(
+            append(
+                data,
+                item.clone(),
+                Some(Vec::<Entry>::schema_container()),
+                false
+            ),
+            Ok(borsh::try_to_vec_with_schema(&vec![entry(1), entry(2)]).unwrap()),
+        );
+
+        let data = entry(1).try_to_vec().unwrap();
+        assert_eq!(
+            append(data, item.clone(), Some(Entry::schema_container()), true),
+            Ok([
+                entry(1).try_to_vec().unwrap(),
+                entry(2).try_to_vec().unwrap()
+            ]
+            .concat()),
+        );
+    }
+
+    #[test]
+    fn mismatched_data() {
+        let item = json!({ "id": 2, "name": "2" });
+        let schema = Vec::<Entry>::schema_container;
+
+        // A header for another schema is not taken for elements
+        let data = borsh::try_to_vec_with_schema(&vec![1u64]).unwrap();
+        assert!(append(data, item.clone(), Some(schema()), false).is_err());
+
+        // The length prefix must agree with the elements that follow
+        let mut data = vec![Entry {
+            id: 1,
+            name: "1".to_string(),
+        }]
+        .try_to_vec()
+        .unwrap();
+        data.push(0);
+        assert!(append(data.clone(), item.clone(), Some(schema()), false).is_err());
+        data.truncate(data.len() - 2);
+        assert!(append(data, item, Some(schema()), false).is_err());
+    }
+}
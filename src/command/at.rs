@@ -0,0 +1,472 @@
+use std::{
+    fs::{self, File},
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSerialize};
+use clap::Args;
+
+use crate::{
+    dynamic_schema::deserialize_from_schema,
+    records::{IndexFile, OffsetIndex, Records},
+    schema_util::clone_schema,
+};
+
+use super::{
+    get_input_bytes, get_input_schema,
+    index::{modified, sidecar_path},
+    output_json, output_writer, split_schema_header, Execute, IOError,
+};
+
+/// An element index like `7`, or a range of them like `10..20`, `10..` or
+/// `..20`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    Element(u64),
+    Range(u64, Option<u64>),
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |s: &str| {
+            s.parse::<u64>()
+                .map_err(|_| format!("Expected an element index or a range like 10..20: {s}"))
+        };
+        match s.split_once("..") {
+            None => number(s).map(Self::Element),
+            Some((start, end)) => Ok(Self::Range(
+                if start.is_empty() { 0 } else { number(start)? },
+                if end.is_empty() {
+                    None
+                } else {
+                    Some(number(end)?)
+                },
+            )),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+/// Decode selected elements of a top-level sequence or record stream to JSON.
+///
+/// Only the schema header and the selected elements are read from the input
+/// file. They are located with the index built by `borsh index`
+/// (`<INPUT_PATH>.index` by default), or without one if the elements have a
+/// fixed size. Otherwise, the whole input is read and indexed first.
+pub struct AtArgs {
+    /// Element to decode, like `7`, or a range of elements, like `10..20`,
+    /// which is decoded to an array.
+    pub selection: Selection,
+
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+
+    /// Index built by `borsh index`.
+    #[arg(short, long)]
+    pub index: Option<PathBuf>,
+
+    /// Format output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+/// Data that elements can be read from without loading all of it.
+pub trait Input: Read + Seek {}
+
+impl<T: Read + Seek> Input for T {}
+
+pub struct At<'a> {
+    pub selection: Selection,
+    pub input: Box<dyn Input + 'a>,
+    /// Modification time of the input file, to check the index against.
+    pub modified: Option<u64>,
+    pub output: Box<dyn Write + 'a>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+    pub index: Option<Vec<u8>>,
+    /// Whether the index was found next to the input rather than given, in
+    /// which case a stale one is ignored rather than an error.
+    pub sidecar: bool,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ AtArgs> for At<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        AtArgs {
+            selection,
+            input_path,
+            output_path,
+            schema,
+            stream,
+            index,
+            pretty,
+        }: &'_ AtArgs,
+    ) -> Result<Self, Self::Error> {
+        let modified = input_path.as_deref().and_then(modified);
+        let (index, sidecar) = match (index, input_path) {
+            (Some(path), _) => (Some(get_input_bytes(Some(path))?), false),
+            // A missing sidecar just means indexing the input
+            (None, Some(input_path)) => (fs::read(sidecar_path(input_path)).ok(), true),
+            (None, None) => (None, false),
+        };
+        // Stdin cannot seek, so it is read whole
+        let input: Box<dyn Input> = match input_path {
+            Some(path) => Box::new(
+                File::open(path).map_err(|_| IOError::ReadInputFile(path.display().to_string()))?,
+            ),
+            None => Box::new(Cursor::new(get_input_bytes(None)?)),
+        };
+        Ok(Self {
+            selection: *selection,
+            input,
+            modified,
+            output: output_writer(output_path.as_ref())?,
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+            index,
+            sidecar,
+            pretty: *pretty,
+        })
+    }
+}
+
+/// Reads `len` bytes of input at `offset`.
+fn read_at(input: &mut dyn Input, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    input.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![];
+    input.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+fn read_error(_: io::Error) -> IOError {
+    IOError::DeserializeBorsh("data according to schema")
+}
+
+/// The schema of input of `data_len` bytes, and where its data starts after
+/// any schema header. Only reads as much input as the header takes.
+fn read_header(
+    input: &mut dyn Input,
+    data_len: u64,
+    schema: Option<BorshSchemaContainer>,
+) -> Result<(BorshSchemaContainer, u64), IOError> {
+    let mut size = match &schema {
+        Some(schema) => schema.try_to_vec().map_err(|_| IOError::WriteBorsh)?.len() as u64,
+        None => 4096,
+    };
+    loop {
+        let prefix = read_at(input, 0, size.min(data_len)).map_err(read_error)?;
+        match split_schema_header(&prefix, schema.as_ref().map(clone_schema)) {
+            Ok((schema, _, data)) => return Ok((schema, (prefix.len() - data.len()) as u64)),
+            // The header may be longer than what was read
+            Err(IOError::DeserializeBorsh(_)) if size < data_len => size *= 2,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+impl At<'_> {
+    /// Checks the index, if any, against the input.
+    fn fresh_index(
+        &mut self,
+        records: &Records,
+        data_len: u64,
+    ) -> Result<Option<IndexFile>, IOError> {
+        let Some(index) = &self.index else {
+            return Ok(None);
+        };
+        let index = IndexFile::try_from_slice(index)
+            .map_err(|_| IOError::InvalidIndex("not an index file".to_string()))?;
+        if !index.describes(records) {
+            return match self.sidecar {
+                true => Ok(None),
+                false => Err(IOError::InvalidIndex(
+                    "the input was indexed with a different schema or --stream mode".to_string(),
+                )),
+            };
+        }
+        let fresh = self
+            .input
+            .rewind()
+            .and_then(|_| index.is_fresh(data_len, self.modified, &mut self.input))
+            .map_err(|_| IOError::InvalidIndex("failed to hash the input".to_string()))?;
+        match fresh {
+            true => Ok(Some(index)),
+            // A stale sidecar just means indexing again
+            false if self.sidecar => Ok(None),
+            false => Err(IOError::InvalidIndex(
+                "the input has changed since it was indexed".to_string(),
+            )),
+        }
+    }
+
+    /// Locates the elements that start at `start`, reading as little of the
+    /// input as possible.
+    fn offsets(
+        &mut self,
+        records: &Records,
+        start: u64,
+        data_len: u64,
+    ) -> Result<OffsetIndex, IOError> {
+        if let Some(index) = self.fresh_index(records, data_len)? {
+            return Ok(index.offsets);
+        }
+
+        let (length, data_start) = match records.prefixed {
+            true => {
+                let prefix = read_at(&mut self.input, start, 4).map_err(read_error)?;
+                let length = u32::from_le_bytes(prefix.try_into().unwrap());
+                (Some(length), start + 4)
+            }
+            false => (None, start),
+        };
+        if let Some(index) = records.fixed_index(length, data_start, data_len - data_start) {
+            return index.map_err(read_error);
+        }
+
+        // Elements of varying size can only be found by decoding them all
+        let data = read_at(&mut self.input, start, data_len - start).map_err(read_error)?;
+        records.index(&data, start).map_err(read_error)
+    }
+}
+
+impl Execute for At<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let data_len = self.input.seek(SeekFrom::End(0)).map_err(read_error)?;
+        let (schema, start) = read_header(&mut self.input, data_len, self.schema.take())?;
+        let records = Records::new(&schema, self.stream).map_err(IOError::NotASequence)?;
+        let offsets = self.offsets(&records, start, data_len)?;
+
+        let len = offsets.len();
+        let mut decode = |i: u64| {
+            let range = offsets.range(i).ok_or(IOError::OutOfRange(i, len))?;
+            let bytes = read_at(&mut self.input, range.start, range.end - range.start)
+                .map_err(|_| IOError::InvalidIndex("offsets past the end".to_string()))?;
+            deserialize_from_schema(&mut &bytes[..], &records.schema)
+                .map_err(|_| IOError::DeserializeBorsh("data according to schema"))
+        };
+
+        let value = match self.selection {
+            Selection::Element(i) => decode(i)?,
+            Selection::Range(from, to) => {
+                let to = to.unwrap_or(len);
+                if to > len {
+                    return Err(IOError::OutOfRange(to - 1, len));
+                }
+                if from > len {
+                    return Err(IOError::OutOfRange(from, len));
+                }
+                if from > to {
+                    return Err(IOError::InvalidIndex(format!(
+                        "range {from}..{to} is backwards"
+                    )));
+                }
+                (from..to)
+                    .map(decode)
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into()
+            }
+        };
+
+        output_json(&mut self.output, &value, self.pretty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        io::{BufWriter, Cursor, Read, Seek, SeekFrom},
+        rc::Rc,
+    };
+
+    use borsh::{BorshSchema, BorshSerialize};
+    use serde_json::json;
+
+    use crate::command::{index, Execute};
+
+    use super::{At, Selection};
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Entry {
+        id: u8,
+        name: String,
+    }
+
+    /// Input that counts the bytes read from it.
+    struct Counted {
+        input: Cursor<Vec<u8>>,
+        read: Rc<Cell<usize>>,
+    }
+
+    impl Read for Counted {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.input.read(buf)?;
+            self.read.set(self.read.get() + n);
+            Ok(n)
+        }
+    }
+
+    impl Seek for Counted {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.input.seek(pos)
+        }
+    }
+
+    /// Decoded selection, and the number of bytes of input read.
+    fn at_modified(
+        selection: &str,
+        input: &[u8],
+        modified: Option<u64>,
+        schema: Option<borsh::schema::BorshSchemaContainer>,
+        stream: bool,
+        index: Option<Vec<u8>>,
+    ) -> (Result<serde_json::Value, String>, usize) {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+        let read = Rc::new(Cell::new(0));
+
+        let mut p = At {
+            selection: selection.parse().unwrap(),
+            input: Box::new(Counted {
+                input: Cursor::new(input.to_vec()),
+                read: read.clone(),
+            }),
+            modified,
+            output: Box::new(writer),
+            schema,
+            stream,
+            index,
+            sidecar: false,
+            pretty: false,
+        };
+
+        let result = p.execute();
+        drop(p);
+
+        let value = result
+            .map(|_| serde_json::from_slice(&output_vector).unwrap())
+            .map_err(|e| e.to_string());
+        (value, read.get())
+    }
+
+    fn at(
+        selection: &str,
+        input: &[u8],
+        schema: Option<borsh::schema::BorshSchemaContainer>,
+        stream: bool,
+        index: Option<Vec<u8>>,
+    ) -> Result<serde_json::Value, String> {
+        at_modified(selection, input, None, schema, stream, index).0
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!("3".parse(), Ok(Selection::Element(3)));
+        assert_eq!("..3".parse(), Ok(Selection::Range(0, Some(3))));
+        assert_eq!("2..".parse(), Ok(Selection::Range(2, None)));
+        assert!("a..3".parse::<Selection>().is_err());
+
+        let value = (0..5)
+            .map(|id| Entry {
+                id,
+                name: "x".repeat(id as usize),
+            })
+            .collect::<Vec<_>>();
+        let input = borsh::try_to_vec_with_schema(&value).unwrap();
+        let (_, index) = index::index(&input, None, None, false).unwrap();
+        let index = index.try_to_vec().unwrap();
+
+        assert_eq!(
+            at("3", &input, None, false, Some(index.clone())),
+            Ok(json!({ "id": 3, "name": "xxx" })),
+        );
+        assert_eq!(
+            at("1..3", &input, None, false, None),
+            Ok(json!([{ "id": 1, "name": "x" }, { "id": 2, "name": "xx" }])),
+        );
+        assert_eq!(at("5..", &input, None, false, None), Ok(json!([])));
+        for selection in ["5", "3..6", "6.."] {
+            assert!(at(selection, &input, None, false, None).is_err());
+        }
+        assert_eq!(
+            at("3..2", &input, None, false, None),
+            Err("Invalid index: range 3..2 is backwards".to_string()),
+        );
+
+        // Sorting rewrites the data to the same length
+        let mut sorted = value;
+        sorted.reverse();
+        let sorted = borsh::try_to_vec_with_schema(&sorted).unwrap();
+        assert_eq!(sorted.len(), input.len());
+        assert!(at("3", &sorted, None, false, Some(index.clone())).is_err());
+
+        // The index only holds for the layout it was built with
+        assert_eq!(
+            at("0", &input, None, true, Some(index)),
+            Err(
+                "Invalid index: the input was indexed with a different schema or --stream mode"
+                    .to_string()
+            ),
+        );
+
+        // Fixed-size records
+        let input = [1u64, 2, 3].map(u64::to_le_bytes).concat();
+        assert_eq!(
+            at("1..", &input, Some(u64::schema_container()), true, None),
+            Ok(json!([2, 3])),
+        );
+    }
+
+    #[test]
+    fn reads_only_selection() {
+        let value = (0..1000)
+            .map(|id| Entry {
+                id: (id % 256) as u8,
+                name: "x".repeat(id % 10),
+            })
+            .collect::<Vec<_>>();
+        let input = borsh::try_to_vec_with_schema(&value).unwrap();
+        let header = input.len() - value.try_to_vec().unwrap().len();
+        let (_, index) = index::index(&input, Some(1), None, false).unwrap();
+        let index = index.try_to_vec().unwrap();
+
+        let (element, read) = at_modified("999", &input, Some(1), None, false, Some(index));
+        assert_eq!(element, Ok(json!({ "id": 231, "name": "xxxxxxxxx" })));
+        // The header, found in the first chunk, and the element
+        assert!(read <= 4096 + 14, "read {read} of {} bytes", input.len());
+        assert!(header < 4096);
+
+        let input = (0..10_000u64)
+            .flat_map(u64::to_le_bytes)
+            .collect::<Vec<_>>();
+        let schema = u64::schema_container();
+        // Checking for a header, and the element
+        let expected = schema.try_to_vec().unwrap().len() + 8;
+        let (element, read) = at_modified("9999", &input, None, Some(schema), true, None);
+        assert_eq!(element, Ok(json!(9999)));
+        assert_eq!(read, expected);
+    }
+}
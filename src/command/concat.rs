@@ -0,0 +1,224 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::{records::Records, schema_fingerprint::fingerprint, schema_util::clone_schema};
+
+use super::{
+    get_input_bytes, get_input_schema, has_other_header, output_bytes, output_writer,
+    split_schema_header, Execute, IOError,
+};
+
+#[derive(Args, Debug)]
+/// Concatenate top-level sequences or record streams with the same element
+/// schema.
+///
+/// The output keeps the schema header of the first input, if any, and its
+/// length prefix is the total number of elements. Elements are checked
+/// against the schema and copied unchanged, without decoding them if they
+/// have a fixed size.
+pub struct ConcatArgs {
+    /// Files to concatenate, in order.
+    #[arg(required = true)]
+    pub input_paths: Vec<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Schema of the inputs. Defaults to the schema embedded in each input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the inputs as streams of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+}
+
+pub struct Concat<'a> {
+    /// Names and contents of the inputs.
+    pub inputs: Vec<(String, Vec<u8>)>,
+    pub output: Box<dyn Write + 'a>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+}
+
+impl TryFrom<&'_ ConcatArgs> for Concat<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        ConcatArgs {
+            input_paths,
+            output,
+            schema,
+            stream,
+        }: &'_ ConcatArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            inputs: input_paths
+                .iter()
+                .map(|path| Ok((path.display().to_string(), get_input_bytes(Some(path))?)))
+                .collect::<Result<_, IOError>>()?,
+            output: output_writer(output.as_ref())?,
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+        })
+    }
+}
+
+impl Execute for Concat<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let mut header: &[u8] = &[];
+        let mut first: Option<(&str, String)> = None;
+        let mut length = 0u32;
+        let mut bodies = vec![];
+
+        for (name, input) in &self.inputs {
+            let (schema, has_header, data) =
+                split_schema_header(input, self.schema.as_ref().map(clone_schema))?;
+            if !has_header && has_other_header(input) {
+                return Err(IOError::Concat(format!(
+                    "{name} has an embedded schema header that differs from --schema"
+                )));
+            }
+            let records = Records::new(&schema, self.stream).map_err(IOError::NotASequence)?;
+
+            let elements = fingerprint(&records.schema, false);
+            match &first {
+                None => {
+                    header = &input[..input.len() - data.len()];
+                    first = Some((name, elements));
+                }
+                Some((first_name, first_elements)) if *first_elements != elements => {
+                    return Err(IOError::Concat(format!(
+                        "the elements of {name} ({}) have a different schema from those of {first_name}",
+                        records.schema.declaration,
+                    )));
+                }
+                Some(_) => {}
+            }
+
+            // Elements of a fixed size are only counted, not decoded
+            records
+                .index(data, 0)
+                .map_err(|e| IOError::Concat(format!("{name} does not match the schema: {e}")))?;
+            let (prefix, body) = records
+                .split_prefix(data)
+                .map_err(|_| IOError::DeserializeBorsh("sequence length"))?;
+            if let Some(prefix) = prefix {
+                length = length
+                    .checked_add(prefix)
+                    .ok_or_else(|| IOError::Concat("more than u32::MAX elements".to_string()))?;
+            }
+            bodies.push(body);
+        }
+
+        output_bytes(&mut self.output, header)?;
+        if !self.stream {
+            output_bytes(&mut self.output, &length.to_le_bytes())?;
+        }
+        for body in bodies {
+            output_bytes(&mut self.output, body)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{schema::BorshSchemaContainer, BorshSchema, BorshSerialize};
+
+    use crate::command::Execute;
+
+    use super::Concat;
+
+    #[derive(BorshSerialize, BorshSchema, Clone)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    fn concat(
+        inputs: Vec<Vec<u8>>,
+        schema: Option<BorshSchemaContainer>,
+    ) -> Result<Vec<u8>, String> {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Concat {
+            inputs: inputs
+                .into_iter()
+                .enumerate()
+                .map(|(i, input)| (i.to_string(), input))
+                .collect(),
+            output: Box::new(writer),
+            schema,
+            stream: false,
+        };
+
+        let result = p.execute();
+        drop(p);
+
+        result.map(|_| output_vector).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test() {
+        let point = |x| Point { x, y: -x };
+        let a = vec![point(1), point(2)];
+        let b = vec![point(3)];
+
+        assert_eq!(
+            concat(
+                vec![
+                    borsh::try_to_vec_with_schema(&a).unwrap(),
+                    borsh::try_to_vec_with_schema(&b).unwrap(),
+                ],
+                None,
+            ),
+            Ok(borsh::try_to_vec_with_schema(&[a.clone(), b.clone()].concat()).unwrap()),
+        );
+
+        assert!(concat(
+            vec![
+                borsh::try_to_vec_with_schema(&vec![point(1)]).unwrap(),
+                borsh::try_to_vec_with_schema(&vec![1u64]).unwrap(),
+            ],
+            None,
+        )
+        .is_err());
+
+        // With --schema, a different embedded header is not taken for data
+        let schema = Vec::<Point>::schema_container;
+        assert_eq!(
+            concat(
+                vec![a.try_to_vec().unwrap(), b.try_to_vec().unwrap()],
+                Some(schema()),
+            ),
+            Ok([a.clone(), b].concat().try_to_vec().unwrap()),
+        );
+        assert!(concat(
+            vec![
+                a.try_to_vec().unwrap(),
+                borsh::try_to_vec_with_schema(&vec![1u64]).unwrap(),
+            ],
+            Some(schema()),
+        )
+        .is_err());
+
+        // Every input must hold exactly as many elements as its prefix says
+        let mut trailing = a.try_to_vec().unwrap();
+        trailing.push(0);
+        let mut truncated = a.try_to_vec().unwrap();
+        truncated.pop();
+        for input in [trailing, truncated] {
+            assert!(concat(vec![a.try_to_vec().unwrap(), input], Some(schema())).is_err());
+        }
+    }
+}
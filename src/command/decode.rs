@@ -3,7 +3,7 @@ use std::{io::Write, path::PathBuf};
 use borsh::{schema::BorshSchemaContainer, BorshDeserialize};
 use clap::Args;
 
-use super::{get_input_bytes, output_json, output_writer, Execute, IOError};
+use super::{get_input_bytes, output_json, output_writer, validated, Execute, IOError};
 
 #[derive(Args, Debug)]
 /// Decode Borsh input to JSON.
@@ -50,7 +50,8 @@ impl Execute for Decode<'_> {
         let mut buf = &self.input as &[u8];
 
         let schema = <BorshSchemaContainer as BorshDeserialize>::deserialize(&mut buf)
-            .map_err(|_| IOError::DeserializeBorsh("schema"))?;
+            .map_err(|_| IOError::DeserializeBorsh("schema"))
+            .and_then(validated)?;
 
         let value = crate::dynamic_schema::deserialize_from_schema(&mut buf, &schema)
             .map_err(|_| IOError::DeserializeBorsh("data according to embedded schema"))?;
@@ -1,11 +1,11 @@
 use std::{io::Write, path::PathBuf};
 
-use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSerialize};
+use borsh::{schema::BorshSchemaContainer, BorshSerialize};
 use clap::Args;
 
 use crate::{dynamic_schema::serialize_with_schema, json_borsh::JsonSerializableAsBorsh};
 
-use super::{get_input_bytes, output_borsh, output_writer, Execute, IOError};
+use super::{get_input_bytes, get_input_schema, output_borsh, output_writer, Execute, IOError};
 
 #[derive(Args, Debug)]
 /// Convert JSON to Borsh.
@@ -38,17 +38,11 @@ impl TryFrom<&'_ EncodeArgs> for Encode<'_> {
             input: serde_json::from_slice(&get_input_bytes(args.input_path.as_ref())?)
                 .map_err(|_e| IOError::DeserializeJson)?,
             output: output_writer(args.output_path.as_ref())?,
-            schema: if let Some(ref path) = args.schema {
-                let schema_bytes = get_input_bytes(Some(path))?;
-                let schema = <BorshSchemaContainer as BorshDeserialize>::deserialize(
-                    &mut (&schema_bytes as &[u8]),
-                )
-                .map_err(|_| IOError::DeserializeBorsh("schema header"))?;
-
-                Some(schema)
-            } else {
-                None
-            },
+            schema: args
+                .schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
         })
     }
 }
@@ -61,7 +55,7 @@ impl Execute for Encode<'_> {
             serialize_with_schema(writer, &self.input, schema).map_err(|_| IOError::WriteBorsh)?;
             Ok(())
         } else {
-            output_borsh(writer, &JsonSerializableAsBorsh(&self.input))
+            output_borsh(writer, JsonSerializableAsBorsh(&self.input))
         }
     }
 }
@@ -70,8 +64,9 @@ impl Execute for Encode<'_> {
 mod tests {
     use std::io::BufWriter;
 
-    use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+    use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSchema, BorshSerialize};
     use serde::{Deserialize, Serialize};
+    use serde_json::json;
 
     use crate::command::Execute;
 
@@ -165,4 +160,87 @@ mod tests {
             expected,
         );
     }
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Memo {
+        memo: Option<String>,
+    }
+
+    #[allow(non_snake_case)]
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Wrapper {
+        Some: u8,
+    }
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Wrapped {
+        wrapper: Option<Wrapper>,
+    }
+
+    fn encode(input: serde_json::Value, schema: BorshSchemaContainer) -> Vec<u8> {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Encode {
+            input,
+            output: Box::new(writer),
+            schema: Some(schema),
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        output_vector
+    }
+
+    #[test]
+    fn options() {
+        let memo = |memo: Option<&str>| {
+            borsh::try_to_vec_with_schema(&Memo {
+                memo: memo.map(str::to_string),
+            })
+            .unwrap()
+        };
+        let schema = Memo::schema_container;
+
+        assert_eq!(
+            encode(json!({ "memo": "None" }), schema()),
+            memo(Some("None"))
+        );
+        assert_eq!(
+            encode(json!({ "memo": "Some" }), schema()),
+            memo(Some("Some"))
+        );
+        assert_eq!(encode(json!({ "memo": null }), schema()), memo(None));
+        assert_eq!(encode(json!({}), schema()), memo(None));
+        assert_eq!(
+            encode(json!({ "memo": { "None": null } }), schema()),
+            memo(None)
+        );
+        assert_eq!(
+            encode(json!({ "memo": { "Some": "x" } }), schema()),
+            memo(Some("x"))
+        );
+
+        let wrapped = |wrapper: Option<u8>| {
+            borsh::try_to_vec_with_schema(&Wrapped {
+                wrapper: wrapper.map(|value| Wrapper { Some: value }),
+            })
+            .unwrap()
+        };
+        let schema = Wrapped::schema_container;
+
+        assert_eq!(
+            encode(json!({ "wrapper": { "Some": 1 } }), schema()),
+            wrapped(Some(1)),
+        );
+        assert_eq!(
+            encode(json!({ "wrapper": { "Some": { "Some": 1 } } }), schema()),
+            wrapped(Some(1)),
+        );
+        assert_eq!(
+            encode(json!({ "wrapper": { "None": null } }), schema()),
+            wrapped(None),
+        );
+    }
 }
@@ -0,0 +1,174 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::query::Predicate;
+
+use super::{
+    get_input_bytes, get_input_schema, output_bytes, output_writer, rewrite_elements, Execute,
+    IOError,
+};
+
+#[derive(Args, Debug)]
+/// Keep the elements of a top-level sequence or record stream that match a
+/// predicate.
+///
+/// Predicates compare field paths of an element with JSON literals, like
+/// `.balance > 1000` or `.kind == "Transfer"`, and can be joined with `&&`
+/// and `||`. Enums compare equal to the name of their variant. The output
+/// has the same schema, and matching elements are copied unchanged.
+pub struct FilterArgs {
+    /// Predicate that elements must match.
+    pub predicate: String,
+
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+
+    /// Keep the elements that do not match instead.
+    #[arg(short = 'v', long)]
+    pub invert: bool,
+}
+
+pub struct Filter<'a> {
+    pub predicate: String,
+    pub input: Vec<u8>,
+    pub output: Box<dyn Write + 'a>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+    pub invert: bool,
+}
+
+impl TryFrom<&'_ FilterArgs> for Filter<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        FilterArgs {
+            predicate,
+            input_path,
+            output_path,
+            schema,
+            stream,
+            invert,
+        }: &'_ FilterArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            predicate: predicate.clone(),
+            input: get_input_bytes(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+            invert: *invert,
+        })
+    }
+}
+
+impl Execute for Filter<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let output = rewrite_elements(
+            &self.input,
+            self.schema.take(),
+            self.stream,
+            |records, mut elements| {
+                let predicate =
+                    Predicate::parse(&self.predicate, &records.schema).map_err(IOError::Query)?;
+                elements
+                    .retain(|(_, value)| predicate.matches(&records.schema, value) != self.invert);
+                Ok(elements)
+            },
+        )?;
+        output_bytes(&mut self.output, &output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use crate::command::Execute;
+
+    use super::Filter;
+
+    #[derive(BorshSerialize, BorshSchema, Clone)]
+    enum Kind {
+        Transfer,
+        Mint,
+    }
+
+    #[derive(BorshSerialize, BorshSchema, Clone)]
+    struct Event {
+        balance: u128,
+        kind: Kind,
+    }
+
+    fn filter(predicate: &str, input: Vec<u8>, stream: bool) -> Vec<u8> {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Filter {
+            predicate: predicate.to_string(),
+            input,
+            output: Box::new(writer),
+            schema: stream.then(Event::schema_container),
+            stream,
+            invert: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        output_vector
+    }
+
+    #[test]
+    fn test() {
+        let events = vec![
+            Event {
+                balance: 500,
+                kind: Kind::Transfer,
+            },
+            Event {
+                balance: 5000,
+                kind: Kind::Transfer,
+            },
+            Event {
+                balance: 5000,
+                kind: Kind::Mint,
+            },
+        ];
+
+        assert_eq!(
+            filter(
+                r#".balance > 1000 && .kind == "Transfer""#,
+                borsh::try_to_vec_with_schema(&events).unwrap(),
+                false,
+            ),
+            borsh::try_to_vec_with_schema(&vec![events[1].clone()]).unwrap(),
+        );
+
+        let stream = events
+            .iter()
+            .flat_map(|e| e.try_to_vec().unwrap())
+            .collect();
+        assert_eq!(
+            filter(r#".kind == "Mint""#, stream, true),
+            events[2].try_to_vec().unwrap(),
+        );
+    }
+}
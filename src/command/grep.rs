@@ -0,0 +1,250 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::{Args, ValueEnum};
+
+use crate::{
+    dynamic_schema::walk_from_schema,
+    grep::{Grep as Matches, Match, Pattern},
+};
+
+use super::{
+    get_input_bytes, get_input_schema, output_json, output_writer, split_schema_header, Execute,
+    IOError,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    /// Strings, matched by regular expression.
+    String,
+    /// Integers and floats, matched by value or range like `10..20`.
+    Number,
+    /// `Vec<u8>` and `[u8; N]`, matched by hex bytes they contain.
+    Bytes,
+}
+
+#[derive(Args, Debug)]
+/// Search decoded Borsh data for field values.
+///
+/// The pattern is a regular expression for strings, a number or range like
+/// `10..20`, `10..=20`, `10..` or `..20` for numbers, and hex like
+/// `0xdeadbeef` for byte arrays. Every matching value is printed with its
+/// byte offset in the input and its field path, like `.[3].owner` for a field
+/// of the fourth element.
+pub struct GrepArgs {
+    /// Pattern to search for.
+    pub pattern: String,
+
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Only search values of this kind. Repeat to search several kinds.
+    #[arg(short, long = "type")]
+    pub r#type: Vec<Kind>,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+
+    /// Output JSON instead of text.
+    #[arg(short, long)]
+    pub json: bool,
+
+    /// Format JSON output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+pub struct Grep<'a> {
+    pub pattern: String,
+    pub kinds: Vec<Kind>,
+    pub input: Vec<u8>,
+    pub output: Box<dyn Write + 'a>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+    pub json: bool,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ GrepArgs> for Grep<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        GrepArgs {
+            pattern,
+            input_path,
+            output_path,
+            r#type,
+            schema,
+            stream,
+            json,
+            pretty,
+        }: &'_ GrepArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pattern: pattern.clone(),
+            kinds: r#type.clone(),
+            input: get_input_bytes(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+            json: *json,
+            pretty: *pretty,
+        })
+    }
+}
+
+impl Grep<'_> {
+    /// Every match, with offsets from the start of the input and, for
+    /// streams, paths starting with the record number.
+    fn matches(&mut self) -> Result<Vec<Match>, IOError> {
+        let kind = |kind| self.kinds.is_empty() || self.kinds.contains(&kind);
+        let pattern = Pattern::new(
+            &self.pattern,
+            kind(Kind::String),
+            kind(Kind::Number),
+            kind(Kind::Bytes),
+        )
+        .map_err(IOError::Grep)?;
+
+        let (schema, _, data) = split_schema_header(&self.input, self.schema.take())?;
+        let header = self.input.len() - data.len();
+
+        let mut matches = vec![];
+        let mut buf = data;
+        let mut record = 0;
+        loop {
+            if self.stream && buf.is_empty() {
+                break;
+            }
+            let base = header + data.len() - buf.len();
+            let mut visitor = Matches::new(&schema, &pattern);
+            walk_from_schema(&mut buf, &schema, &mut visitor)
+                .map_err(|_| IOError::DeserializeBorsh("data according to schema"))?;
+            matches.extend(visitor.matches.into_iter().map(|mut m| {
+                m.offset += base;
+                if self.stream {
+                    m.path = match m.path.strip_prefix('.') {
+                        Some("") => format!("[{record}]"),
+                        Some(rest) if rest.starts_with('[') => format!("[{record}]{rest}"),
+                        _ => format!("[{record}]{}", m.path),
+                    };
+                }
+                m
+            }));
+            record += 1;
+            if !self.stream {
+                break;
+            }
+        }
+        Ok(matches)
+    }
+}
+
+impl Execute for Grep<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let matches = self.matches()?;
+        if self.json {
+            return output_json(&mut self.output, &matches, self.pretty);
+        }
+        for Match {
+            path,
+            offset,
+            value,
+            ..
+        } in &matches
+        {
+            writeln!(self.output, "{offset}:{path}: {value}").map_err(|_| IOError::WriteBytes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use crate::command::Execute;
+
+    use super::{Grep, Kind};
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Account {
+        id: String,
+        key: Vec<u8>,
+        nonce: u64,
+    }
+
+    fn grep(pattern: &str, kinds: Vec<Kind>, input: Vec<u8>, stream: bool) -> String {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Grep {
+            pattern: pattern.to_string(),
+            kinds,
+            input,
+            output: Box::new(writer),
+            schema: stream.then(Account::schema_container),
+            stream,
+            json: false,
+            pretty: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        String::from_utf8(output_vector).unwrap()
+    }
+
+    #[test]
+    fn test() {
+        let accounts = vec![
+            Account {
+                id: "alice.near".to_string(),
+                key: vec![0xab, 0xcd, 0x12],
+                nonce: 7,
+            },
+            Account {
+                id: "bob.near".to_string(),
+                key: vec![0x12, 0x34],
+                nonce: 12,
+            },
+        ];
+
+        let stream: Vec<u8> = accounts
+            .iter()
+            .flat_map(|a| a.try_to_vec().unwrap())
+            .collect();
+        assert_eq!(
+            grep("^bob", vec![], stream.clone(), true),
+            "29:[1].id: \"bob.near\"\n",
+        );
+        assert_eq!(
+            grep("0x12", vec![Kind::Bytes], stream.clone(), true),
+            "14:[0].key: 0xabcd12\n41:[1].key: 0x1234\n",
+        );
+        assert_eq!(
+            grep("5..", vec![Kind::Number], stream, true),
+            "21:[0].nonce: 7\n47:[1].nonce: 12\n",
+        );
+
+        let data = borsh::try_to_vec_with_schema(&accounts).unwrap();
+        let header = data.len() - accounts.try_to_vec().unwrap().len();
+        assert_eq!(
+            grep("12", vec![Kind::Number], data, false),
+            format!("{}:.[1].nonce: 12\n", header + 51),
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::records::{checksum, IndexFile, Records};
+
+use super::{
+    get_input_bytes, get_input_schema, output_borsh, output_writer, split_schema_header, Execute,
+    IOError,
+};
+
+#[derive(Args, Debug)]
+/// Build an index of the elements of a top-level sequence or record stream.
+///
+/// The index records where every element starts, so that `borsh at` can
+/// decode single elements without decoding everything before them. It is
+/// written next to the input as `<INPUT_PATH>.index` by default. Elements of
+/// a fixed size are located without decoding them.
+pub struct IndexArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write the index to this file, otherwise next to the input file, or
+    /// to stdout when reading from stdin.
+    pub output_path: Option<PathBuf>,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+}
+
+/// Modification time of a file, in nanoseconds since the Unix epoch.
+pub(super) fn modified(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    u64::try_from(nanos).ok()
+}
+
+/// Default location of the index of a file.
+pub(super) fn sidecar_path(input_path: &Path) -> PathBuf {
+    let mut path = input_path.as_os_str().to_owned();
+    path.push(".index");
+    path.into()
+}
+
+pub struct Index<'a> {
+    pub input: Vec<u8>,
+    /// Modification time of the input file, see [`modified`].
+    pub modified: Option<u64>,
+    pub output: Box<dyn Write + 'a>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+}
+
+impl TryFrom<&'_ IndexArgs> for Index<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        IndexArgs {
+            input_path,
+            output_path,
+            schema,
+            stream,
+        }: &'_ IndexArgs,
+    ) -> Result<Self, Self::Error> {
+        let output_path = output_path
+            .clone()
+            .or_else(|| input_path.as_deref().map(sidecar_path));
+        // Before reading, so that a change while reading makes the index stale
+        let modified = input_path.as_deref().and_then(modified);
+        Ok(Self {
+            input: get_input_bytes(input_path.as_ref())?,
+            modified,
+            output: output_writer(output_path.as_ref())?,
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+        })
+    }
+}
+
+/// Indexes input, with offsets from the start of the input.
+pub(super) fn index(
+    input: &[u8],
+    modified: Option<u64>,
+    schema: Option<BorshSchemaContainer>,
+    stream: bool,
+) -> Result<(Records, IndexFile), IOError> {
+    let (schema, _, data) = split_schema_header(input, schema)?;
+    let records = Records::new(&schema, stream).map_err(IOError::NotASequence)?;
+    let offsets = records
+        .index(data, (input.len() - data.len()) as u64)
+        .map_err(|_| IOError::DeserializeBorsh("data according to schema"))?;
+    let index = IndexFile {
+        data_len: input.len() as u64,
+        modified,
+        checksum: checksum(input).map_err(|_| IOError::WriteBorsh)?,
+        fingerprint: records.fingerprint(),
+        prefixed: records.prefixed,
+        offsets,
+    };
+    Ok((records, index))
+}
+
+impl Execute for Index<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let (_, index) = index(&self.input, self.modified, self.schema.take(), self.stream)?;
+        output_borsh(&mut self.output, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+    use crate::{
+        command::Execute,
+        records::{checksum, IndexFile, OffsetIndex, Records},
+    };
+
+    use super::Index;
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Entry {
+        id: u8,
+        name: String,
+    }
+
+    #[test]
+    fn test() {
+        let value = vec![
+            Entry {
+                id: 1,
+                name: "a".to_string(),
+            },
+            Entry {
+                id: 2,
+                name: "bc".to_string(),
+            },
+        ];
+        let input = borsh::try_to_vec_with_schema(&value).unwrap();
+        let header = Vec::<Entry>::schema_container().try_to_vec().unwrap().len() as u64;
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Index {
+            input: input.clone(),
+            modified: None,
+            output: Box::new(writer),
+            schema: None,
+            stream: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            IndexFile::try_from_slice(&output_vector).unwrap(),
+            IndexFile {
+                data_len: input.len() as u64,
+                modified: None,
+                checksum: checksum(&input[..]).unwrap(),
+                fingerprint: Records::new(&Vec::<Entry>::schema_container(), false)
+                    .unwrap()
+                    .fingerprint(),
+                prefixed: true,
+                offsets: OffsetIndex::Offsets(vec![header + 4, header + 10, header + 17]),
+            },
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::{schema::BorshSchemaContainer, BorshSerialize};
+use clap::Args;
+
+use crate::{
+    dynamic_schema::{deserialize_from_schema, serialize_with_schema},
+    migration::{Mapping, Migration},
+};
+
+use super::{
+    get_input_bytes, get_input_schema, output_bytes, output_writer, split_schema_header, Execute,
+    IOError,
+};
+
+#[derive(Args, Debug)]
+/// Convert Borsh data from one schema version to another.
+///
+/// Values are decoded with the old schema, fields and variants are matched
+/// by name (following the mapping rules), and the result is encoded with the
+/// new schema. If the input has an embedded schema header, the output gets
+/// the new schema as its header.
+///
+/// The mapping is a JSON object keyed by struct or enum name, e.g.
+/// `{"Account": {"rename": {"balance": "amount"}, "default": {"version": 2},
+/// "drop": ["legacy"]}, "Status": {"variants": {"Frozen": "Suspended"}}}`.
+/// New fields without a default are only allowed if they are Options.
+pub struct MigrateArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Old schema. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub from: Option<PathBuf>,
+
+    /// New schema.
+    #[arg(short, long)]
+    pub to: PathBuf,
+
+    /// JSON file of renames, defaults, dropped fields and variant remappings.
+    #[arg(short, long)]
+    pub mapping: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+}
+
+pub struct Migrate<'a> {
+    pub input: Vec<u8>,
+    pub output: Box<dyn Write + 'a>,
+    pub from: Option<BorshSchemaContainer>,
+    pub to: BorshSchemaContainer,
+    pub mapping: Mapping,
+    pub stream: bool,
+}
+
+impl TryFrom<&'_ MigrateArgs> for Migrate<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        MigrateArgs {
+            input_path,
+            output_path,
+            from,
+            to,
+            mapping,
+            stream,
+        }: &'_ MigrateArgs,
+    ) -> Result<Self, Self::Error> {
+        let mapping = match mapping {
+            Some(path) => serde_json::from_slice(&get_input_bytes(Some(path))?)
+                .map_err(|e| IOError::Migrate(format!("Invalid mapping: {e}")))?,
+            None => Mapping::new(),
+        };
+
+        Ok(Self {
+            input: get_input_bytes(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            from: from
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            to: get_input_schema(Some(to))?,
+            mapping,
+            stream: *stream,
+        })
+    }
+}
+
+impl Execute for Migrate<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let (from, has_header, mut buf) = split_schema_header(&self.input, self.from.take())?;
+
+        let migration = Migration::new(&from, &self.to, &self.mapping)
+            .map_err(|e| IOError::Migrate(e.to_string()))?;
+
+        let mut output = vec![];
+        if has_header {
+            BorshSerialize::serialize(&self.to, &mut output).map_err(|_| IOError::WriteBorsh)?;
+        }
+
+        let mut record = 0;
+        loop {
+            if self.stream && buf.is_empty() {
+                break;
+            }
+            let context = |e: String| {
+                if self.stream {
+                    IOError::Migrate(format!("Record {record}: {e}"))
+                } else {
+                    IOError::Migrate(e)
+                }
+            };
+
+            let value = deserialize_from_schema(&mut buf, &from)
+                .map_err(|_| IOError::DeserializeBorsh("data according to old schema"))?;
+            let migrated = migration
+                .migrate(&value)
+                .map_err(|e| context(e.to_string()))?;
+            serialize_with_schema(&mut output, &migrated, &self.to)
+                .map_err(|e| context(e.to_string()))?;
+
+            record += 1;
+            if !self.stream {
+                break;
+            }
+        }
+        if !buf.is_empty() {
+            return Err(IOError::Migrate(
+                "trailing bytes after the value, use --stream for a stream of records".to_string(),
+            ));
+        }
+
+        output_bytes(&mut self.output, &output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+    use crate::{
+        command::{Execute, IOError},
+        migration::Mapping,
+    };
+
+    use super::Migrate;
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Old {
+        id: u32,
+    }
+
+    #[derive(BorshDeserialize, BorshSchema, Debug, PartialEq)]
+    struct New {
+        id: u64,
+        memo: Option<String>,
+    }
+
+    fn migrate(input: Vec<u8>, stream: bool) -> Result<Vec<u8>, IOError> {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Migrate {
+            input,
+            output: Box::new(writer),
+            from: Some(Old::schema_container()),
+            to: New::schema_container(),
+            mapping: Mapping::new(),
+            stream,
+        };
+
+        p.execute()?;
+        drop(p);
+
+        Ok(output_vector)
+    }
+
+    #[test]
+    fn test() {
+        let mut input = vec![];
+        for id in [1, 2, 3] {
+            Old { id }.serialize(&mut input).unwrap();
+        }
+
+        // Without --stream, records after the first are not dropped
+        assert!(matches!(
+            migrate(input.clone(), false),
+            Err(IOError::Migrate(_))
+        ));
+
+        let output_vector = migrate(input, true).unwrap();
+
+        let mut buf = &output_vector as &[u8];
+        for id in [1, 2, 3] {
+            assert_eq!(New::deserialize(&mut buf).unwrap(), New { id, memo: None });
+        }
+        assert!(buf.is_empty());
+    }
+}
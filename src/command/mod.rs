@@ -4,20 +4,36 @@ use std::{
     path::PathBuf,
 };
 
-use borsh::BorshSerialize;
+use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSerialize};
 use clap::Subcommand;
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::{records::Records, schema_json, schema_validate};
+
 use self::{
-    decode::Decode, encode::Encode, extract::Extract, pack::Pack, strip::Strip, unpack::Unpack,
+    append::Append, at::At, concat::Concat, decode::Decode, encode::Encode, extract::Extract,
+    filter::Filter, grep::Grep, index::Index, migrate::Migrate, pack::Pack, sort::Sort,
+    split::Split, stats::Stats, strip::Strip, uniq::Uniq, unpack::Unpack,
 };
 
+mod append;
+mod at;
+mod concat;
 mod decode;
 mod encode;
 mod extract;
+mod filter;
+mod grep;
+mod index;
+mod migrate;
 mod pack;
+mod schema;
+mod sort;
+mod split;
+mod stats;
 mod strip;
+mod uniq;
 mod unpack;
 
 trait Execute {
@@ -32,15 +48,27 @@ pub enum Command {
     Decode(decode::DecodeArgs),
     Extract(extract::ExtractArgs),
     Strip(strip::StripArgs),
+    Schema(schema::SchemaArgs),
+    Migrate(migrate::MigrateArgs),
+    Stats(stats::StatsArgs),
+    Index(index::IndexArgs),
+    At(at::AtArgs),
+    Append(append::AppendArgs),
+    Filter(filter::FilterArgs),
+    Sort(sort::SortArgs),
+    Uniq(uniq::UniqArgs),
+    Concat(concat::ConcatArgs),
+    Split(split::SplitArgs),
+    Grep(grep::GrepArgs),
+}
+
+#[inline]
+fn run_args<E: Execute>(args: impl TryInto<E, Error = IOError>) -> Result<(), IOError> {
+    E::execute(&mut args.try_into()?)
 }
 
 impl Command {
     pub fn run(&self) {
-        #[inline]
-        fn run_args<E: Execute>(args: impl TryInto<E, Error = IOError>) -> Result<(), IOError> {
-            E::execute(&mut args.try_into()?)
-        }
-
         if let Err(e) = match self {
             Command::Pack(args) => run_args::<Pack>(args),
             Command::Unpack(args) => run_args::<Unpack>(args),
@@ -48,8 +76,21 @@ impl Command {
             Command::Decode(args) => run_args::<Decode>(args),
             Command::Extract(args) => run_args::<Extract>(args),
             Command::Strip(args) => run_args::<Strip>(args),
+            Command::Schema(args) => args.command.run(),
+            Command::Migrate(args) => run_args::<Migrate>(args),
+            Command::Stats(args) => run_args::<Stats>(args),
+            Command::Index(args) => run_args::<Index>(args),
+            Command::At(args) => run_args::<At>(args),
+            Command::Append(args) => run_args::<Append>(args),
+            Command::Filter(args) => run_args::<Filter>(args),
+            Command::Sort(args) => run_args::<Sort>(args),
+            Command::Uniq(args) => run_args::<Uniq>(args),
+            Command::Concat(args) => run_args::<Concat>(args),
+            Command::Split(args) => run_args::<Split>(args),
+            Command::Grep(args) => run_args::<Grep>(args),
         } {
             eprintln!("Error: {e}");
+            std::process::exit(1);
         }
     }
 }
@@ -74,6 +115,38 @@ pub enum IOError {
     DeserializeJson,
     #[error("Unexpected schema header: {0}")]
     IncorrectBorshSchemaHeader(String),
+    #[error("Failed to convert schema: {0}")]
+    ConvertSchema(String),
+    #[error("Failed to compile schema: {0}")]
+    CompileSchema(String),
+    #[error("Failed to extract schema from Rust source: {0}")]
+    ExtractSchema(String),
+    #[error("Failed to infer schema: {0}")]
+    InferSchema(String),
+    #[error("Found {0} breaking change(s)")]
+    IncompatibleSchema(usize),
+    #[error("Invalid schema: {0}")]
+    InvalidSchema(String),
+    #[error("Failed to migrate: {0}")]
+    Migrate(String),
+    #[error("Found {0} denied lint(s)")]
+    Lint(usize),
+    #[error("Expected the root type {0} to be a sequence (use --stream for record streams)")]
+    NotASequence(String),
+    #[error("Invalid index: {0}")]
+    InvalidIndex(String),
+    #[error("Element {0} is out of range for {1} element(s)")]
+    OutOfRange(u64, u64),
+    #[error("Failed to append: {0}")]
+    Append(String),
+    #[error("Invalid query: {0}")]
+    Query(String),
+    #[error("Failed to concatenate: {0}")]
+    Concat(String),
+    #[error("Failed to split: {0}")]
+    Split(String),
+    #[error("Invalid pattern: {0}")]
+    Grep(String),
 }
 
 fn get_input_bytes(input_path: Option<&PathBuf>) -> Result<Vec<u8>, IOError> {
@@ -90,6 +163,89 @@ fn read_stdin() -> Result<Vec<u8>, IOError> {
     Ok(v)
 }
 
+/// Reads a schema container from a standalone schema file, from the header
+/// of a file containing data with an embedded schema, or from the JSON
+/// representation of a schema. The schema is validated.
+fn get_input_schema(input_path: Option<&PathBuf>) -> Result<BorshSchemaContainer, IOError> {
+    validated(read_input_schema(input_path)?)
+}
+
+/// Like [`get_input_schema`], but without validation.
+fn read_input_schema(input_path: Option<&PathBuf>) -> Result<BorshSchemaContainer, IOError> {
+    let bytes = get_input_bytes(input_path)?;
+    if schema_json::is_json(&bytes) {
+        if let Ok(schema) = serde_json::from_slice::<schema_json::SchemaJson>(&bytes) {
+            return Ok(schema.into());
+        }
+    }
+    <BorshSchemaContainer as BorshDeserialize>::deserialize(&mut (&bytes as &[u8]))
+        .map_err(|_| IOError::DeserializeBorsh("schema header"))
+}
+
+fn validated(schema: BorshSchemaContainer) -> Result<BorshSchemaContainer, IOError> {
+    match schema_validate::errors(&schema) {
+        Some(errors) => Err(IOError::InvalidSchema(errors)),
+        None => Ok(schema),
+    }
+}
+
+/// Splits input into its schema and the encoded data. Without `schema`, the
+/// input must start with an embedded schema header. With it, a header equal
+/// to `schema` is skipped. Also returns whether the input had a header.
+fn split_schema_header(
+    input: &[u8],
+    schema: Option<BorshSchemaContainer>,
+) -> Result<(BorshSchemaContainer, bool, &[u8]), IOError> {
+    let mut buf = input;
+    match schema {
+        Some(schema) => {
+            let header = schema.try_to_vec().map_err(|_| IOError::WriteBorsh)?;
+            let has_header = buf.starts_with(&header);
+            if has_header {
+                buf = &buf[header.len()..];
+            }
+            Ok((schema, has_header, buf))
+        }
+        None => {
+            let schema = <BorshSchemaContainer as BorshDeserialize>::deserialize(&mut buf)
+                .map_err(|_| IOError::DeserializeBorsh("schema"))
+                .and_then(validated)?;
+            Ok((schema, true, buf))
+        }
+    }
+}
+
+/// Whether input without the header of a given schema starts with the header
+/// of another, valid schema instead.
+fn has_other_header(input: &[u8]) -> bool {
+    <BorshSchemaContainer as BorshDeserialize>::deserialize(&mut &input[..])
+        .is_ok_and(|schema| schema_validate::errors(&schema).is_none())
+}
+
+/// Decoded elements of a top-level sequence or record stream.
+type Elements<'a> = Vec<(&'a [u8], serde_json::Value)>;
+
+/// Replaces the elements of a top-level sequence or record stream with a
+/// selection of them, keeping any schema header and updating the length
+/// prefix. Elements are copied as encoded, not re-encoded.
+fn rewrite_elements<'a>(
+    input: &'a [u8],
+    schema: Option<BorshSchemaContainer>,
+    stream: bool,
+    select: impl FnOnce(&Records, Elements<'a>) -> Result<Elements<'a>, IOError>,
+) -> Result<Vec<u8>, IOError> {
+    let (schema, _, data) = split_schema_header(input, schema)?;
+    let records = Records::new(&schema, stream).map_err(IOError::NotASequence)?;
+    let elements = records
+        .decode(data)
+        .map_err(|_| IOError::DeserializeBorsh("data according to schema"))?;
+    let elements = select(&records, elements)?;
+    let body = records
+        .join(elements.into_iter().map(|(bytes, _)| bytes))
+        .map_err(|_| IOError::WriteBorsh)?;
+    Ok([&input[..input.len() - data.len()], &body].concat())
+}
+
 fn output_writer(output: Option<&PathBuf>) -> Result<Box<dyn Write>, IOError> {
     if let Some(o) = output {
         let f =
@@ -0,0 +1,134 @@
+use std::{fmt::Write as _, io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::schema_validate::{validate, Severity};
+
+use super::super::{output_bytes, output_writer, read_input_schema, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Check a Borsh schema for structural problems.
+///
+/// Errors are references to undefined types, recursion without a base case,
+/// duplicate field or variant names, and enums with more than 256 variants.
+/// Warnings are definitions unreachable from the root and enums without
+/// variants. Schemas with errors are also rejected by every other command.
+pub struct CheckArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Fail on warnings as well as errors.
+    #[arg(short, long)]
+    pub strict: bool,
+}
+
+pub struct Check<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub strict: bool,
+}
+
+impl TryFrom<&'_ CheckArgs> for Check<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        CheckArgs {
+            input_path,
+            output_path,
+            strict,
+        }: &'_ CheckArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: read_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            strict: *strict,
+        })
+    }
+}
+
+impl Execute for Check<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let issues = validate(&self.schema);
+
+        let mut report = String::new();
+        for issue in &issues {
+            writeln!(report, "{issue}").unwrap();
+        }
+        if issues.is_empty() {
+            writeln!(report, "No issues").unwrap();
+        }
+        output_bytes(&mut self.output, report.as_bytes())?;
+
+        let failures = issues
+            .iter()
+            .filter(|i| self.strict || i.severity == Severity::Error)
+            .count();
+        if failures > 0 {
+            return Err(IOError::InvalidSchema(format!("{failures} issue(s) found")));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::schema::{BorshSchemaContainer, Definition};
+
+    use crate::command::Execute;
+
+    use super::Check;
+
+    #[test]
+    fn test() {
+        let schema = || BorshSchemaContainer {
+            declaration: "Vec<u8>".to_string(),
+            definitions: [
+                (
+                    "Vec<u8>".to_string(),
+                    Definition::Sequence {
+                        elements: "u8".to_string(),
+                    },
+                ),
+                (
+                    "Unused".to_string(),
+                    Definition::Sequence {
+                        elements: "u8".to_string(),
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Check {
+            schema: schema(),
+            output: Box::new(writer),
+            strict: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            String::from_utf8(output_vector).unwrap(),
+            "warning: Unused: is not reachable from the root declaration\n",
+        );
+
+        let mut p = Check {
+            schema: schema(),
+            output: Box::new(std::io::sink()),
+            strict: true,
+        };
+        assert!(p.execute().is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::{Args, ValueEnum};
+
+use crate::codegen;
+
+use super::super::{get_input_schema, output_bytes, output_writer, Execute, IOError};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Language {
+    /// Python borsh-construct definitions
+    #[value(alias = "py")]
+    Python,
+    /// Rust structs and enums deriving the Borsh traits
+    #[value(alias = "rs")]
+    Rust,
+    /// TypeScript types and borsh-js schema objects
+    #[value(alias = "ts")]
+    Typescript,
+}
+
+#[derive(Args, Debug)]
+/// Generate type definitions from a Borsh schema.
+pub struct CodegenArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Language to generate.
+    #[arg(short, long, value_enum)]
+    pub lang: Language,
+}
+
+pub struct Codegen<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub lang: Language,
+}
+
+impl TryFrom<&'_ CodegenArgs> for Codegen<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        CodegenArgs {
+            input_path,
+            output_path,
+            lang,
+        }: &'_ CodegenArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            lang: *lang,
+        })
+    }
+}
+
+impl Execute for Codegen<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let source = match self.lang {
+            Language::Python => codegen::python::generate(&self.schema),
+            Language::Rust => codegen::rust::generate(&self.schema),
+            Language::Typescript => codegen::typescript::generate(&self.schema),
+        }
+        .map_err(|e| IOError::ConvertSchema(e.to_string()))?;
+
+        output_bytes(&mut self.output, source.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+
+    use crate::command::Execute;
+
+    use super::{Codegen, Language};
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Parent {
+        integer: u32,
+        vector: Vec<String>,
+    }
+
+    #[test]
+    fn rust() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Codegen {
+            schema: Parent::schema_container(),
+            output: Box::new(writer),
+            lang: Language::Rust,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let source = String::from_utf8(output_vector).unwrap();
+
+        assert!(source.contains("pub struct Parent {"));
+        assert!(source.contains("    pub vector: Vec<String>,"));
+    }
+
+    #[test]
+    fn typescript() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Codegen {
+            schema: Parent::schema_container(),
+            output: Box::new(writer),
+            lang: Language::Typescript,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let source = String::from_utf8(output_vector).unwrap();
+
+        assert!(source.contains("export interface Parent {"));
+        assert!(source.contains("export const ParentSchema: Schema = {"));
+    }
+}
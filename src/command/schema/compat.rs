@@ -0,0 +1,161 @@
+use std::{fmt::Write as _, io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::{Args, ValueEnum};
+
+use crate::schema_compat::{compare, Direction};
+
+use super::super::{get_input_schema, output_bytes, output_writer, Execute, IOError};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum DirectionArg {
+    /// New readers must read data written with the old schema
+    Backward,
+    /// Old readers must read data written with the new schema
+    Forward,
+    /// Both
+    Full,
+}
+
+impl From<DirectionArg> for Direction {
+    fn from(direction: DirectionArg) -> Self {
+        match direction {
+            DirectionArg::Backward => Direction::Backward,
+            DirectionArg::Forward => Direction::Forward,
+            DirectionArg::Full => Direction::Full,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+/// Check whether a new schema can still read data written with an old one.
+///
+/// Every change between the two layouts is listed as `compatible` (same
+/// bytes), `backward` (only new readers can read old data), `forward` (only
+/// old readers can read new data), or `breaking`. Exits with an error if any
+/// change is breaking in the chosen direction.
+pub struct CompatArgs {
+    /// Old schema. Data with an embedded schema header is also accepted.
+    pub old_path: PathBuf,
+
+    /// New schema. Data with an embedded schema header is also accepted.
+    pub new_path: PathBuf,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Which readers must keep working.
+    #[arg(short, long, value_enum, default_value = "backward")]
+    pub direction: DirectionArg,
+}
+
+pub struct Compat<'a> {
+    pub old: BorshSchemaContainer,
+    pub new: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub direction: Direction,
+}
+
+impl TryFrom<&'_ CompatArgs> for Compat<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        CompatArgs {
+            old_path,
+            new_path,
+            output_path,
+            direction,
+        }: &'_ CompatArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            old: get_input_schema(Some(old_path))?,
+            new: get_input_schema(Some(new_path))?,
+            output: output_writer(output_path.as_ref())?,
+            direction: (*direction).into(),
+        })
+    }
+}
+
+impl Execute for Compat<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let changes = compare(&self.old, &self.new);
+        let breaking = changes
+            .iter()
+            .filter(|c| c.is_breaking(self.direction))
+            .count();
+
+        let mut report = String::new();
+        for change in &changes {
+            writeln!(report, "{change}").unwrap();
+        }
+        if changes.is_empty() {
+            writeln!(report, "No changes").unwrap();
+        }
+        output_bytes(&mut self.output, report.as_bytes())?;
+
+        if breaking > 0 {
+            return Err(IOError::IncompatibleSchema(breaking));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+
+    use crate::{
+        command::{Execute, IOError},
+        schema_compat::Direction,
+    };
+
+    use super::Compat;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Old {
+        balance: u64,
+    }
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct New {
+        amount: u64,
+        memo: String,
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Compat {
+            old: Old::schema_container(),
+            new: New::schema_container(),
+            output: Box::new(writer),
+            direction: Direction::Forward,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            String::from_utf8(output_vector).unwrap(),
+            "compatible  Old: type renamed from Old to New
+compatible  Old: field 0 renamed from balance to amount
+forward     Old: field memo appended, old readers can ignore the trailing bytes, but new readers cannot read old data
+",
+        );
+
+        let mut p = Compat {
+            old: Old::schema_container(),
+            new: New::schema_container(),
+            output: Box::new(std::io::sink()),
+            direction: Direction::Backward,
+        };
+
+        assert!(matches!(p.execute(), Err(IOError::IncompatibleSchema(1))));
+    }
+}
@@ -0,0 +1,120 @@
+use std::{io::Write, path::PathBuf};
+
+use clap::Args;
+
+use crate::schema_dsl::compile;
+
+use super::super::{get_input_bytes, output_borsh, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Compile a textual schema into a Borsh schema.
+///
+/// The input declares types with Rust-like `struct` and `enum` items, using
+/// Borsh primitives, `String`, `Vec<T>`, `[T; N]`, `Option<T>`, tuples and
+/// `HashMap<K, V>`. The output can be used with `encode --schema`.
+pub struct CompileArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Root type of the schema. Defaults to the first type declared.
+    #[arg(short, long)]
+    pub root: Option<String>,
+}
+
+pub struct Compile<'a> {
+    pub input: Vec<u8>,
+    pub output: Box<dyn Write + 'a>,
+    pub root: Option<String>,
+}
+
+impl TryFrom<&'_ CompileArgs> for Compile<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        CompileArgs {
+            input_path,
+            output_path,
+            root,
+        }: &'_ CompileArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            input: get_input_bytes(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            root: root.clone(),
+        })
+    }
+}
+
+impl Execute for Compile<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let source = std::str::from_utf8(&self.input)
+            .map_err(|_| IOError::CompileSchema("input is not valid UTF-8".to_string()))?;
+
+        let schema = compile(source, self.root.as_deref())
+            .map_err(|e| IOError::CompileSchema(e.to_string()))?;
+
+        output_borsh(&mut self.output, &schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSchema};
+
+    use crate::command::Execute;
+
+    use super::Compile;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Parent {
+        integer: u32,
+        vector: [u8; 8],
+        child: Child,
+    }
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Child {
+        string: String,
+        boolean: bool,
+    }
+
+    #[test]
+    fn test() {
+        let source = "
+            struct Parent {
+                integer: u32,
+                vector: [u8; 8],
+                child: Child,
+            }
+
+            struct Child {
+                string: String,
+                boolean: bool,
+            }
+        ";
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Compile {
+            input: source.as_bytes().to_vec(),
+            output: Box::new(writer),
+            root: None,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            Parent::schema_container(),
+            BorshSchemaContainer::try_from_slice(&output_vector).unwrap(),
+        );
+    }
+}
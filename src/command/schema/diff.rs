@@ -0,0 +1,148 @@
+use std::{fmt::Write as _, io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::schema_diff::diff;
+
+use super::super::{get_input_schema, output_bytes, output_json, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Show every difference between two Borsh schemas.
+///
+/// Lists definitions added, removed or renamed, fields added, removed,
+/// renamed or retyped, and enum variants added, removed, renamed or
+/// reordered. Use `schema compat` to check whether the changes are safe.
+pub struct DiffArgs {
+    /// Old schema. Data with an embedded schema header is also accepted.
+    pub old_path: PathBuf,
+
+    /// New schema. Data with an embedded schema header is also accepted.
+    pub new_path: PathBuf,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Output a JSON array of changes instead of text.
+    #[arg(short, long)]
+    pub json: bool,
+
+    /// Format JSON output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+pub struct Diff<'a> {
+    pub old: BorshSchemaContainer,
+    pub new: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub json: bool,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ DiffArgs> for Diff<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        DiffArgs {
+            old_path,
+            new_path,
+            output_path,
+            json,
+            pretty,
+        }: &'_ DiffArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            old: get_input_schema(Some(old_path))?,
+            new: get_input_schema(Some(new_path))?,
+            output: output_writer(output_path.as_ref())?,
+            json: *json,
+            pretty: *pretty,
+        })
+    }
+}
+
+impl Execute for Diff<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let changes = diff(&self.old, &self.new);
+
+        if self.json {
+            return output_json(&mut self.output, &changes, self.pretty);
+        }
+
+        let mut text = String::new();
+        for change in &changes {
+            writeln!(text, "{change}").unwrap();
+        }
+        output_bytes(&mut self.output, text.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+    use serde_json::json;
+
+    use crate::command::Execute;
+
+    use super::Diff;
+
+    #[allow(dead_code)]
+    mod a {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Record {
+            pub id: u32,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod b {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Record {
+            pub id: u64,
+            pub name: String,
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Diff {
+            old: a::Record::schema_container(),
+            new: b::Record::schema_container(),
+            output: Box::new(writer),
+            json: true,
+            pretty: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&output_vector).unwrap(),
+            json!([
+                {
+                    "change": "field_retyped",
+                    "declaration": "Record",
+                    "field": "id",
+                    "from": "u32",
+                    "to": "u64",
+                },
+                {
+                    "change": "field_added",
+                    "declaration": "Record",
+                    "field": "name",
+                    "type": "string",
+                },
+            ]),
+        );
+    }
+}
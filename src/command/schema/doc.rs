@@ -0,0 +1,105 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::{Args, ValueEnum};
+
+use crate::schema_doc;
+
+use super::super::{get_input_schema, output_bytes, output_writer, Execute, IOError};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    /// GitHub-flavored Markdown
+    #[value(alias = "md")]
+    Markdown,
+    /// Standalone HTML page
+    Html,
+}
+
+#[derive(Args, Debug)]
+/// Generate wire-format documentation for a Borsh schema.
+///
+/// Every type reachable from the root gets a section with its fields or
+/// variants in encoding order, how each one is encoded, fixed sizes in bytes
+/// where values always have the same size, and enum tag values.
+pub struct DocArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = Format::Markdown)]
+    pub format: Format,
+}
+
+pub struct Doc<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub format: Format,
+}
+
+impl TryFrom<&'_ DocArgs> for Doc<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        DocArgs {
+            input_path,
+            output_path,
+            format,
+        }: &'_ DocArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            format: *format,
+        })
+    }
+}
+
+impl Execute for Doc<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let doc = match self.format {
+            Format::Markdown => schema_doc::markdown(&self.schema),
+            Format::Html => schema_doc::html(&self.schema),
+        };
+        output_bytes(&mut self.output, doc.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+
+    use crate::command::Execute;
+
+    use super::{Doc, Format};
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Pair(u16, Vec<u8>);
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Doc {
+            schema: Pair::schema_container(),
+            output: Box::new(writer),
+            format: Format::Html,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let html = String::from_utf8(output_vector).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<tr><td>0</td><td><code>u16</code></td><td>2-byte unsigned integer, little-endian</td><td>2</td></tr>"));
+        assert!(html.contains("<h2 id=\"vecu8\"><code>Vec&lt;u8&gt;</code></h2>"));
+    }
+}
@@ -0,0 +1,123 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::schema_fingerprint::{fingerprint, normalize};
+
+use super::super::{get_input_schema, output_borsh, output_bytes, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Print a stable hash identifying a Borsh schema.
+///
+/// The schema is normalized first: definitions unreachable from the root are
+/// dropped and the rest are sorted, so schemas that only differ in unused
+/// definitions or definition order have the same fingerprint. The hash is
+/// the hex-encoded SHA-256 of the normalized schema.
+pub struct FingerprintArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Ignore type, field and variant names, so that only the layout counts.
+    #[arg(short, long)]
+    pub structural: bool,
+
+    /// Output the normalized schema instead of its hash.
+    #[arg(short, long)]
+    pub normalized: bool,
+}
+
+pub struct Fingerprint<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub structural: bool,
+    pub normalized: bool,
+}
+
+impl TryFrom<&'_ FingerprintArgs> for Fingerprint<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        FingerprintArgs {
+            input_path,
+            output_path,
+            structural,
+            normalized,
+        }: &'_ FingerprintArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            structural: *structural,
+            normalized: *normalized,
+        })
+    }
+}
+
+impl Execute for Fingerprint<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        if self.normalized {
+            return output_borsh(&mut self.output, normalize(&self.schema, self.structural));
+        }
+
+        let hash = fingerprint(&self.schema, self.structural);
+        output_bytes(&mut self.output, format!("{hash}\n").as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+
+    use crate::command::Execute;
+
+    use super::Fingerprint;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct First {
+        a: u8,
+        b: Option<u16>,
+    }
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Second {
+        x: u8,
+        y: Option<u16>,
+    }
+
+    fn run(schema: borsh::schema::BorshSchemaContainer, structural: bool) -> String {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Fingerprint {
+            schema,
+            output: Box::new(writer),
+            structural,
+            normalized: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        String::from_utf8(output_vector).unwrap()
+    }
+
+    #[test]
+    fn test() {
+        let first = run(First::schema_container(), false);
+        assert_eq!(first.trim_end().len(), 64);
+        assert_ne!(first, run(Second::schema_container(), false));
+        assert_eq!(
+            run(First::schema_container(), true),
+            run(Second::schema_container(), true),
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::schema_json::SchemaJson;
+
+use super::super::{get_input_bytes, output_borsh, output_writer, validated, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Convert the JSON representation of a schema to a Borsh schema.
+pub struct FromJsonArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+}
+
+pub struct FromJson<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+}
+
+impl TryFrom<&'_ FromJsonArgs> for FromJson<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        FromJsonArgs {
+            input_path,
+            output_path,
+        }: &'_ FromJsonArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: validated(
+                serde_json::from_slice::<SchemaJson>(&get_input_bytes(input_path.as_ref())?)
+                    .map_err(|_| IOError::DeserializeJson)?
+                    .into(),
+            )?,
+            output: output_writer(output_path.as_ref())?,
+        })
+    }
+}
+
+impl Execute for FromJson<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        output_borsh(&mut self.output, &self.schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSchema};
+
+    use crate::{command::Execute, schema_json::SchemaJson};
+
+    use super::FromJson;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Parent {
+        integer: u32,
+        vector: [u8; 8],
+    }
+
+    #[test]
+    fn test() {
+        let json = serde_json::to_vec(&SchemaJson::from(&Parent::schema_container())).unwrap();
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = FromJson {
+            schema: serde_json::from_slice::<SchemaJson>(&json).unwrap().into(),
+            output: Box::new(writer),
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            Parent::schema_container(),
+            BorshSchemaContainer::try_from_slice(&output_vector).unwrap(),
+        );
+    }
+}
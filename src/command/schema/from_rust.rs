@@ -0,0 +1,111 @@
+use std::{io::Write, path::PathBuf};
+
+use clap::Args;
+
+use crate::schema_from_rust::{build, collect, TypeItem};
+
+use super::super::{output_borsh, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Extract a Borsh schema from Rust source code without compiling it.
+///
+/// Reads every struct and enum that derives `BorshSchema` or `BorshSerialize`
+/// from a `.rs` file or a directory of them (e.g. a crate's `src/`), and
+/// builds the schema the derive macro would generate for the root type.
+/// Fields marked `#[borsh_skip]` are omitted.
+pub struct FromRustArgs {
+    /// Rust source file, or a directory to search recursively.
+    pub path: PathBuf,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Root type of the schema, e.g. `Account` or `my_mod::Wrapper<u64>`.
+    /// Generic types must be given concrete type arguments.
+    #[arg(short, long)]
+    pub root: String,
+}
+
+pub struct FromRust<'a> {
+    pub items: Vec<TypeItem>,
+    pub output: Box<dyn Write + 'a>,
+    pub root: String,
+}
+
+impl TryFrom<&'_ FromRustArgs> for FromRust<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        FromRustArgs {
+            path,
+            output_path,
+            root,
+        }: &'_ FromRustArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            items: collect(path).map_err(|e| IOError::ExtractSchema(e.to_string()))?,
+            output: output_writer(output_path.as_ref())?,
+            root: root.clone(),
+        })
+    }
+}
+
+impl Execute for FromRust<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let schema =
+            build(&self.items, &self.root).map_err(|e| IOError::ExtractSchema(e.to_string()))?;
+
+        output_borsh(&mut self.output, &schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSchema};
+
+    use crate::{command::Execute, schema_from_rust::collect_items};
+
+    use super::FromRust;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Account {
+        owner: [u8; 32],
+        balance: u128,
+        memo: Option<String>,
+    }
+
+    #[test]
+    fn test() {
+        let source = "
+            #[derive(BorshSerialize, BorshDeserialize, BorshSchema)]
+            struct Account {
+                owner: [u8; 32],
+                balance: u128,
+                memo: Option<String>,
+            }
+        ";
+
+        let mut items = vec![];
+        collect_items(&syn::parse_file(source).unwrap().items, vec![], &mut items);
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = FromRust {
+            items,
+            output: Box::new(writer),
+            root: "Account".to_string(),
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            Account::schema_container(),
+            BorshSchemaContainer::try_from_slice(&output_vector).unwrap(),
+        );
+    }
+}
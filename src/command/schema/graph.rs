@@ -0,0 +1,128 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::{Args, ValueEnum};
+
+use crate::schema_graph;
+
+use super::super::{get_input_schema, output_bytes, output_writer, Execute, IOError};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    /// Graphviz DOT, e.g. for `dot -Tsvg`
+    Dot,
+    /// Mermaid flowchart, e.g. for Markdown documentation
+    Mermaid,
+}
+
+#[derive(Args, Debug)]
+/// Render the type dependency graph of a Borsh schema.
+///
+/// Nodes are types labelled with their kind, and edges are labelled with
+/// field or variant names. The root type has a bold border, and types and
+/// references that form a cycle are drawn in red.
+pub struct GraphArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = Format::Dot)]
+    pub format: Format,
+
+    /// Include primitive types such as `u64` and `string` as nodes.
+    #[arg(short, long)]
+    pub primitives: bool,
+}
+
+pub struct Graph<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub format: Format,
+    pub primitives: bool,
+}
+
+impl TryFrom<&'_ GraphArgs> for Graph<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        GraphArgs {
+            input_path,
+            output_path,
+            format,
+            primitives,
+        }: &'_ GraphArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            format: *format,
+            primitives: *primitives,
+        })
+    }
+}
+
+impl Execute for Graph<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let source = match self.format {
+            Format::Dot => schema_graph::dot(&self.schema, self.primitives),
+            Format::Mermaid => schema_graph::mermaid(&self.schema, self.primitives),
+        };
+        output_bytes(&mut self.output, source.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+
+    use crate::command::Execute;
+
+    use super::{Format, Graph};
+    use fixtures::Account;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Account {
+            owner: [u8; 32],
+            status: Status,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Active,
+            Frozen { until: u64 },
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Graph {
+            schema: Account::schema_container(),
+            output: Box::new(writer),
+            format: Format::Dot,
+            primitives: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let dot = String::from_utf8(output_vector).unwrap();
+        assert!(dot.contains("[label=\"Account\\nstruct\", style=bold];"));
+        assert!(dot.contains("[label=\"Array<u8, 32>\\narray[32]\"];"));
+        assert!(dot.contains("[label=\"status\"];"));
+        assert!(dot.contains("[label=\"Frozen\"];"));
+        assert!(!dot.contains("#d62728"));
+    }
+}
@@ -0,0 +1,121 @@
+use std::{io::Write, path::PathBuf};
+
+use clap::Args;
+
+use crate::schema_infer::{infer, TypeOverride};
+
+use super::super::{get_input_bytes, output_borsh, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Infer a Borsh schema from sample JSON documents.
+///
+/// The shapes of all samples are unified: objects become structs, arrays
+/// become sequences, values that are sometimes null or missing become
+/// Options, and integers get the narrowest width that fits every sample.
+/// The output can be used with `encode --schema`.
+pub struct InferArgs {
+    /// Sample files, each containing one or more JSON documents. Reads from
+    /// stdin if none are given.
+    pub sample_paths: Vec<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Name of the root struct.
+    #[arg(short, long, default_value = "Root")]
+    pub name: String,
+
+    /// Override the inferred type at a path, e.g. `balance=u64` or
+    /// `accounts[].id=u128`. Use `.` between field names and `[]` for array
+    /// elements.
+    #[arg(short, long = "type", value_name = "PATH=TYPE")]
+    pub types: Vec<TypeOverride>,
+}
+
+pub struct Infer<'a> {
+    pub samples: Vec<serde_json::Value>,
+    pub output: Box<dyn Write + 'a>,
+    pub name: String,
+    pub types: Vec<TypeOverride>,
+}
+
+impl TryFrom<&'_ InferArgs> for Infer<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        InferArgs {
+            sample_paths,
+            output,
+            name,
+            types,
+        }: &'_ InferArgs,
+    ) -> Result<Self, Self::Error> {
+        let inputs = if sample_paths.is_empty() {
+            vec![get_input_bytes(None)?]
+        } else {
+            sample_paths
+                .iter()
+                .map(|path| get_input_bytes(Some(path)))
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut samples = vec![];
+        for input in inputs {
+            for sample in serde_json::Deserializer::from_slice(&input).into_iter() {
+                samples.push(sample.map_err(|_| IOError::DeserializeJson)?);
+            }
+        }
+
+        Ok(Self {
+            samples,
+            output: output_writer(output.as_ref())?,
+            name: name.clone(),
+            types: types.clone(),
+        })
+    }
+}
+
+impl Execute for Infer<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let schema = infer(&self.samples, &self.name, &self.types)
+            .map_err(|e| IOError::InferSchema(e.to_string()))?;
+
+        output_borsh(&mut self.output, &schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{schema::BorshSchemaContainer, BorshDeserialize};
+    use serde_json::json;
+
+    use crate::command::Execute;
+
+    use super::Infer;
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Infer {
+            samples: vec![
+                json!({ "id": 1, "memo": null }),
+                json!({ "id": 300, "memo": "x" }),
+            ],
+            output: Box::new(writer),
+            name: "Record".to_string(),
+            types: vec!["id=u64".parse().unwrap()],
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let schema = BorshSchemaContainer::try_from_slice(&output_vector).unwrap();
+        assert_eq!(schema.declaration, "Record");
+        assert_eq!(schema.definitions.len(), 2);
+    }
+}
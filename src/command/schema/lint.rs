@@ -0,0 +1,216 @@
+use std::{collections::HashMap, fmt::Write as _, io::Write, path::PathBuf, str::FromStr};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+use serde::Serialize;
+
+use crate::schema_lint::{lint, Finding, Rule};
+
+use super::super::{get_input_schema, output_bytes, output_json, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Warn about schema patterns that are valid Borsh but often cause trouble.
+///
+/// Rules: `float` (f32/f64, whose encodings are not canonical), `hash-map`
+/// (encoded in key order rather than iteration order), `nested-option`
+/// (`Option<Option<T>>`), `large-array` (fixed arrays over 1024 elements),
+/// `many-variants` (enums with 200 or more variants), `unbounded-recursion`
+/// (recursive types), and `byte-vec` (`Vec<u8>` fields named like hashes,
+/// keys or signatures). Every rule warns by default; the command fails if a
+/// denied rule is triggered.
+pub struct LintArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Ignore a rule, or `all`.
+    #[arg(short = 'A', long, value_name = "RULE")]
+    pub allow: Vec<Rules>,
+
+    /// Report a rule as a warning, or `all`. Overrides --allow.
+    #[arg(short = 'W', long, value_name = "RULE")]
+    pub warn: Vec<Rules>,
+
+    /// Fail if a rule is triggered, or `all`. Overrides --allow and --warn.
+    #[arg(short = 'D', long, value_name = "RULE")]
+    pub deny: Vec<Rules>,
+
+    /// Output a JSON array of findings instead of text.
+    #[arg(short, long)]
+    pub json: bool,
+
+    /// Format JSON output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+/// A single rule, or every rule.
+#[derive(Clone, Debug)]
+pub struct Rules(Vec<Rule>);
+
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "all" {
+            Ok(Self(Rule::ALL.to_vec()))
+        } else {
+            s.parse().map(|rule| Self(vec![rule]))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    level: Level,
+    #[serde(flatten)]
+    finding: &'a Finding,
+}
+
+pub struct Lint<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub levels: HashMap<Rule, Level>,
+    pub json: bool,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ LintArgs> for Lint<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        LintArgs {
+            input_path,
+            output_path,
+            allow,
+            warn,
+            deny,
+            json,
+            pretty,
+        }: &'_ LintArgs,
+    ) -> Result<Self, Self::Error> {
+        let mut levels = HashMap::new();
+        for (rules, level) in [
+            (allow, Level::Allow),
+            (warn, Level::Warn),
+            (deny, Level::Deny),
+        ] {
+            for rule in rules.iter().flat_map(|r| &r.0) {
+                levels.insert(*rule, level);
+            }
+        }
+
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            levels,
+            json: *json,
+            pretty: *pretty,
+        })
+    }
+}
+
+impl Execute for Lint<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let findings = lint(&self.schema);
+        let reports = findings
+            .iter()
+            .map(|finding| Report {
+                level: self
+                    .levels
+                    .get(&finding.rule)
+                    .copied()
+                    .unwrap_or(Level::Warn),
+                finding,
+            })
+            .filter(|r| r.level != Level::Allow)
+            .collect::<Vec<_>>();
+
+        if self.json {
+            output_json(&mut self.output, &reports, self.pretty)?;
+        } else {
+            let mut text = String::new();
+            for Report { level, finding } in &reports {
+                let level = match level {
+                    Level::Deny => "error",
+                    _ => "warning",
+                };
+                writeln!(
+                    text,
+                    "{level}[{}]: {}: {}",
+                    finding.rule, finding.location, finding.message,
+                )
+                .unwrap();
+            }
+            if reports.is_empty() {
+                writeln!(text, "No issues").unwrap();
+            }
+            output_bytes(&mut self.output, text.as_bytes())?;
+        }
+
+        let denied = reports.iter().filter(|r| r.level == Level::Deny).count();
+        if denied > 0 {
+            return Err(IOError::Lint(denied));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io::BufWriter};
+
+    use borsh::BorshSchema;
+    use serde_json::json;
+
+    use crate::{
+        command::{Execute, IOError},
+        schema_lint::Rule,
+    };
+
+    use super::{Level, Lint};
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Quote {
+        price: f32,
+        volume: f64,
+        signature: Vec<u8>,
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Lint {
+            schema: Quote::schema_container(),
+            output: Box::new(writer),
+            levels: HashMap::from([(Rule::Float, Level::Deny), (Rule::ByteVec, Level::Allow)]),
+            json: true,
+            pretty: false,
+        };
+
+        assert!(matches!(p.execute(), Err(IOError::Lint(2))));
+        drop(p);
+
+        let findings = serde_json::from_slice::<serde_json::Value>(&output_vector).unwrap();
+        let findings = findings.as_array().unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0]["rule"], json!("float"));
+        assert_eq!(findings[0]["level"], json!("deny"));
+        assert_eq!(findings[0]["location"], json!("Quote.price"));
+        assert_eq!(findings[1]["location"], json!("Quote.volume"));
+    }
+}
@@ -0,0 +1,76 @@
+use clap::{Args, Subcommand};
+
+use super::{run_args, IOError};
+
+use self::{
+    check::Check, codegen::Codegen, compat::Compat, compile::Compile, diff::Diff, doc::Doc,
+    fingerprint::Fingerprint, from_json::FromJson, from_rust::FromRust, graph::Graph, infer::Infer,
+    lint::Lint, show::Show, size::Size, to_json::ToJson, to_json_schema::ToJsonSchema,
+};
+
+mod check;
+mod codegen;
+mod compat;
+mod compile;
+mod diff;
+mod doc;
+mod fingerprint;
+mod from_json;
+mod from_rust;
+mod graph;
+mod infer;
+mod lint;
+mod show;
+mod size;
+mod to_json;
+mod to_json_schema;
+
+#[derive(Args, Debug)]
+/// Inspect, convert, and generate code from Borsh schemas.
+pub struct SchemaArgs {
+    #[command(subcommand)]
+    pub command: SchemaCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SchemaCommand {
+    Show(show::ShowArgs),
+    ToJson(to_json::ToJsonArgs),
+    FromJson(from_json::FromJsonArgs),
+    ToJsonSchema(to_json_schema::ToJsonSchemaArgs),
+    Codegen(codegen::CodegenArgs),
+    Compile(compile::CompileArgs),
+    FromRust(from_rust::FromRustArgs),
+    Infer(infer::InferArgs),
+    Compat(compat::CompatArgs),
+    Check(check::CheckArgs),
+    Diff(diff::DiffArgs),
+    Lint(lint::LintArgs),
+    Fingerprint(fingerprint::FingerprintArgs),
+    Graph(graph::GraphArgs),
+    Doc(doc::DocArgs),
+    Size(size::SizeArgs),
+}
+
+impl SchemaCommand {
+    pub fn run(&self) -> Result<(), IOError> {
+        match self {
+            SchemaCommand::Show(args) => run_args::<Show>(args),
+            SchemaCommand::ToJson(args) => run_args::<ToJson>(args),
+            SchemaCommand::FromJson(args) => run_args::<FromJson>(args),
+            SchemaCommand::ToJsonSchema(args) => run_args::<ToJsonSchema>(args),
+            SchemaCommand::Codegen(args) => run_args::<Codegen>(args),
+            SchemaCommand::Compile(args) => run_args::<Compile>(args),
+            SchemaCommand::FromRust(args) => run_args::<FromRust>(args),
+            SchemaCommand::Infer(args) => run_args::<Infer>(args),
+            SchemaCommand::Compat(args) => run_args::<Compat>(args),
+            SchemaCommand::Check(args) => run_args::<Check>(args),
+            SchemaCommand::Diff(args) => run_args::<Diff>(args),
+            SchemaCommand::Lint(args) => run_args::<Lint>(args),
+            SchemaCommand::Fingerprint(args) => run_args::<Fingerprint>(args),
+            SchemaCommand::Graph(args) => run_args::<Graph>(args),
+            SchemaCommand::Doc(args) => run_args::<Doc>(args),
+            SchemaCommand::Size(args) => run_args::<Size>(args),
+        }
+    }
+}
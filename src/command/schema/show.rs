@@ -0,0 +1,125 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::{codegen::rust::declarations, schema_tree::tree};
+
+use super::super::{get_input_schema, output_bytes, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Print a schema as Rust-like type declarations.
+///
+/// Declarations are listed in dependency order, ending with the root.
+pub struct ShowArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Print a tree rooted at the schema declaration instead.
+    #[arg(short, long)]
+    pub tree: bool,
+}
+
+pub struct Show<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub tree: bool,
+}
+
+impl TryFrom<&'_ ShowArgs> for Show<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        ShowArgs {
+            input_path,
+            output_path,
+            tree,
+        }: &'_ ShowArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            tree: *tree,
+        })
+    }
+}
+
+impl Execute for Show<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let text = if self.tree {
+            tree(&self.schema)
+        } else {
+            declarations(&self.schema).map_err(|e| IOError::ConvertSchema(e.to_string()))?
+        };
+
+        output_bytes(&mut self.output, text.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+
+    use crate::command::Execute;
+
+    use super::Show;
+    use fixtures::Parent;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Parent {
+            integer: u32,
+            child: Child,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Child {
+            Alpha { field: u32 },
+            Beta(u8),
+            Gamma,
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Show {
+            schema: Parent::schema_container(),
+            output: Box::new(writer),
+            tree: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            String::from_utf8(output_vector).unwrap(),
+            "// Root: Parent
+
+enum Child {
+    Alpha {
+        field: u32,
+    },
+    Beta(u8),
+    Gamma,
+}
+
+struct Parent {
+    integer: u32,
+    child: Child,
+}
+",
+        );
+    }
+}
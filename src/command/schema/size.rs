@@ -0,0 +1,123 @@
+use std::{fmt::Write as _, io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::schema_size::sizes;
+
+use super::super::{get_input_schema, output_bytes, output_json, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Compute the encoded size of every type in a Borsh schema.
+///
+/// Fixed-size types get their exact size. Other types get their minimum
+/// size and a formula in terms of the lengths of their sequences and
+/// strings, e.g. `4 + 33*len(.items)`, where `(a | b)` is the size of
+/// whichever enum variant is present. Recursive types are flagged as
+/// unbounded.
+pub struct SizeArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Output a JSON array of sizes instead of text.
+    #[arg(short, long)]
+    pub json: bool,
+
+    /// Format JSON output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+pub struct Size<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub json: bool,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ SizeArgs> for Size<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        SizeArgs {
+            input_path,
+            output_path,
+            json,
+            pretty,
+        }: &'_ SizeArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            json: *json,
+            pretty: *pretty,
+        })
+    }
+}
+
+impl Execute for Size<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let sizes = sizes(&self.schema);
+
+        if self.json {
+            return output_json(&mut self.output, &sizes, self.pretty);
+        }
+
+        let mut text = String::new();
+        for size in &sizes {
+            writeln!(text, "{size}").unwrap();
+        }
+        output_bytes(&mut self.output, text.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+    use serde_json::json;
+
+    use crate::command::Execute;
+
+    use super::Size;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Record {
+        id: u32,
+        memo: Option<[u8; 8]>,
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Size {
+            schema: Record::schema_container(),
+            output: Box::new(writer),
+            json: true,
+            pretty: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let sizes = serde_json::from_slice::<serde_json::Value>(&output_vector).unwrap();
+        assert_eq!(
+            sizes[0],
+            json!({
+                "declaration": "Record",
+                "kind": "bounded",
+                "min": 5,
+                "max": 13,
+                "formula": "5 + (0 | 8)",
+            }),
+        );
+    }
+}
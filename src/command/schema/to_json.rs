@@ -0,0 +1,97 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::schema_json::SchemaJson;
+
+use super::super::{get_input_schema, output_json, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Convert a Borsh schema to its JSON representation.
+pub struct ToJsonArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Format output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+pub struct ToJson<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ ToJsonArgs> for ToJson<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        ToJsonArgs {
+            input_path,
+            output_path,
+            pretty,
+        }: &'_ ToJsonArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            pretty: *pretty,
+        })
+    }
+}
+
+impl Execute for ToJson<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        output_json(
+            &mut self.output,
+            &SchemaJson::from(&self.schema),
+            self.pretty,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{schema::BorshSchemaContainer, BorshSchema};
+
+    use crate::{command::Execute, schema_json::SchemaJson};
+
+    use super::ToJson;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Parent {
+        integer: u32,
+        vector: Vec<String>,
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = ToJson {
+            schema: Parent::schema_container(),
+            output: Box::new(writer),
+            pretty: true,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let parsed = serde_json::from_slice::<SchemaJson>(&output_vector).unwrap();
+
+        assert_eq!(
+            Parent::schema_container(),
+            BorshSchemaContainer::from(parsed)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::json_schema::to_json_schema;
+
+use super::super::{get_input_schema, output_json, output_writer, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Convert a Borsh schema to a JSON Schema document.
+///
+/// The JSON Schema (draft 2020-12) describes the JSON accepted by `encode`
+/// with the same Borsh schema.
+pub struct ToJsonSchemaArgs {
+    /// Read schema from this file, otherwise from stdin. Data with an
+    /// embedded schema header is also accepted.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Format output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+pub struct ToJsonSchema<'a> {
+    pub schema: BorshSchemaContainer,
+    pub output: Box<dyn Write + 'a>,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ ToJsonSchemaArgs> for ToJsonSchema<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        ToJsonSchemaArgs {
+            input_path,
+            output_path,
+            pretty,
+        }: &'_ ToJsonSchemaArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema: get_input_schema(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            pretty: *pretty,
+        })
+    }
+}
+
+impl Execute for ToJsonSchema<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let document =
+            to_json_schema(&self.schema).map_err(|e| IOError::ConvertSchema(e.to_string()))?;
+
+        output_json(&mut self.output, &document, self.pretty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::BorshSchema;
+
+    use crate::command::Execute;
+
+    use super::ToJsonSchema;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Parent {
+        integer: u32,
+        vector: Vec<String>,
+    }
+
+    #[test]
+    fn test() {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = ToJsonSchema {
+            schema: Parent::schema_container(),
+            output: Box::new(writer),
+            pretty: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let document = serde_json::from_slice::<serde_json::Value>(&output_vector).unwrap();
+
+        assert_eq!(
+            document["$defs"]["Vec<string>"],
+            serde_json::json!({ "type": "array", "items": { "type": "string" } }),
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::{io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::query::{Key, Path};
+
+use super::{
+    get_input_bytes, get_input_schema, output_bytes, output_writer, rewrite_elements, Execute,
+    IOError,
+};
+
+#[derive(Args, Debug)]
+/// Sort the elements of a top-level sequence or record stream by field.
+///
+/// Values are ordered like the Rust values: integers exactly (including
+/// `u128`), `None` first, enums by variant declaration order, and structs
+/// field by field. The sort is stable, and elements are copied unchanged.
+pub struct SortArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Field path to sort by, like `.balance`. Repeat to break ties.
+    #[arg(short, long, required = true)]
+    pub by: Vec<Path>,
+
+    /// Sort in descending order.
+    #[arg(short, long)]
+    pub reverse: bool,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+}
+
+pub struct Sort<'a> {
+    pub input: Vec<u8>,
+    pub output: Box<dyn Write + 'a>,
+    pub by: Vec<Path>,
+    pub reverse: bool,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+}
+
+impl TryFrom<&'_ SortArgs> for Sort<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        SortArgs {
+            input_path,
+            output_path,
+            by,
+            reverse,
+            schema,
+            stream,
+        }: &'_ SortArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            input: get_input_bytes(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            by: by.clone(),
+            reverse: *reverse,
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+        })
+    }
+}
+
+/// Keys of the values at `paths`, with [`Key::Null`] for missing values.
+pub(super) fn keys(
+    schema: &BorshSchemaContainer,
+    paths: &[Path],
+    value: &serde_json::Value,
+) -> Vec<Key> {
+    paths
+        .iter()
+        .map(|path| {
+            path.resolve(schema, &schema.declaration, value)
+                .map_or(Key::Null, |(declaration, value)| {
+                    Key::new(schema, declaration, value)
+                })
+        })
+        .collect()
+}
+
+/// Checks that `paths` exist in the elements.
+pub(super) fn check_paths(schema: &BorshSchemaContainer, paths: &[Path]) -> Result<(), IOError> {
+    for path in paths {
+        path.declaration(schema, &schema.declaration)
+            .map_err(IOError::Query)?;
+    }
+    Ok(())
+}
+
+impl Execute for Sort<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let output = rewrite_elements(
+            &self.input,
+            self.schema.take(),
+            self.stream,
+            |records, elements| {
+                check_paths(&records.schema, &self.by)?;
+                let mut keyed = elements
+                    .into_iter()
+                    .map(|element| (keys(&records.schema, &self.by, &element.1), element))
+                    .collect::<Vec<_>>();
+                if self.reverse {
+                    keyed.sort_by(|(a, _), (b, _)| b.cmp(a));
+                } else {
+                    keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+                }
+                Ok(keyed.into_iter().map(|(_, element)| element).collect())
+            },
+        )?;
+        output_bytes(&mut self.output, &output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use crate::command::Execute;
+
+    use super::Sort;
+
+    #[derive(BorshSerialize, BorshSchema, Clone)]
+    struct Account {
+        name: String,
+        balance: u128,
+    }
+
+    #[test]
+    fn test() {
+        let account = |name: &str, balance| Account {
+            name: name.to_string(),
+            balance,
+        };
+        let accounts = vec![
+            account("a", u128::MAX),
+            account("b", 2),
+            account("c", 10),
+            account("d", 2),
+        ];
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Sort {
+            input: borsh::try_to_vec_with_schema(&accounts).unwrap(),
+            output: Box::new(writer),
+            by: vec![".balance".parse().unwrap()],
+            reverse: false,
+            schema: None,
+            stream: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let sorted = vec![
+            account("b", 2),
+            account("d", 2),
+            account("c", 10),
+            account("a", u128::MAX),
+        ];
+        assert_eq!(
+            output_vector,
+            borsh::try_to_vec_with_schema(&sorted).unwrap(),
+        );
+    }
+}
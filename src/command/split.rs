@@ -0,0 +1,264 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use borsh::{schema::BorshSchemaContainer, BorshSerialize};
+use clap::Args;
+
+use crate::records::Records;
+
+use super::{get_input_bytes, get_input_schema, split_schema_header, Execute, IOError};
+
+#[derive(Args, Debug)]
+/// Split a top-level sequence or record stream into several files.
+///
+/// Every file is valid Borsh data holding a consecutive slice of the
+/// elements, so that `borsh concat` of the files in order gives back the
+/// original. Files keep the schema header of the input, if any, unless
+/// `--no-header` is given. Elements are copied without decoding them if they
+/// have a fixed size.
+pub struct SplitArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Names of the output files, with `{}` standing for the chunk number,
+    /// e.g. `shard-{}.borsh`. Defaults to `<INPUT_STEM>.{}.<INPUT_EXTENSION>`.
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Maximum number of elements per file.
+    #[arg(
+        short = 'n',
+        long,
+        conflicts_with = "chunks",
+        required_unless_present = "chunks"
+    )]
+    pub chunk_size: Option<usize>,
+
+    /// Number of files, with the elements spread as evenly as possible.
+    #[arg(short = 'k', long)]
+    pub chunks: Option<usize>,
+
+    /// Embed the schema header in every file, even if the input has none.
+    #[arg(long, conflicts_with = "no_header")]
+    pub header: bool,
+
+    /// Leave out the schema header, even if the input has one.
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chunking {
+    /// Elements per chunk.
+    Size(usize),
+    /// Number of chunks.
+    Count(usize),
+}
+
+impl Chunking {
+    /// Number of elements in each chunk of `n` elements. There is always at
+    /// least one chunk.
+    fn lengths(self, n: usize) -> Vec<usize> {
+        match self {
+            Self::Size(size) => {
+                let mut lengths = vec![size; n / size];
+                if !n.is_multiple_of(size) || n == 0 {
+                    lengths.push(n % size);
+                }
+                lengths
+            }
+            Self::Count(count) => (0..count)
+                .map(|i| n / count + usize::from(i < n % count))
+                .collect(),
+        }
+    }
+}
+
+pub struct Split {
+    pub input: Vec<u8>,
+    /// File name pattern, with `{}` for the chunk number.
+    pub output: String,
+    pub chunking: Chunking,
+    /// Whether to embed the schema header, otherwise as in the input.
+    pub header: Option<bool>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+}
+
+/// Default output pattern for an input file: `data.borsh` is split into
+/// `data.0.borsh`, `data.1.borsh`, ….
+fn default_pattern(input_path: &Path) -> String {
+    let stem = input_path.with_extension("");
+    match input_path.extension() {
+        Some(extension) => format!("{}.{{}}.{}", stem.display(), extension.to_string_lossy()),
+        None => format!("{}.{{}}", stem.display()),
+    }
+}
+
+impl TryFrom<&'_ SplitArgs> for Split {
+    type Error = IOError;
+
+    fn try_from(
+        SplitArgs {
+            input_path,
+            output,
+            chunk_size,
+            chunks,
+            header,
+            no_header,
+            schema,
+            stream,
+        }: &'_ SplitArgs,
+    ) -> Result<Self, Self::Error> {
+        let output = match (output, input_path) {
+            (Some(output), _) => output.clone(),
+            (None, Some(input_path)) => default_pattern(input_path),
+            (None, None) => {
+                return Err(IOError::Split(
+                    "--output is required when reading from stdin".to_string(),
+                ))
+            }
+        };
+        if !output.contains("{}") {
+            return Err(IOError::Split(format!(
+                "the output pattern {output} must contain {{}} for the chunk number"
+            )));
+        }
+        let chunking = match (chunk_size, chunks) {
+            (Some(0), _) | (_, Some(0)) => {
+                return Err(IOError::Split("chunks cannot be empty".to_string()))
+            }
+            (Some(size), _) => Chunking::Size(*size),
+            (None, Some(count)) => Chunking::Count(*count),
+            (None, None) => unreachable!("required by clap"),
+        };
+        Ok(Self {
+            input: get_input_bytes(input_path.as_ref())?,
+            output,
+            chunking,
+            header: match (header, no_header) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+        })
+    }
+}
+
+impl Split {
+    /// Contents of every output file.
+    fn chunks(&mut self) -> Result<Vec<Vec<u8>>, IOError> {
+        let (schema, has_header, data) = split_schema_header(&self.input, self.schema.take())?;
+        let records = Records::new(&schema, self.stream).map_err(IOError::NotASequence)?;
+        let elements = records
+            .elements(data)
+            .map_err(|_| IOError::DeserializeBorsh("data according to schema"))?;
+
+        let header = match self.header.unwrap_or(has_header) {
+            true if has_header => self.input[..self.input.len() - data.len()].to_vec(),
+            true => schema.try_to_vec().map_err(|_| IOError::WriteBorsh)?,
+            false => vec![],
+        };
+
+        let mut rest = &elements[..];
+        self.chunking
+            .lengths(elements.len())
+            .into_iter()
+            .map(|length| {
+                let (chunk, next) = rest.split_at(length);
+                rest = next;
+                let body = records
+                    .join(chunk.iter().copied())
+                    .map_err(|_| IOError::WriteBorsh)?;
+                Ok([&header[..], &body].concat())
+            })
+            .collect()
+    }
+}
+
+impl Execute for Split {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let chunks = self.chunks()?;
+        // Zero-padded, so that the files sort in order
+        let width = (chunks.len() - 1).to_string().len();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let path = self.output.replace("{}", &format!("{i:0width$}"));
+            fs::write(&path, chunk).map_err(|_| IOError::CreateOutputFile(path))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use super::{default_pattern, Chunking, Split};
+
+    #[derive(BorshSerialize, BorshSchema, Clone)]
+    struct Entry {
+        id: u8,
+        name: String,
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Chunking::Size(2).lengths(5), [2, 2, 1]);
+        assert_eq!(Chunking::Size(2).lengths(0), [0]);
+        assert_eq!(Chunking::Count(3).lengths(7), [3, 2, 2]);
+        assert_eq!(
+            default_pattern("dir/data.borsh".as_ref()),
+            "dir/data.{}.borsh"
+        );
+
+        let entries = (0..5)
+            .map(|id| Entry {
+                id,
+                name: id.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut p = Split {
+            input: borsh::try_to_vec_with_schema(&entries).unwrap(),
+            output: "unused-{}".to_string(),
+            chunking: Chunking::Size(2),
+            header: None,
+            schema: None,
+            stream: false,
+        };
+        let chunks = p.chunks().unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks[2],
+            borsh::try_to_vec_with_schema(&entries[4..].to_vec()).unwrap(),
+        );
+
+        let mut p = Split {
+            input: borsh::try_to_vec_with_schema(&entries).unwrap(),
+            output: "unused-{}".to_string(),
+            chunking: Chunking::Count(2),
+            header: Some(false),
+            schema: None,
+            stream: false,
+        };
+        assert_eq!(
+            p.chunks().unwrap()[1],
+            entries[3..].to_vec().try_to_vec().unwrap()
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::{fmt::Write as _, io::Write, path::PathBuf};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+use serde::Serialize;
+
+use crate::{
+    dynamic_schema::{walk_from_schema, Visitor},
+    stats::{ByteUsage, Count, FieldSummary, Summary, Usage, ValueStats},
+};
+
+use super::{
+    get_input_bytes, get_input_schema, output_bytes, output_json, output_writer,
+    split_schema_header, Execute, IOError,
+};
+
+#[derive(Args, Debug)]
+/// Summarize Borsh data.
+///
+/// By default, profiles the values of every field path over a top-level
+/// sequence or a record stream: count, min, max and mean of numbers,
+/// distinct and most common strings, booleans and enum variants, length
+/// histograms of sequences and the ratio of `None` options.
+///
+/// With `--sizes`, reports where the bytes go: the encoded size of every
+/// field path and every type, summed over the whole value or record stream.
+/// Sizes include everything nested in a value, like `du`.
+pub struct StatsArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+
+    /// Report encoded bytes per field path and per type instead of values.
+    #[arg(long)]
+    pub sizes: bool,
+
+    /// Number of most common values to show for strings and enums.
+    #[arg(short, long, default_value_t = 5)]
+    pub top: usize,
+
+    /// Output JSON instead of text.
+    #[arg(short, long)]
+    pub json: bool,
+
+    /// Format JSON output
+    #[arg(short, long)]
+    pub pretty: bool,
+}
+
+pub struct Stats<'a> {
+    pub input: Vec<u8>,
+    pub output: Box<dyn Write + 'a>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+    pub sizes: bool,
+    pub top: usize,
+    pub json: bool,
+    pub pretty: bool,
+}
+
+impl TryFrom<&'_ StatsArgs> for Stats<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        StatsArgs {
+            input_path,
+            output_path,
+            schema,
+            stream,
+            sizes,
+            top,
+            json,
+            pretty,
+        }: &'_ StatsArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            input: get_input_bytes(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+            sizes: *sizes,
+            top: *top,
+            json: *json,
+            pretty: *pretty,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct PathUsage<'a> {
+    path: &'a str,
+    #[serde(flatten)]
+    usage: &'a Usage,
+}
+
+#[derive(Serialize)]
+struct TypeUsage<'a> {
+    r#type: &'a str,
+    #[serde(flatten)]
+    usage: &'a Usage,
+}
+
+#[derive(Serialize)]
+struct SizesReport<'a> {
+    records: u64,
+    bytes: u64,
+    paths: Vec<PathUsage<'a>>,
+    types: Vec<TypeUsage<'a>>,
+}
+
+#[derive(Serialize)]
+struct ValuesReport {
+    records: u64,
+    fields: Vec<FieldSummary>,
+}
+
+/// Walks one value, or every record of a stream, returning the number of
+/// records.
+fn walk(
+    mut buf: &[u8],
+    schema: &BorshSchemaContainer,
+    stream: bool,
+    visitor: &mut dyn Visitor,
+) -> Result<u64, IOError> {
+    let mut records = 0u64;
+    loop {
+        if stream && buf.is_empty() {
+            break;
+        }
+        walk_from_schema(&mut buf, schema, visitor)
+            .map_err(|_| IOError::DeserializeBorsh("data according to schema"))?;
+        records += 1;
+        if !stream {
+            break;
+        }
+    }
+    Ok(records)
+}
+
+/// Formats a number with at most a few decimals.
+fn decimal(x: f64) -> String {
+    let s = format!("{x:.3}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn counts(counts: &[Count]) -> String {
+    counts
+        .iter()
+        .map(|c| format!("{} ({})", c.value, c.count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Stats<'_> {
+    fn sizes(&mut self, schema: &BorshSchemaContainer, buf: &[u8]) -> Result<(), IOError> {
+        let mut usage = ByteUsage::default();
+        let records = walk(buf, schema, self.stream, &mut usage)?;
+
+        let report = SizesReport {
+            records,
+            bytes: usage.total(),
+            paths: usage
+                .paths
+                .iter()
+                .map(|(path, usage)| PathUsage { path, usage })
+                .collect(),
+            types: usage
+                .types_by_size()
+                .into_iter()
+                .map(|(r#type, usage)| TypeUsage { r#type, usage })
+                .collect(),
+        };
+
+        if self.json {
+            return output_json(&mut self.output, &report, self.pretty);
+        }
+
+        let percent = |bytes: u64| match report.bytes {
+            0 => 0.0,
+            total => bytes as f64 * 100.0 / total as f64,
+        };
+        let mut text = String::new();
+        writeln!(
+            text,
+            "{} bytes in {} record(s)",
+            report.bytes, report.records,
+        )
+        .unwrap();
+        for (title, rows) in [
+            (
+                "path",
+                report
+                    .paths
+                    .iter()
+                    .map(|p| (p.path, p.usage))
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                "type",
+                report.types.iter().map(|t| (t.r#type, t.usage)).collect(),
+            ),
+        ] {
+            writeln!(text).unwrap();
+            writeln!(text, "{:>12} {:>7} {:>10}  {title}", "bytes", "%", "count").unwrap();
+            for (name, usage) in rows {
+                writeln!(
+                    text,
+                    "{:>12} {:>6.1}% {:>10}  {name}",
+                    usage.bytes,
+                    percent(usage.bytes),
+                    usage.count,
+                )
+                .unwrap();
+            }
+        }
+        output_bytes(&mut self.output, text.as_bytes())
+    }
+
+    fn values(&mut self, schema: &BorshSchemaContainer, buf: &[u8]) -> Result<(), IOError> {
+        let mut stats = ValueStats::new(schema);
+        let records = walk(buf, schema, self.stream, &mut stats)?;
+        let report = ValuesReport {
+            records,
+            fields: stats.summaries(self.top),
+        };
+
+        if self.json {
+            return output_json(&mut self.output, &report, self.pretty);
+        }
+
+        let mut text = String::new();
+        writeln!(text, "{} record(s)", report.records).unwrap();
+        for field in &report.fields {
+            writeln!(text).unwrap();
+            writeln!(text, "{} ({})", field.path, field.r#type).unwrap();
+            writeln!(text, "  count     {}", field.count).unwrap();
+            match &field.summary {
+                Summary::Number { min, max, mean } => {
+                    // 128-bit integers are JSON strings
+                    let number = |v: &serde_json::Value| match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        v => v.to_string(),
+                    };
+                    writeln!(text, "  min       {}", number(min)).unwrap();
+                    writeln!(text, "  max       {}", number(max)).unwrap();
+                    writeln!(text, "  mean      {}", decimal(*mean)).unwrap();
+                }
+                Summary::Category { distinct, top } => {
+                    writeln!(text, "  distinct  {distinct}").unwrap();
+                    writeln!(text, "  top       {}", counts(top)).unwrap();
+                }
+                Summary::Option { none_ratio } => {
+                    writeln!(text, "  none      {}%", decimal(none_ratio * 100.0)).unwrap();
+                }
+                Summary::Sequence {
+                    min_length,
+                    max_length,
+                    mean_length,
+                    histogram,
+                } => {
+                    writeln!(
+                        text,
+                        "  length    {min_length}..={max_length}, mean {}",
+                        decimal(*mean_length),
+                    )
+                    .unwrap();
+                    writeln!(text, "  histogram {}", counts(histogram)).unwrap();
+                }
+            }
+        }
+        output_bytes(&mut self.output, text.as_bytes())
+    }
+}
+
+impl Execute for Stats<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let input = std::mem::take(&mut self.input);
+        let (schema, _, buf) = split_schema_header(&input, self.schema.take())?;
+        if self.sizes {
+            self.sizes(&schema, buf)
+        } else {
+            self.values(&schema, buf)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{BorshSchema, BorshSerialize};
+    use serde_json::json;
+
+    use crate::command::Execute;
+
+    use super::Stats;
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct State {
+        owner: [u8; 32],
+        balances: Vec<(u8, u64)>,
+    }
+
+    #[test]
+    fn test() {
+        let value = State {
+            owner: [0; 32],
+            balances: vec![(1, 100), (2, 200)],
+        };
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Stats {
+            input: borsh::try_to_vec_with_schema(&value).unwrap(),
+            output: Box::new(writer),
+            schema: None,
+            stream: false,
+            sizes: true,
+            top: 5,
+            json: true,
+            pretty: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        let report = serde_json::from_slice::<serde_json::Value>(&output_vector).unwrap();
+        assert_eq!(report["records"], json!(1));
+        assert_eq!(report["bytes"], json!(54));
+        assert_eq!(
+            report["paths"][1],
+            json!({ "path": ".balances", "count": 1, "bytes": 22 }),
+        );
+        assert_eq!(
+            report["types"][0],
+            json!({ "type": "State", "count": 1, "bytes": 54 }),
+        );
+    }
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Account {
+        name: String,
+        balance: u128,
+        tags: Vec<u8>,
+    }
+
+    #[test]
+    fn values() {
+        let mut input = vec![];
+        for (name, balance, tags) in [
+            ("a", 5, vec![]),
+            ("b", 15, vec![1, 2, 3]),
+            ("a", 10, vec![4]),
+        ] {
+            Account {
+                name: name.to_string(),
+                balance,
+                tags,
+            }
+            .serialize(&mut input)
+            .unwrap();
+        }
+
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Stats {
+            input,
+            output: Box::new(writer),
+            schema: Some(Account::schema_container()),
+            stream: true,
+            sizes: false,
+            top: 5,
+            json: false,
+            pretty: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        assert_eq!(
+            String::from_utf8(output_vector).unwrap(),
+            r#"3 record(s)
+
+.balance (u128)
+  count     3
+  min       5
+  max       15
+  mean      10
+
+.name (string)
+  count     3
+  distinct  2
+  top       a (2), b (1)
+
+.tags (Vec<u8>)
+  count     3
+  length    0..=3, mean 1.333
+  histogram 0 (1), 1 (1), 2-3 (1)
+
+.tags[] (u8)
+  count     4
+  min       1
+  max       4
+  mean      2.5
+"#,
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    io::Write,
+    path::PathBuf,
+};
+
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+
+use crate::query::Path;
+
+use super::{
+    get_input_bytes, get_input_schema, output_bytes, output_writer, rewrite_elements,
+    sort::{check_paths, keys},
+    Execute, IOError,
+};
+
+#[derive(Args, Debug)]
+/// Remove duplicate elements from a top-level sequence or record stream.
+///
+/// Keeps the first element with each value of the `--by` fields, or each
+/// distinct element without `--by`. Unlike the Unix `uniq`, duplicates need
+/// not be adjacent. Elements are copied unchanged.
+pub struct UniqArgs {
+    /// Read input from this file, otherwise from stdin.
+    pub input_path: Option<PathBuf>,
+
+    /// Write output to this file, otherwise to stdout.
+    pub output_path: Option<PathBuf>,
+
+    /// Field path to compare, like `.id`. Repeat to compare several fields.
+    #[arg(short, long)]
+    pub by: Vec<Path>,
+
+    /// Schema of the data. Defaults to the schema embedded in the input.
+    #[arg(short, long)]
+    pub schema: Option<PathBuf>,
+
+    /// Treat the input as a stream of back-to-back records of the root type.
+    #[arg(short = 'S', long)]
+    pub stream: bool,
+}
+
+pub struct Uniq<'a> {
+    pub input: Vec<u8>,
+    pub output: Box<dyn Write + 'a>,
+    pub by: Vec<Path>,
+    pub schema: Option<BorshSchemaContainer>,
+    pub stream: bool,
+}
+
+impl TryFrom<&'_ UniqArgs> for Uniq<'_> {
+    type Error = IOError;
+
+    fn try_from(
+        UniqArgs {
+            input_path,
+            output_path,
+            by,
+            schema,
+            stream,
+        }: &'_ UniqArgs,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            input: get_input_bytes(input_path.as_ref())?,
+            output: output_writer(output_path.as_ref())?,
+            by: by.clone(),
+            schema: schema
+                .as_ref()
+                .map(|path| get_input_schema(Some(path)))
+                .transpose()?,
+            stream: *stream,
+        })
+    }
+}
+
+impl Execute for Uniq<'_> {
+    fn execute(&mut self) -> Result<(), IOError> {
+        let output = rewrite_elements(
+            &self.input,
+            self.schema.take(),
+            self.stream,
+            |records, mut elements| {
+                check_paths(&records.schema, &self.by)?;
+                if self.by.is_empty() {
+                    // Borsh is canonical, so equal values have equal bytes
+                    let mut seen = HashSet::new();
+                    elements.retain(|(bytes, _)| seen.insert(*bytes));
+                } else {
+                    let mut seen = BTreeSet::new();
+                    elements
+                        .retain(|(_, value)| seen.insert(keys(&records.schema, &self.by, value)));
+                }
+                Ok(elements)
+            },
+        )?;
+        output_bytes(&mut self.output, &output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use crate::command::Execute;
+
+    use super::Uniq;
+
+    #[derive(BorshSerialize, BorshSchema, Clone)]
+    struct Transfer {
+        from: String,
+        amount: u64,
+    }
+
+    fn uniq(by: &[&str], transfers: &[Transfer]) -> Vec<u8> {
+        let mut output_vector: Vec<u8> = vec![];
+        let writer = BufWriter::new(&mut output_vector);
+
+        let mut p = Uniq {
+            input: borsh::try_to_vec_with_schema(&transfers.to_vec()).unwrap(),
+            output: Box::new(writer),
+            by: by.iter().map(|path| path.parse().unwrap()).collect(),
+            schema: None,
+            stream: false,
+        };
+
+        p.execute().unwrap();
+        drop(p);
+
+        output_vector
+    }
+
+    #[test]
+    fn test() {
+        let transfer = |from: &str, amount| Transfer {
+            from: from.to_string(),
+            amount,
+        };
+        let transfers = [
+            transfer("a", 1),
+            transfer("b", 1),
+            transfer("a", 1),
+            transfer("a", 2),
+        ];
+
+        assert_eq!(
+            uniq(&[], &transfers),
+            borsh::try_to_vec_with_schema(&vec![
+                transfers[0].clone(),
+                transfers[1].clone(),
+                transfers[3].clone(),
+            ])
+            .unwrap(),
+        );
+        assert_eq!(
+            uniq(&[".from"], &transfers),
+            borsh::try_to_vec_with_schema(&transfers[..2].to_vec()).unwrap(),
+        );
+    }
+}
@@ -3,11 +3,13 @@ use std::io::{Error, Write};
 use std::str::FromStr;
 
 use anyhow::anyhow;
-use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde_json::json;
 use thiserror::Error;
 
+use crate::schema_util::as_option;
+
 fn deserialize_type<T: BorshDeserialize + Into<serde_json::Value>>(
     buf: &mut &[u8],
     type_name: &str,
@@ -17,10 +19,88 @@ fn deserialize_type<T: BorshDeserialize + Into<serde_json::Value>>(
         .map_err(|_| Error::new(std::io::ErrorKind::InvalidData, type_name))
 }
 
-fn deserialize_declaration_from_schema(
+/// Receives every value decoded by [`walk_from_schema`], children before
+/// their parents.
+pub trait Visitor {
+    /// `path` is like `.items[].name`, with `.` for the root, `[]` for
+    /// sequence and array elements, and the variant name for enum payloads.
+    /// The value was decoded from `size` bytes starting `offset` bytes into
+    /// the root value.
+    fn visit(
+        &mut self,
+        path: &str,
+        declaration: &Declaration,
+        value: &serde_json::Value,
+        offset: usize,
+        size: usize,
+    );
+
+    /// Whether paths include element indices, like `.items[3].name`.
+    fn indexed(&self) -> bool {
+        false
+    }
+}
+
+impl Visitor for () {
+    fn visit(&mut self, _: &str, _: &Declaration, _: &serde_json::Value, _: usize, _: usize) {}
+}
+
+/// Appends a field name (or `[]` for elements) to a path, and removes it
+/// again when dropped.
+struct PathGuard<'a>(&'a mut String, usize);
+
+impl<'a> PathGuard<'a> {
+    fn field(path: &'a mut String, name: &str) -> Self {
+        let len = path.len();
+        if path != "." {
+            path.push('.');
+        }
+        path.push_str(name);
+        Self(path, len)
+    }
+
+    fn element(path: &'a mut String, index: Option<usize>) -> Self {
+        let len = path.len();
+        match index {
+            Some(index) => {
+                path.push('[');
+                path.push_str(&index.to_string());
+                path.push(']');
+            }
+            None => path.push_str("[]"),
+        }
+        Self(path, len)
+    }
+}
+
+impl Drop for PathGuard<'_> {
+    fn drop(&mut self) {
+        self.0.truncate(self.1);
+    }
+}
+
+/// `origin` is the length of the buffer at the start of the root value.
+fn walk_declaration(
     buf: &mut &[u8],
+    origin: usize,
     schema: &BorshSchemaContainer,
-    declaration: &borsh::schema::Declaration,
+    declaration: &Declaration,
+    path: &mut String,
+    visitor: &mut dyn Visitor,
+) -> std::io::Result<serde_json::Value> {
+    let start = buf.len();
+    let value = walk_value(buf, origin, schema, declaration, path, visitor)?;
+    visitor.visit(path, declaration, &value, origin - start, start - buf.len());
+    Ok(value)
+}
+
+fn walk_value(
+    buf: &mut &[u8],
+    origin: usize,
+    schema: &BorshSchemaContainer,
+    declaration: &Declaration,
+    path: &mut String,
+    visitor: &mut dyn Visitor,
 ) -> std::io::Result<serde_json::Value> {
     match &declaration[..] {
         "u8" => deserialize_type::<u8>(buf, "u8"),
@@ -41,6 +121,7 @@ fn deserialize_declaration_from_schema(
         "f64" => deserialize_type::<f64>(buf, "f64"),
         "string" => deserialize_type::<String>(buf, "string"),
         "bool" => deserialize_type::<bool>(buf, "bool"),
+        "nil" => Ok(serde_json::Value::Null),
 
         _ => {
             if let Some(d) = schema.definitions.get(declaration) {
@@ -50,8 +131,10 @@ fn deserialize_declaration_from_schema(
                         ref elements,
                     } => {
                         let mut v = Vec::<serde_json::Value>::with_capacity(*length as usize);
-                        for _ in 0..*length {
-                            let e = deserialize_declaration_from_schema(buf, schema, elements)?;
+                        for i in 0..*length as usize {
+                            let path = PathGuard::element(path, visitor.indexed().then_some(i));
+                            let e =
+                                walk_declaration(buf, origin, schema, elements, path.0, visitor)?;
                             v.push(e);
                         }
                         Ok(v.into())
@@ -59,8 +142,10 @@ fn deserialize_declaration_from_schema(
                     Definition::Sequence { elements } => {
                         let length = u32::deserialize(buf)?;
                         let mut v = Vec::<serde_json::Value>::with_capacity(length as usize);
-                        for _ in 0..length {
-                            let e = deserialize_declaration_from_schema(buf, schema, elements)?;
+                        for i in 0..length as usize {
+                            let path = PathGuard::element(path, visitor.indexed().then_some(i));
+                            let e =
+                                walk_declaration(buf, origin, schema, elements, path.0, visitor)?;
                             v.push(e);
                         }
                         Ok(v.into())
@@ -68,26 +153,36 @@ fn deserialize_declaration_from_schema(
                     Definition::Tuple { elements } => {
                         // try_collect not stable :'(
                         let mut v = Vec::<serde_json::Value>::with_capacity(elements.len());
-                        for element in elements {
-                            let e = deserialize_declaration_from_schema(buf, schema, element)?;
+                        for (i, element) in elements.iter().enumerate() {
+                            let path = PathGuard::field(path, &i.to_string());
+                            let e =
+                                walk_declaration(buf, origin, schema, element, path.0, visitor)?;
                             v.push(e);
                         }
                         Ok(v.into())
                     }
                     Definition::Enum { variants } => {
                         let variant_index = u8::deserialize(buf)?;
-                        let (variant_name, variant_declaration) = &variants[variant_index as usize];
-                        deserialize_declaration_from_schema(buf, schema, variant_declaration)
+                        let (variant_name, variant_declaration) =
+                            variants.get(variant_index as usize).ok_or_else(|| {
+                                Error::new(std::io::ErrorKind::InvalidData, "enum variant")
+                            })?;
+                        let path = PathGuard::field(path, variant_name);
+                        walk_declaration(buf, origin, schema, variant_declaration, path.0, visitor)
                             .map(|v| json!({ variant_name: v }))
                     }
                     Definition::Struct { fields } => match fields {
                         Fields::NamedFields(fields) => {
                             let mut object = HashMap::<String, serde_json::Value>::new();
                             for (key, value_declaration) in fields {
-                                let value = deserialize_declaration_from_schema(
+                                let path = PathGuard::field(path, key);
+                                let value = walk_declaration(
                                     buf,
+                                    origin,
                                     schema,
                                     value_declaration,
+                                    path.0,
+                                    visitor,
                                 )?;
                                 object.insert(key.to_string(), value);
                             }
@@ -95,8 +190,11 @@ fn deserialize_declaration_from_schema(
                         }
                         Fields::UnnamedFields(elements) => {
                             let mut v = Vec::<serde_json::Value>::with_capacity(elements.len());
-                            for element in elements {
-                                let e = deserialize_declaration_from_schema(buf, schema, element)?;
+                            for (i, element) in elements.iter().enumerate() {
+                                let path = PathGuard::field(path, &i.to_string());
+                                let e = walk_declaration(
+                                    buf, origin, schema, element, path.0, visitor,
+                                )?;
                                 v.push(e);
                             }
                             Ok(v.into())
@@ -105,7 +203,10 @@ fn deserialize_declaration_from_schema(
                     },
                 }
             } else {
-                todo!("Unknown type to deserialize: {declaration}")
+                Err(Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unknown type to deserialize: {declaration}"),
+                ))
             }
         }
     }
@@ -115,7 +216,24 @@ pub fn deserialize_from_schema(
     buf: &mut &[u8],
     schema: &BorshSchemaContainer,
 ) -> std::io::Result<serde_json::Value> {
-    deserialize_declaration_from_schema(buf, schema, &schema.declaration)
+    walk_from_schema(buf, schema, &mut ())
+}
+
+/// Like [`deserialize_from_schema`], but also passes every value within the
+/// root value to a visitor, e.g. to collect statistics.
+pub fn walk_from_schema(
+    buf: &mut &[u8],
+    schema: &BorshSchemaContainer,
+    visitor: &mut dyn Visitor,
+) -> std::io::Result<serde_json::Value> {
+    walk_declaration(
+        buf,
+        buf.len(),
+        schema,
+        &schema.declaration,
+        &mut ".".to_string(),
+        visitor,
+    )
 }
 
 #[derive(Error, Debug)]
@@ -187,7 +305,25 @@ where
     Ok(())
 }
 
-fn serialize_declaration_with_schema(
+/// 128-bit integers are written as strings, but small enough ones may also be
+/// JSON numbers.
+fn serialize_wide<T: BorshSerialize + FromStr>(
+    writer: &mut impl Write,
+    value: &serde_json::Value,
+) -> anyhow::Result<()>
+where
+    <T as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
+    match value {
+        serde_json::Value::Number(n) => {
+            BorshSerialize::serialize(&T::from_str(&n.to_string())?, writer)?;
+            Ok(())
+        }
+        value => serialize_string::<T>(writer, value),
+    }
+}
+
+pub(crate) fn serialize_declaration_with_schema(
     writer: &mut impl Write,
     value: &serde_json::Value,
     schema: &BorshSchemaContainer,
@@ -198,13 +334,25 @@ fn serialize_declaration_with_schema(
         "u16" => serialize_unsigned::<u16>(writer, value),
         "u32" => serialize_unsigned::<u32>(writer, value),
         "u64" => serialize_unsigned::<u64>(writer, value),
-        "u128" => serialize_string::<u128>(writer, value),
+        "u128" => serialize_wide::<u128>(writer, value),
         "i8" => serialize_signed::<i8>(writer, value),
         "i16" => serialize_signed::<i16>(writer, value),
         "i32" => serialize_signed::<i32>(writer, value),
         "i64" => serialize_signed::<i64>(writer, value),
-        "i128" => serialize_string::<i128>(writer, value),
+        "i128" => serialize_wide::<i128>(writer, value),
+        "f32" => {
+            let v = value.as_f64().ok_or(ExpectationError::Number)? as f32;
+            BorshSerialize::serialize(&v, writer)?;
+            Ok(())
+        }
+        "f64" => {
+            let v = value.as_f64().ok_or(ExpectationError::Number)?;
+            BorshSerialize::serialize(&v, writer)?;
+            Ok(())
+        }
         "string" => serialize_string::<String>(writer, value),
+        // The unit type, e.g. the payload of `Option::None`
+        "nil" => Ok(()),
         "bool" => {
             let v = value.as_bool().ok_or(ExpectationError::Boolean)?;
             BorshSerialize::serialize(&v, writer)?;
@@ -245,6 +393,40 @@ fn serialize_declaration_with_schema(
                         Ok(())
                     }
                     Definition::Enum { variants } => {
+                        // Options may also be written as `null` or as the
+                        // bare value, the way serde represents them. A value
+                        // is only taken for a tag like `{"Some": …}` or
+                        // `"None"` if it is not a valid inner value, so that
+                        // e.g. the string "None" stays a string.
+                        if let Some(inner) = as_option(definition) {
+                            if value.is_null() {
+                                BorshSerialize::serialize(&0u8, writer)?;
+                                return Ok(());
+                            }
+                            let mut bare = vec![];
+                            let error = match serialize_declaration_with_schema(
+                                &mut bare, value, schema, inner,
+                            ) {
+                                Ok(()) => {
+                                    BorshSerialize::serialize(&1u8, writer)?;
+                                    writer.write_all(&bare)?;
+                                    return Ok(());
+                                }
+                                Err(error) => error,
+                            };
+                            let is_tagged = match value {
+                                serde_json::Value::String(s) => s == "None",
+                                serde_json::Value::Object(o) => {
+                                    o.len() == 1
+                                        && (o.contains_key("None") || o.contains_key("Some"))
+                                }
+                                _ => false,
+                            };
+                            if !is_tagged {
+                                return Err(error);
+                            }
+                        }
+
                         let (input_variant, variant_values) = value
                             .as_object()
                             .and_then(|o| o.keys().next().map(|s| (s.as_str(), Some(&o[s]))))
@@ -280,9 +462,19 @@ fn serialize_declaration_with_schema(
                         Fields::NamedFields(fields) => {
                             let object = value.as_object().ok_or(ExpectationError::Object)?;
                             for (key, value_declaration) in fields {
-                                let property_value = object
-                                    .get(key)
-                                    .ok_or_else(|| anyhow!("Expected property {key}"))?;
+                                let property_value = match object.get(key) {
+                                    Some(v) => v,
+                                    // A missing optional property is `None`
+                                    None if schema
+                                        .definitions
+                                        .get(value_declaration)
+                                        .and_then(as_option)
+                                        .is_some() =>
+                                    {
+                                        &serde_json::Value::Null
+                                    }
+                                    None => return Err(anyhow!("Expected property {key}")),
+                                };
                                 serialize_declaration_with_schema(
                                     writer,
                                     property_value,
@@ -323,7 +515,7 @@ fn serialize_declaration_with_schema(
                     },
                 }
             } else {
-                todo!("Unknown declaration to serialize: {declaration}")
+                Err(anyhow!("Unknown declaration to serialize: {declaration}"))
             }
         }
     }
@@ -0,0 +1,337 @@
+//! Searching decoded values for strings, numbers and bytes, see
+//! [`crate::dynamic_schema::Visitor`].
+
+use std::{cmp::Ordering, str::FromStr};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{dynamic_schema::Visitor, query::Integer, schema_util::is_integer};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Number {
+    Integer(Integer),
+    Float(f64),
+}
+
+impl FromStr for Number {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(integer) => Ok(Self::Integer(integer)),
+            Err(()) => s
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(Self::Float)
+                .ok_or(()),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => Some(a.cmp(b)),
+            (Self::Integer(a), Self::Float(b)) => a.to_f64().partial_cmp(b),
+            (Self::Float(a), Self::Integer(b)) => a.partial_cmp(&b.to_f64()),
+            (Self::Float(a), Self::Float(b)) => a.partial_cmp(b),
+        }
+    }
+}
+
+/// A number like `42`, or a range like `10..20`, `10..=20`, `10..` or `..20`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Range {
+    start: Option<Number>,
+    end: Option<Number>,
+    inclusive: bool,
+}
+
+impl FromStr for Range {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bound = |s: &str| match s {
+            "" => Ok(None),
+            s => s.parse().map(Some),
+        };
+        match s.split_once("..") {
+            None => {
+                let number = Some(s.parse()?);
+                Ok(Self {
+                    start: number,
+                    end: number,
+                    inclusive: true,
+                })
+            }
+            Some((start, end)) => {
+                let (end, inclusive) = match end.strip_prefix('=') {
+                    Some(end) if !end.is_empty() => (end, true),
+                    Some(_) => return Err(()),
+                    None => (end, false),
+                };
+                Ok(Self {
+                    start: bound(start)?,
+                    end: bound(end)?,
+                    inclusive,
+                })
+            }
+        }
+    }
+}
+
+impl Range {
+    fn contains(&self, number: Number) -> bool {
+        let above = self.start.is_none_or(|start| number >= start);
+        let below = self.end.is_none_or(|end| {
+            if self.inclusive {
+                number <= end
+            } else {
+                number < end
+            }
+        });
+        above && below
+    }
+}
+
+/// Bytes written as hex, like `0xdeadbeef`. Without the prefix, the pattern
+/// must contain a hex letter, so that numbers are not taken for bytes.
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    let digits = match s.strip_prefix("0x") {
+        Some(digits) => digits,
+        None if s.chars().any(|c| c.is_ascii_alphabetic()) => s,
+        None => return None,
+    };
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// A search pattern, interpreted according to the type of each value: a
+/// regular expression for strings, a number or range for numbers, and hex
+/// for byte arrays (`Vec<u8>` and `[u8; N]`), which match if they contain the
+/// bytes.
+#[derive(Debug)]
+pub struct Pattern {
+    regex: Option<Regex>,
+    range: Option<Range>,
+    bytes: Option<Vec<u8>>,
+}
+
+impl Pattern {
+    /// Parses a pattern for the kinds of values to search. Returns an error
+    /// if it is not valid for any of them.
+    pub fn new(pattern: &str, strings: bool, numbers: bool, bytes: bool) -> Result<Self, String> {
+        let parsed = Self {
+            regex: strings.then(|| Regex::new(pattern).ok()).flatten(),
+            range: numbers.then(|| pattern.parse().ok()).flatten(),
+            bytes: bytes.then(|| parse_hex(pattern)).flatten(),
+        };
+        if parsed.regex.is_none() && parsed.range.is_none() && parsed.bytes.is_none() {
+            return Err(format!(
+                "{pattern:?} is not a regular expression, number, range or hex bytes",
+            ));
+        }
+        Ok(parsed)
+    }
+
+    fn matches(
+        &self,
+        schema: &BorshSchemaContainer,
+        declaration: &Declaration,
+        value: &serde_json::Value,
+    ) -> bool {
+        match (schema.definitions.get(declaration), value) {
+            (None, serde_json::Value::String(s)) if declaration == "string" => {
+                self.regex.as_ref().is_some_and(|regex| regex.is_match(s))
+            }
+            (None, value) => {
+                let Some(range) = &self.range else {
+                    return false;
+                };
+                let number = match value {
+                    // 128-bit integers
+                    serde_json::Value::String(s) if is_integer(declaration) => s.parse().ok(),
+                    serde_json::Value::Number(n) => n.to_string().parse().ok(),
+                    _ => None,
+                };
+                number.is_some_and(|number| range.contains(number))
+            }
+            (
+                Some(Definition::Sequence { elements } | Definition::Array { elements, .. }),
+                serde_json::Value::Array(values),
+            ) if elements == "u8" => {
+                let Some(pattern) = &self.bytes else {
+                    return false;
+                };
+                let bytes = values
+                    .iter()
+                    .filter_map(|v| v.as_u64().map(|b| b as u8))
+                    .collect::<Vec<_>>();
+                bytes.windows(pattern.len()).any(|w| w == &pattern[..])
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Display form of a matching value: byte arrays as hex, everything else as
+/// JSON.
+fn display(declaration: &Declaration, value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Array(values) if !is_integer(declaration) => {
+            let mut hex = "0x".to_string();
+            for byte in values.iter().filter_map(serde_json::Value::as_u64) {
+                hex.push_str(&format!("{byte:02x}"));
+            }
+            hex
+        }
+        value => value.to_string(),
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Match {
+    pub path: String,
+    /// Offset of the value from the start of the root value.
+    pub offset: usize,
+    pub r#type: Declaration,
+    pub value: String,
+}
+
+/// Every value matching a pattern, with paths that include element indices.
+#[derive(Debug)]
+pub struct Grep<'a> {
+    schema: &'a BorshSchemaContainer,
+    pattern: &'a Pattern,
+    pub matches: Vec<Match>,
+}
+
+impl<'a> Grep<'a> {
+    pub fn new(schema: &'a BorshSchemaContainer, pattern: &'a Pattern) -> Self {
+        Self {
+            schema,
+            pattern,
+            matches: vec![],
+        }
+    }
+}
+
+impl Visitor for Grep<'_> {
+    fn visit(
+        &mut self,
+        path: &str,
+        declaration: &Declaration,
+        value: &serde_json::Value,
+        offset: usize,
+        _: usize,
+    ) {
+        // Elements of byte arrays are matched as bytes, not numbers
+        if declaration == "u8" && path.ends_with(']') {
+            return;
+        }
+        if self.pattern.matches(self.schema, declaration, value) {
+            self.matches.push(Match {
+                path: path.to_string(),
+                offset,
+                r#type: declaration.clone(),
+                value: display(declaration, value),
+            });
+        }
+    }
+
+    fn indexed(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use crate::dynamic_schema::walk_from_schema;
+
+    use super::{Grep, Pattern};
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Account {
+        key: [u8; 4],
+        name: String,
+        balance: u128,
+        score: f32,
+    }
+
+    fn grep(pattern: &str, strings: bool, numbers: bool, bytes: bool) -> Vec<(String, usize)> {
+        let accounts = vec![
+            Account {
+                key: [0xde, 0xad, 0xbe, 0xef],
+                name: "alice".to_string(),
+                balance: u128::MAX,
+                score: 1.5,
+            },
+            Account {
+                key: [1, 2, 3, 4],
+                name: "bob".to_string(),
+                balance: 1000,
+                score: -2.0,
+            },
+        ];
+        let schema = Vec::<Account>::schema_container();
+        let data = accounts.try_to_vec().unwrap();
+
+        let pattern = Pattern::new(pattern, strings, numbers, bytes).unwrap();
+        let mut grep = Grep::new(&schema, &pattern);
+        walk_from_schema(&mut (&data as &[u8]), &schema, &mut grep).unwrap();
+        grep.matches
+            .into_iter()
+            .map(|m| (m.path, m.offset))
+            .collect()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(
+            grep("^b", true, true, true),
+            [(".[1].name".to_string(), 41)]
+        );
+        assert_eq!(
+            grep("1000..", true, true, true),
+            [
+                (".[0].balance".to_string(), 17),
+                (".[1].balance".to_string(), 48),
+            ],
+        );
+        assert_eq!(
+            grep(
+                "340282366920938463463374607431768211455",
+                false,
+                true,
+                false
+            ),
+            [(".[0].balance".to_string(), 17)],
+        );
+        assert_eq!(
+            grep("-3..=-2", false, true, false),
+            [(".[1].score".to_string(), 64)]
+        );
+        assert_eq!(
+            grep("adbe", false, false, true),
+            [(".[0].key".to_string(), 4)]
+        );
+        assert_eq!(
+            grep("0x0203", true, true, true),
+            [(".[1].key".to_string(), 37)]
+        );
+        // Elements of byte arrays are not numbers
+        assert!(grep("3", false, true, false).is_empty());
+
+        assert!(Pattern::new("(", true, false, false).is_err());
+    }
+}
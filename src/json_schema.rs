@@ -0,0 +1,274 @@
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use crate::schema_util::as_option;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Error, Debug)]
+pub enum JsonSchemaError {
+    #[error("Unknown declaration: {0}")]
+    UnknownDeclaration(Declaration),
+}
+
+/// Produces a JSON Schema (draft 2020-12) document describing the JSON values
+/// that `encode` accepts for the given Borsh schema.
+///
+/// Every entry in `definitions` becomes an entry in `$defs`, and the root
+/// declaration is referenced from the top level.
+pub fn to_json_schema(schema: &BorshSchemaContainer) -> Result<Value, JsonSchemaError> {
+    let mut names = schema.definitions.keys().collect::<Vec<_>>();
+    names.sort();
+
+    let mut defs = Map::new();
+    for name in names {
+        defs.insert(
+            name.to_string(),
+            definition_schema(schema, &schema.definitions[name])?,
+        );
+    }
+
+    let mut document = Map::new();
+    document.insert("$schema".to_string(), DRAFT.into());
+    document.insert("title".to_string(), schema.declaration.clone().into());
+    if let Value::Object(root) = declaration_schema(schema, &schema.declaration)? {
+        document.extend(root);
+    }
+    if !defs.is_empty() {
+        document.insert("$defs".to_string(), defs.into());
+    }
+
+    Ok(document.into())
+}
+
+fn declaration_schema(
+    schema: &BorshSchemaContainer,
+    declaration: &Declaration,
+) -> Result<Value, JsonSchemaError> {
+    Ok(match &declaration[..] {
+        "u8" => unsigned_schema(u8::MAX.into()),
+        "u16" => unsigned_schema(u16::MAX.into()),
+        "u32" => unsigned_schema(u32::MAX.into()),
+        "u64" => unsigned_schema(u64::MAX),
+        "i8" => signed_schema(i8::MIN.into(), i8::MAX.into()),
+        "i16" => signed_schema(i16::MIN.into(), i16::MAX.into()),
+        "i32" => signed_schema(i32::MIN.into(), i32::MAX.into()),
+        "i64" => signed_schema(i64::MIN, i64::MAX),
+        // 128-bit integers may not fit in a JSON number, so they can also be
+        // strings
+        "u128" => json!({
+            "type": ["integer", "string"],
+            "minimum": 0,
+            "pattern": "^\\+?[0-9]+$",
+        }),
+        "i128" => json!({ "type": ["integer", "string"], "pattern": "^[+-]?[0-9]+$" }),
+        "f32" | "f64" => json!({ "type": "number" }),
+        "string" => json!({ "type": "string" }),
+        "bool" => json!({ "type": "boolean" }),
+        "nil" => json!({}),
+        _ if schema.definitions.contains_key(declaration) => {
+            json!({ "$ref": format!("#/$defs/{}", escape_pointer(declaration)) })
+        }
+        _ => return Err(JsonSchemaError::UnknownDeclaration(declaration.clone())),
+    })
+}
+
+fn unsigned_schema(max: u64) -> Value {
+    json!({ "type": "integer", "minimum": 0, "maximum": max })
+}
+
+fn signed_schema(min: i64, max: i64) -> Value {
+    json!({ "type": "integer", "minimum": min, "maximum": max })
+}
+
+fn fixed_array_schema(items: Vec<Value>) -> Value {
+    let length = items.len();
+    json!({
+        "type": "array",
+        "prefixItems": items,
+        "items": false,
+        "minItems": length,
+        "maxItems": length,
+    })
+}
+
+fn definition_schema(
+    schema: &BorshSchemaContainer,
+    definition: &Definition,
+) -> Result<Value, JsonSchemaError> {
+    Ok(match definition {
+        Definition::Array { length, elements } => json!({
+            "type": "array",
+            "items": declaration_schema(schema, elements)?,
+            "minItems": length,
+            "maxItems": length,
+        }),
+        Definition::Sequence { elements } => json!({
+            "type": "array",
+            "items": declaration_schema(schema, elements)?,
+        }),
+        Definition::Tuple { elements } => fixed_array_schema(
+            elements
+                .iter()
+                .map(|e| declaration_schema(schema, e))
+                .collect::<Result<_, _>>()?,
+        ),
+        Definition::Enum { variants } => {
+            let mut one_of = Vec::with_capacity(variants.len());
+            // `encode` takes `null` as `None`, then a valid inner value as
+            // `Some`, and only then the tagged forms below
+            let option = as_option(definition);
+            if let Some(inner) = option {
+                one_of.push(json!({ "type": "null" }));
+                one_of.push(declaration_schema(schema, inner)?);
+            }
+            for (variant_name, variant_declaration) in variants {
+                // `encode` also accepts a bare string for variants without data
+                if accepts_bare_variant(schema, variant_declaration) {
+                    one_of.push(json!({ "const": variant_name }));
+                }
+                one_of.push(json!({
+                    "type": "object",
+                    "properties": {
+                        variant_name: declaration_schema(schema, variant_declaration)?,
+                    },
+                    "required": [variant_name],
+                    "minProperties": 1,
+                    "maxProperties": 1,
+                }));
+            }
+            if option.is_some() {
+                // A bare `"None"` string may also be a valid inner value
+                json!({ "anyOf": one_of })
+            } else {
+                json!({ "oneOf": one_of })
+            }
+        }
+        Definition::Struct { fields } => match fields {
+            Fields::NamedFields(fields) => {
+                let mut properties = Map::new();
+                for (name, declaration) in fields {
+                    properties.insert(name.to_string(), declaration_schema(schema, declaration)?);
+                }
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    // `encode` takes a missing option as `None`
+                    "required": fields
+                        .iter()
+                        .filter(|(_, declaration)| !is_option(schema, declaration))
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>(),
+                })
+            }
+            // Newtypes are transparent
+            Fields::UnnamedFields(fields) if fields.len() == 1 => {
+                declaration_schema(schema, &fields[0])?
+            }
+            Fields::UnnamedFields(fields) => fixed_array_schema(
+                fields
+                    .iter()
+                    .map(|e| declaration_schema(schema, e))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Fields::Empty => json!({}),
+        },
+    })
+}
+
+fn is_option(schema: &BorshSchemaContainer, declaration: &Declaration) -> bool {
+    schema
+        .definitions
+        .get(declaration)
+        .and_then(as_option)
+        .is_some()
+}
+
+fn accepts_bare_variant(schema: &BorshSchemaContainer, declaration: &Declaration) -> bool {
+    declaration == "nil"
+        || matches!(
+            schema.definitions.get(declaration),
+            Some(Definition::Struct {
+                fields: Fields::Empty
+            })
+        )
+}
+
+/// Escapes a declaration for use as a JSON pointer segment inside a URI
+/// fragment, e.g. `Vec<string>` becomes `Vec%3Cstring%3E`.
+fn escape_pointer(declaration: &str) -> String {
+    let mut escaped = String::with_capacity(declaration.len());
+    for c in declaration.chars() {
+        match c {
+            '~' => escaped.push_str("~0"),
+            '/' => escaped.push_str("~1"),
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' | '_' => escaped.push(c),
+            _ => {
+                let mut buf = [0u8; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    escaped.push_str(&format!("%{b:02X}"));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+    use serde_json::json;
+
+    use super::to_json_schema;
+    use fixtures::Parent;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Parent {
+            integer: u8,
+            vector: [u16; 2],
+            child: Child,
+            memo: Option<String>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Child {
+            Empty,
+            Value(i128),
+        }
+    }
+
+    #[test]
+    fn test() {
+        let document = to_json_schema(&Parent::schema_container()).unwrap();
+
+        assert_eq!(document["$ref"], json!("#/$defs/Parent"));
+        assert_eq!(
+            document["$defs"]["Parent"]["required"],
+            json!(["integer", "vector", "child"]),
+        );
+        assert_eq!(
+            document["$defs"]["Parent"]["properties"]["integer"],
+            json!({ "type": "integer", "minimum": 0, "maximum": 255 }),
+        );
+        assert_eq!(document["$defs"]["Array<u16, 2>"]["maxItems"], json!(2));
+        assert_eq!(
+            document["$defs"]["Child"]["oneOf"][0],
+            json!({ "const": "Empty" })
+        );
+        assert_eq!(
+            document["$defs"]["ChildValue"]["type"],
+            json!(["integer", "string"]),
+        );
+        assert_eq!(
+            document["$defs"]["Option<string>"]["anyOf"]
+                .as_array()
+                .unwrap()[..2],
+            [json!({ "type": "null" }), json!({ "type": "string" })],
+        );
+    }
+}
@@ -1,8 +1,29 @@
 use clap::Parser;
 
+mod codegen;
 mod command;
 mod dynamic_schema;
+mod grep;
 mod json_borsh;
+mod json_schema;
+mod migration;
+mod query;
+mod records;
+mod schema_compat;
+mod schema_diff;
+mod schema_doc;
+mod schema_dsl;
+mod schema_fingerprint;
+mod schema_from_rust;
+mod schema_graph;
+mod schema_infer;
+mod schema_json;
+mod schema_lint;
+mod schema_size;
+mod schema_tree;
+mod schema_util;
+mod schema_validate;
+mod stats;
 
 #[derive(Parser, Debug)]
 #[command(author, version)]
@@ -0,0 +1,414 @@
+//! Converts values decoded with one schema into values that can be encoded
+//! with another, following a set of mapping rules.
+//!
+//! Values are in the JSON form produced by `dynamic_schema`. The old and new
+//! schemas are walked side by side; fields are matched by name (or renamed
+//! by a rule), enum variants by name (or remapped by a rule), and everything
+//! else by position.
+
+use std::collections::HashMap;
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::schema_util::{as_option, is_primitive};
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("{0}: no value for new field {1}, add a default to the mapping")]
+    MissingField(String, String),
+    #[error("{0}: field {1} is not in the new schema, drop it in the mapping")]
+    UnmappedField(String, String),
+    #[error("{0}: variant {1} is not in the new schema, remap it in the mapping")]
+    UnmappedVariant(String, String),
+    #[error("{0}: cannot convert {1} to {2}")]
+    Incompatible(String, String, String),
+    #[error("{0}: unexpected value for {1}")]
+    UnexpectedValue(String, String),
+    #[error("Mapping refers to type {0}, which is in neither schema")]
+    UnknownType(String),
+}
+
+/// Rules for one struct or enum, keyed by its old or new declaration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TypeRules {
+    /// Old field name to new field name.
+    pub rename: HashMap<String, String>,
+    /// Values for fields that are new, in the JSON form `encode` accepts.
+    pub default: HashMap<String, Value>,
+    /// Old fields to leave out.
+    pub drop: Vec<String>,
+    /// Old variant name to new variant name.
+    pub variants: HashMap<String, String>,
+}
+
+pub type Mapping = HashMap<Declaration, TypeRules>;
+
+pub struct Migration<'a> {
+    old: &'a BorshSchemaContainer,
+    new: &'a BorshSchemaContainer,
+    mapping: &'a Mapping,
+    empty: TypeRules,
+}
+
+impl<'a> Migration<'a> {
+    pub fn new(
+        old: &'a BorshSchemaContainer,
+        new: &'a BorshSchemaContainer,
+        mapping: &'a Mapping,
+    ) -> Result<Self, MigrationError> {
+        if let Some(unknown) = mapping
+            .keys()
+            .find(|d| !old.definitions.contains_key(*d) && !new.definitions.contains_key(*d))
+        {
+            return Err(MigrationError::UnknownType(unknown.clone()));
+        }
+        Ok(Self {
+            old,
+            new,
+            mapping,
+            empty: TypeRules::default(),
+        })
+    }
+
+    /// Converts a root value of the old schema to a root value of the new one.
+    pub fn migrate(&self, value: &Value) -> Result<Value, MigrationError> {
+        self.value(
+            value,
+            &self.old.declaration,
+            &self.new.declaration,
+            &self.new.declaration,
+        )
+    }
+
+    fn rules(&self, old: &Declaration, new: &Declaration) -> &TypeRules {
+        self.mapping
+            .get(new)
+            .or_else(|| self.mapping.get(old))
+            .unwrap_or(&self.empty)
+    }
+
+    fn value(
+        &self,
+        value: &Value,
+        old: &Declaration,
+        new: &Declaration,
+        path: &str,
+    ) -> Result<Value, MigrationError> {
+        let incompatible =
+            || MigrationError::Incompatible(path.to_string(), old.clone(), new.clone());
+        let unexpected = || MigrationError::UnexpectedValue(path.to_string(), old.clone());
+
+        if is_primitive(new) {
+            if !is_primitive(old) {
+                return Err(incompatible());
+            }
+            return primitive(value, new).ok_or_else(incompatible);
+        }
+
+        let new_definition = self.new.definitions.get(new).ok_or_else(incompatible)?;
+        let old_definition = match self.old.definitions.get(old) {
+            Some(definition) => definition,
+            // A primitive that became optional
+            None => {
+                let inner = as_option(new_definition).ok_or_else(incompatible)?;
+                return Ok(json!({ "Some": self.value(value, old, inner, path)? }));
+            }
+        };
+
+        match (old_definition, new_definition) {
+            (d, n) if as_option(n).is_some() && as_option(d).is_none() => {
+                let inner = as_option(n).unwrap();
+                Ok(json!({ "Some": self.value(value, old, inner, path)? }))
+            }
+            (
+                Definition::Sequence { elements: old } | Definition::Array { elements: old, .. },
+                Definition::Sequence { elements: new } | Definition::Array { elements: new, .. },
+            ) => {
+                let array = value.as_array().ok_or_else(unexpected)?;
+                array
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| self.value(v, old, new, &format!("{path}[{i}]")))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)
+            }
+            (Definition::Tuple { elements: old }, Definition::Tuple { elements: new }) => {
+                if old.len() != new.len() {
+                    return Err(incompatible());
+                }
+                let array = value.as_array().ok_or_else(unexpected)?;
+                array
+                    .iter()
+                    .zip(old.iter().zip(new))
+                    .enumerate()
+                    .map(|(i, (v, (o, n)))| self.value(v, o, n, &format!("{path}.{i}")))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)
+            }
+            (
+                Definition::Enum {
+                    variants: old_variants,
+                },
+                Definition::Enum {
+                    variants: new_variants,
+                },
+            ) => {
+                let (name, payload) = value
+                    .as_object()
+                    .and_then(|o| o.iter().next())
+                    .ok_or_else(unexpected)?;
+                let rules = self.rules(old, new);
+                let new_name = rules.variants.get(name).unwrap_or(name);
+                let old_payload = old_variants
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, d)| d)
+                    .ok_or_else(unexpected)?;
+                let new_payload = new_variants
+                    .iter()
+                    .find(|(n, _)| n == new_name)
+                    .map(|(_, d)| d)
+                    .ok_or_else(|| {
+                        MigrationError::UnmappedVariant(path.to_string(), name.clone())
+                    })?;
+                let payload = self.value(
+                    payload,
+                    old_payload,
+                    new_payload,
+                    &format!("{path}::{new_name}"),
+                )?;
+                Ok(json!({ new_name: payload }))
+            }
+            (
+                Definition::Struct { fields: old_fields },
+                Definition::Struct { fields: new_fields },
+            ) => self
+                .fields(value, old_fields, new_fields, self.rules(old, new), path)
+                .map_err(|e| match e {
+                    Some(e) => e,
+                    None => unexpected(),
+                }),
+            _ => Err(incompatible()),
+        }
+    }
+
+    /// `Err(None)` means the value did not have the shape of the old fields.
+    fn fields(
+        &self,
+        value: &Value,
+        old: &Fields,
+        new: &Fields,
+        rules: &TypeRules,
+        path: &str,
+    ) -> Result<Value, Option<MigrationError>> {
+        let join = |name: &str| format!("{path}.{name}");
+
+        // Named, or keyed by position
+        let old_values: Vec<(String, &Value, &Declaration)> = match old {
+            Fields::NamedFields(fields) => {
+                let object = value.as_object().ok_or(None)?;
+                fields
+                    .iter()
+                    .map(|(name, d)| Ok((name.clone(), object.get(name).ok_or(None)?, d)))
+                    .collect::<Result<_, Option<MigrationError>>>()?
+            }
+            Fields::UnnamedFields(fields) => {
+                let array = value.as_array().ok_or(None)?;
+                if array.len() != fields.len() {
+                    return Err(None);
+                }
+                array
+                    .iter()
+                    .zip(fields)
+                    .enumerate()
+                    .map(|(i, (v, d))| (i.to_string(), v, d))
+                    .collect()
+            }
+            Fields::Empty => vec![],
+        };
+        let new_fields: Vec<(String, &Declaration)> = match new {
+            Fields::NamedFields(fields) => fields.iter().map(|(n, d)| (n.clone(), d)).collect(),
+            Fields::UnnamedFields(fields) => fields
+                .iter()
+                .enumerate()
+                .map(|(i, d)| (i.to_string(), d))
+                .collect(),
+            Fields::Empty => vec![],
+        };
+
+        let mut migrated = Vec::with_capacity(new_fields.len());
+        for (name, new_declaration) in &new_fields {
+            let source = old_values.iter().find(|(old_name, ..)| {
+                rules.rename.get(old_name).unwrap_or(old_name) == name
+                    && !rules.drop.contains(old_name)
+            });
+            let value = match source {
+                Some((_, value, old_declaration)) => {
+                    self.value(value, old_declaration, new_declaration, &join(name))?
+                }
+                None => match rules.default.get(name) {
+                    Some(default) => default.clone(),
+                    None if self
+                        .new
+                        .definitions
+                        .get(*new_declaration)
+                        .and_then(as_option)
+                        .is_some() =>
+                    {
+                        Value::Null
+                    }
+                    None => {
+                        return Err(Some(MigrationError::MissingField(
+                            path.to_string(),
+                            name.clone(),
+                        )))
+                    }
+                },
+            };
+            migrated.push((name.clone(), value));
+        }
+
+        for (old_name, ..) in &old_values {
+            let new_name = rules.rename.get(old_name).unwrap_or(old_name);
+            if !rules.drop.contains(old_name) && !new_fields.iter().any(|(n, _)| n == new_name) {
+                return Err(Some(MigrationError::UnmappedField(
+                    path.to_string(),
+                    old_name.clone(),
+                )));
+            }
+        }
+
+        Ok(match new {
+            Fields::NamedFields(_) => Value::Object(migrated.into_iter().collect()),
+            // Newtypes are encoded transparently
+            Fields::UnnamedFields(_) if migrated.len() == 1 => migrated.pop().unwrap().1,
+            Fields::UnnamedFields(_) => {
+                Value::Array(migrated.into_iter().map(|(_, v)| v).collect())
+            }
+            Fields::Empty => json!({}),
+        })
+    }
+}
+
+/// Adjusts the representation of a primitive for its new type: 128-bit
+/// integers are strings, all other numbers are numbers.
+fn primitive(value: &Value, declaration: &str) -> Option<Value> {
+    match (declaration, value) {
+        ("u128" | "i128", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("u8" | "u16" | "u32" | "u64", Value::String(s)) => s.parse::<u64>().ok().map(Into::into),
+        ("i8" | "i16" | "i32" | "i64", Value::String(s)) => s.parse::<i64>().ok().map(Into::into),
+        ("string", Value::String(_))
+        | ("bool", Value::Bool(_))
+        | ("nil", Value::Null)
+        | (_, Value::Number(_))
+        | ("u128" | "i128", Value::String(_)) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+    use serde_json::json;
+
+    use crate::dynamic_schema::{deserialize_from_schema, serialize_with_schema};
+
+    use super::{Mapping, Migration, MigrationError};
+
+    #[allow(dead_code)]
+    mod v1 {
+        use borsh::{BorshSchema, BorshSerialize};
+
+        #[derive(BorshSerialize, BorshSchema)]
+        pub struct Account {
+            pub balance: u32,
+            pub legacy: bool,
+            pub status: Status,
+            pub id: Id,
+        }
+
+        #[derive(BorshSerialize, BorshSchema)]
+        pub enum Status {
+            Active,
+            Frozen { until: u64 },
+        }
+
+        #[derive(BorshSerialize, BorshSchema)]
+        pub struct Id(pub u8);
+    }
+
+    #[allow(dead_code)]
+    mod v2 {
+        use borsh::{BorshDeserialize, BorshSchema};
+
+        #[derive(BorshDeserialize, BorshSchema, Debug, PartialEq)]
+        pub struct Account {
+            pub amount: u128,
+            pub status: Status,
+            pub version: u8,
+            pub memo: Option<String>,
+            pub id: Id,
+        }
+
+        #[derive(BorshDeserialize, BorshSchema, Debug, PartialEq)]
+        pub enum Status {
+            Active,
+            Suspended { until: u64 },
+        }
+
+        #[derive(BorshDeserialize, BorshSchema, Debug, PartialEq)]
+        pub struct Id(pub u16);
+    }
+
+    #[test]
+    fn test() {
+        let old = v1::Account::schema_container();
+        let new = v2::Account::schema_container();
+        let bytes = v1::Account {
+            balance: 7,
+            legacy: true,
+            status: v1::Status::Frozen { until: 99 },
+            id: v1::Id(3),
+        }
+        .try_to_vec()
+        .unwrap();
+
+        let mapping: Mapping = serde_json::from_value(json!({
+            "Account": {
+                "rename": { "balance": "amount" },
+                "default": { "version": 2 },
+                "drop": ["legacy"],
+            },
+            "Status": { "variants": { "Frozen": "Suspended" } },
+        }))
+        .unwrap();
+
+        let migration = Migration::new(&old, &new, &mapping).unwrap();
+        let value = deserialize_from_schema(&mut &bytes[..], &old).unwrap();
+        let migrated = migration.migrate(&value).unwrap();
+        let mut encoded = vec![];
+        serialize_with_schema(&mut encoded, &migrated, &new).unwrap();
+
+        assert_eq!(
+            v2::Account::try_from_slice(&encoded).unwrap(),
+            v2::Account {
+                amount: 7,
+                status: v2::Status::Suspended { until: 99 },
+                version: 2,
+                memo: None,
+                id: v2::Id(3),
+            },
+        );
+
+        let empty = Mapping::new();
+        let migration = Migration::new(&old, &new, &empty).unwrap();
+        assert!(matches!(
+            migration.migrate(&value),
+            Err(MigrationError::MissingField(path, field)) if path == "Account" && field == "amount",
+        ));
+    }
+}
@@ -0,0 +1,597 @@
+//! Field paths, comparison keys and predicates over decoded values, for
+//! commands that select or reorder elements.
+//!
+//! Paths look like those reported by `borsh stats`: `.` is the value itself,
+//! `.name` a struct field or enum variant payload, `.0` a tuple field and
+//! `[3]` a sequence or array element.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::{
+    dynamic_schema::serialize_declaration_with_schema,
+    schema_util::{as_option, is_integer},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path(Vec<Segment>);
+
+impl FromStr for Path {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "." {
+            return Ok(Self(vec![]));
+        }
+        if !s.starts_with(['.', '[']) {
+            return Err(format!("Paths start with `.`: {s}"));
+        }
+
+        let mut segments = vec![];
+        let mut rest = s;
+        while !rest.is_empty() {
+            if let Some(r) = rest.strip_prefix('.') {
+                let end = r.find(['.', '[']).unwrap_or(r.len());
+                if end == 0 {
+                    return Err(format!("Empty field name in path: {s}"));
+                }
+                segments.push(Segment::Field(r[..end].to_string()));
+                rest = &r[end..];
+            } else if let Some(r) = rest.strip_prefix('[') {
+                let end = r
+                    .find(']')
+                    .ok_or_else(|| format!("Unclosed `[` in path: {s}"))?;
+                let index = r[..end]
+                    .parse()
+                    .map_err(|_| format!("Expected an element index like [3] in path: {s}"))?;
+                segments.push(Segment::Index(index));
+                rest = &r[end + 1..];
+            } else {
+                return Err(format!("Unexpected `{rest}` in path: {s}"));
+            }
+        }
+        Ok(Self(segments))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, ".");
+        }
+        for segment in &self.0 {
+            match segment {
+                Segment::Field(name) => write!(f, ".{name}")?,
+                Segment::Index(i) => write!(f, "[{i}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Declaration of field `name` of a definition, or of its element at `index`
+/// for sequences and arrays.
+fn child<'a>(definition: &'a Definition, segment: &Segment) -> Option<&'a Declaration> {
+    match (definition, segment) {
+        (
+            Definition::Array { elements, .. } | Definition::Sequence { elements },
+            Segment::Index(_),
+        ) => Some(elements),
+        (
+            Definition::Tuple { elements }
+            | Definition::Struct {
+                fields: Fields::UnnamedFields(elements),
+            },
+            Segment::Field(name),
+        ) => elements.get(name.parse::<usize>().ok()?),
+        (
+            Definition::Struct {
+                fields: Fields::NamedFields(fields),
+            }
+            | Definition::Enum { variants: fields },
+            Segment::Field(name),
+        ) => fields.iter().find(|(n, _)| n == name).map(|(_, d)| d),
+        _ => None,
+    }
+}
+
+impl Path {
+    /// Declaration of the values at this path, or an error if the path does
+    /// not exist in values of `declaration`.
+    pub fn declaration<'a>(
+        &self,
+        schema: &'a BorshSchemaContainer,
+        declaration: &'a Declaration,
+    ) -> Result<&'a Declaration, String> {
+        let mut declaration = declaration;
+        for segment in &self.0 {
+            declaration = schema
+                .definitions
+                .get(declaration)
+                .and_then(|definition| child(definition, segment))
+                .ok_or_else(|| format!("{self} does not exist in {}", schema.declaration))?;
+        }
+        Ok(declaration)
+    }
+
+    /// The value at this path within a value of `declaration`, with its
+    /// declaration. `None` if the path goes through another enum variant or
+    /// past the end of a sequence.
+    pub fn resolve<'a, 'v>(
+        &self,
+        schema: &'a BorshSchemaContainer,
+        declaration: &'a Declaration,
+        value: &'v serde_json::Value,
+    ) -> Option<(&'a Declaration, &'v serde_json::Value)> {
+        let mut declaration = declaration;
+        let mut value = value;
+        for segment in &self.0 {
+            let definition = schema.definitions.get(declaration)?;
+            declaration = child(definition, segment)?;
+            value = match (definition, segment) {
+                (_, Segment::Index(i)) => value.get(i)?,
+                (
+                    Definition::Tuple { .. }
+                    | Definition::Struct {
+                        fields: Fields::UnnamedFields(_),
+                    },
+                    Segment::Field(name),
+                ) => value.get(name.parse::<usize>().ok()?)?,
+                // Struct fields, and the payload of the current variant
+                (_, Segment::Field(name)) => value.get(name)?,
+            };
+        }
+        Some((declaration, value))
+    }
+}
+
+/// An integer of any width, ordered by value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Integer {
+    negative: bool,
+    magnitude: u128,
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Integer {
+    pub fn to_f64(self) -> f64 {
+        let magnitude = self.magnitude as f64;
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl From<u128> for Integer {
+    fn from(magnitude: u128) -> Self {
+        Self {
+            negative: false,
+            magnitude,
+        }
+    }
+}
+
+impl From<i128> for Integer {
+    fn from(i: i128) -> Self {
+        Self {
+            negative: i < 0,
+            magnitude: i.unsigned_abs(),
+        }
+    }
+}
+
+impl FromStr for Integer {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        let magnitude = digits.parse::<u128>().map_err(|_| ())?;
+        Ok(Self {
+            negative: negative && magnitude != 0,
+            magnitude,
+        })
+    }
+}
+
+/// A float, totally ordered.
+#[derive(Clone, Copy, Debug)]
+pub struct Float(f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Float {}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A decoded value, comparable like the Rust value: integers exactly,
+/// options with `None` first, enums by variant then payload, and structs,
+/// tuples and sequences field by field.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    Null,
+    Bool(bool),
+    Integer(Integer),
+    Float(Float),
+    String(String),
+    Variant(usize, Box<Key>),
+    List(Vec<Key>),
+}
+
+impl Key {
+    /// Key of a value decoded from `declaration`. Values that do not match
+    /// the declaration become [`Key::Null`].
+    pub fn new(
+        schema: &BorshSchemaContainer,
+        declaration: &Declaration,
+        value: &serde_json::Value,
+    ) -> Self {
+        use serde_json::Value;
+
+        let list = |declarations: &mut dyn Iterator<Item = &Declaration>, values: &[Value]| {
+            Key::List(
+                declarations
+                    .zip(values)
+                    .map(|(d, v)| Key::new(schema, d, v))
+                    .collect(),
+            )
+        };
+
+        match (schema.definitions.get(declaration), value) {
+            (None, Value::Bool(b)) => Key::Bool(*b),
+            (None, Value::String(s)) if is_integer(declaration) => {
+                s.parse().map_or(Key::Null, Key::Integer)
+            }
+            (None, Value::String(s)) => Key::String(s.clone()),
+            (None, Value::Number(n)) if is_integer(declaration) => {
+                n.to_string().parse().map_or(Key::Null, Key::Integer)
+            }
+            (None, Value::Number(n)) => Key::Float(Float(n.as_f64().unwrap_or(f64::NAN))),
+            (None, _) => Key::Null,
+
+            (Some(definition), Value::Object(object)) if as_option(definition).is_some() => {
+                match object.get("Some") {
+                    Some(value) => Key::new(schema, as_option(definition).unwrap(), value),
+                    None => Key::Null,
+                }
+            }
+            (Some(Definition::Enum { variants }), Value::Object(object)) => object
+                .iter()
+                .next()
+                .and_then(|(name, payload)| {
+                    let (i, (_, declaration)) =
+                        variants.iter().enumerate().find(|(_, (n, _))| n == name)?;
+                    Some(Key::Variant(
+                        i,
+                        Box::new(Key::new(schema, declaration, payload)),
+                    ))
+                })
+                .unwrap_or(Key::Null),
+            (
+                Some(Definition::Struct {
+                    fields: Fields::NamedFields(fields),
+                }),
+                Value::Object(object),
+            ) => Key::List(
+                fields
+                    .iter()
+                    .map(|(name, declaration)| {
+                        object
+                            .get(name)
+                            .map_or(Key::Null, |v| Key::new(schema, declaration, v))
+                    })
+                    .collect(),
+            ),
+            (
+                Some(
+                    Definition::Tuple { elements }
+                    | Definition::Struct {
+                        fields: Fields::UnnamedFields(elements),
+                    },
+                ),
+                Value::Array(values),
+            ) => list(&mut elements.iter(), values),
+            (
+                Some(Definition::Array { elements, .. } | Definition::Sequence { elements }),
+                Value::Array(values),
+            ) => list(&mut std::iter::repeat(elements), values),
+            (Some(_), _) => Key::List(vec![]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Operator {
+    fn test(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Lt => ordering.is_lt(),
+            Self::Le => ordering.is_le(),
+            Self::Gt => ordering.is_gt(),
+            Self::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Literal {
+    /// An enum variant name, compared with the variant but not its payload.
+    Variant(usize),
+    Key(Key),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Comparison {
+    path: Path,
+    operator: Operator,
+    literal: Literal,
+}
+
+/// Comparisons of field paths with literals, like
+/// `.balance > 1000 && .kind == "Transfer"`, joined by `&&` and `||`, with
+/// `&&` binding tighter. Literals are JSON values, or enum variant names as
+/// strings. A comparison with a path that does not exist in a value, e.g. the
+/// payload of another variant, is false.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Predicate(Vec<Vec<Comparison>>);
+
+/// Splits a predicate into paths, operators, literals and connectives.
+fn tokens(s: &str) -> Result<Vec<&str>, String> {
+    let mut tokens = vec![];
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let len = if let Some(string) = rest.strip_prefix('"') {
+            // JSON string, up to the first unescaped quote
+            let mut escaped = false;
+            1 + string
+                .char_indices()
+                .find(|&(_, c)| {
+                    let end = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    end
+                })
+                .map(|(i, _)| i + 1)
+                .ok_or_else(|| format!("Unclosed string in predicate: {s}"))?
+        } else if let Some(op) = ["==", "!=", "<=", ">=", "&&", "||", "<", ">"]
+            .into_iter()
+            .find(|op| rest.starts_with(op))
+        {
+            op.len()
+        } else {
+            rest.find(|c: char| c.is_whitespace() || "=!<>&|".contains(c))
+                .unwrap_or(rest.len())
+                .max(1)
+        };
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn literal(
+    schema: &BorshSchemaContainer,
+    declaration: &Declaration,
+    token: &str,
+) -> Result<Literal, String> {
+    let error = || format!("{token} is not a valid {declaration}");
+    let definition = schema.definitions.get(declaration);
+
+    if token == "null" {
+        return Ok(Literal::Key(Key::Null));
+    }
+    if let Some(inner) = definition.and_then(as_option) {
+        return literal(schema, inner, token);
+    }
+    let value = serde_json::from_str::<serde_json::Value>(token).map_err(|_| error())?;
+    match (definition, &value) {
+        (Some(Definition::Enum { variants }), serde_json::Value::String(name)) => variants
+            .iter()
+            .position(|(n, _)| n == name)
+            .map(Literal::Variant)
+            .ok_or_else(|| format!("{declaration} has no variant {name}")),
+        // Parsed exactly, rather than as a float like JSON numbers
+        (None, _) if is_integer(declaration) => token
+            .trim_matches('"')
+            .parse()
+            .map(|i| Literal::Key(Key::Integer(i)))
+            .map_err(|_| error()),
+        // Only values of the declared type, as a number would otherwise sort
+        // before every string
+        _ => {
+            serialize_declaration_with_schema(&mut std::io::sink(), &value, schema, declaration)
+                .map_err(|_| error())?;
+            Ok(Literal::Key(Key::new(schema, declaration, &value)))
+        }
+    }
+}
+
+impl Predicate {
+    /// Parses a predicate over values of the root type of `schema`.
+    pub fn parse(s: &str, schema: &BorshSchemaContainer) -> Result<Self, String> {
+        let tokens = tokens(s)?;
+        let mut groups = vec![vec![]];
+        let mut tokens = tokens.into_iter();
+        loop {
+            let (path, operator, token) = match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some(path), Some(operator), Some(token)) => (path, operator, token),
+                _ => return Err(format!("Expected comparisons like `.field == 1`: {s}")),
+            };
+            let path = path.parse::<Path>()?;
+            let declaration = path.declaration(schema, &schema.declaration)?;
+            let operator = match operator {
+                "==" => Operator::Eq,
+                "!=" => Operator::Ne,
+                "<" => Operator::Lt,
+                "<=" => Operator::Le,
+                ">" => Operator::Gt,
+                ">=" => Operator::Ge,
+                _ => return Err(format!("Unknown operator {operator} in predicate: {s}")),
+            };
+            let literal = literal(schema, declaration, token)?;
+            groups.last_mut().unwrap().push(Comparison {
+                path,
+                operator,
+                literal,
+            });
+
+            match tokens.next() {
+                None => return Ok(Self(groups)),
+                Some("&&") => {}
+                Some("||") => groups.push(vec![]),
+                Some(token) => return Err(format!("Expected && or || before {token}: {s}")),
+            }
+        }
+    }
+
+    /// Whether a value of the root type of `schema` matches.
+    pub fn matches(&self, schema: &BorshSchemaContainer, value: &serde_json::Value) -> bool {
+        self.0.iter().any(|group| {
+            group.iter().all(|comparison| {
+                let Some((declaration, value)) =
+                    comparison.path.resolve(schema, &schema.declaration, value)
+                else {
+                    return false;
+                };
+                let key = Key::new(schema, declaration, value);
+                let ordering = match (&comparison.literal, &key) {
+                    (Literal::Variant(i), Key::Variant(j, _)) => j.cmp(i),
+                    (Literal::Variant(_), _) => return false,
+                    (Literal::Key(literal), key) => key.cmp(literal),
+                };
+                comparison.operator.test(ordering)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+    use serde_json::json;
+
+    use super::{Key, Path, Predicate};
+    use fixtures::Event;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub enum Kind {
+            Transfer { to: String },
+            Mint,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Event {
+            balance: u128,
+            kind: Kind,
+            memo: Option<String>,
+            pair: (u8, i64),
+        }
+    }
+
+    #[test]
+    fn test() {
+        let schema = Event::schema_container();
+        let event = |balance: &str, kind| json!({ "balance": balance, "kind": kind, "memo": { "None": null }, "pair": [1, -5] });
+        let transfer = event(
+            "340282366920938463463374607431768211455",
+            json!({ "Transfer": { "to": "bob" } }),
+        );
+        let mint = event("1000", json!({ "Mint": null }));
+
+        let path = ".kind.Transfer.to".parse::<Path>().unwrap();
+        assert_eq!(path.to_string(), ".kind.Transfer.to");
+        assert_eq!(
+            path.resolve(&schema, &schema.declaration, &transfer),
+            Some((&"string".to_string(), &json!("bob"))),
+        );
+        assert_eq!(path.resolve(&schema, &schema.declaration, &mint), None);
+        assert!(".nope"
+            .parse::<Path>()
+            .unwrap()
+            .declaration(&schema, &schema.declaration)
+            .is_err());
+
+        assert!(
+            Key::new(&schema, &schema.declaration, &mint)
+                < Key::new(&schema, &schema.declaration, &transfer)
+        );
+
+        let matches = |predicate: &str, value| {
+            Predicate::parse(predicate, &schema)
+                .unwrap()
+                .matches(&schema, value)
+        };
+        assert!(matches(".balance > 1000", &transfer));
+        assert!(!matches(".balance > 1000", &mint));
+        assert!(matches(
+            ".balance == 340282366920938463463374607431768211455",
+            &transfer,
+        ));
+        assert!(matches(r#".kind == "Transfer""#, &transfer));
+        assert!(matches(r#".kind.Transfer.to == "bob""#, &transfer));
+        assert!(!matches(r#".kind.Transfer.to != "bob""#, &mint));
+        assert!(matches(".memo == null && .pair.1 < 0", &mint));
+        assert!(matches(r#".balance < 5 || .kind == "Mint""#, &mint));
+
+        assert!(Predicate::parse(r#".kind == "Burn""#, &schema).is_err());
+        assert!(Predicate::parse(".balance > 1000 &&", &schema).is_err());
+        assert!(Predicate::parse(r#".balance > "x""#, &schema).is_err());
+        assert!(Predicate::parse(".kind.Transfer.to > 5", &schema).is_err());
+        assert!(Predicate::parse(r#".pair == [1,"x"]"#, &schema).is_err());
+        assert!(matches(".pair == [1,-5]", &mint));
+    }
+}
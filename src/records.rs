@@ -0,0 +1,299 @@
+//! Elements of Borsh data that holds a list: a value whose root type is a
+//! sequence, or a stream of back-to-back records of the root type.
+
+use std::{
+    io::{self, Read},
+    ops::Range,
+};
+
+use borsh::{
+    schema::{BorshSchemaContainer, Definition},
+    BorshDeserialize, BorshSerialize,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    dynamic_schema::deserialize_from_schema,
+    schema_fingerprint::fingerprint,
+    schema_util::{clone_schema, fixed_size},
+};
+
+/// How the elements of some data are laid out.
+pub struct Records {
+    /// Schema of a single element.
+    pub schema: BorshSchemaContainer,
+    /// Whether the elements follow a `u32` length prefix, i.e. the data is a
+    /// top-level sequence rather than a record stream.
+    pub prefixed: bool,
+}
+
+impl Records {
+    /// Elements of a top-level sequence, or with `stream`, records of the
+    /// root type. Returns the root declaration if it is not a sequence.
+    pub fn new(schema: &BorshSchemaContainer, stream: bool) -> Result<Self, String> {
+        let declaration = match schema.definitions.get(&schema.declaration) {
+            _ if stream => schema.declaration.clone(),
+            Some(Definition::Sequence { elements }) => elements.clone(),
+            _ => return Err(schema.declaration.clone()),
+        };
+        let mut schema = clone_schema(schema);
+        schema.declaration = declaration;
+        Ok(Self {
+            schema,
+            prefixed: !stream,
+        })
+    }
+
+    /// Fingerprint of the element layout, which the offsets of the elements
+    /// depend on.
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.schema, true)
+    }
+
+    /// Splits data (after any schema header) into the length prefix, if
+    /// any, and the encoded elements.
+    pub fn split_prefix<'a>(&self, data: &'a [u8]) -> io::Result<(Option<u32>, &'a [u8])> {
+        let mut buf = data;
+        let length = self
+            .prefixed
+            .then(|| u32::deserialize(&mut buf))
+            .transpose()?;
+        Ok((length, buf))
+    }
+
+    /// Encodes elements, with a length prefix if the data is a sequence.
+    pub fn join<'a>(&self, elements: impl IntoIterator<Item = &'a [u8]>) -> io::Result<Vec<u8>> {
+        let mut count = 0u32;
+        let mut out = vec![];
+        if self.prefixed {
+            out.extend_from_slice(&[0; 4]);
+        }
+        for element in elements {
+            if self.prefixed {
+                count = count.checked_add(1).ok_or_else(too_many)?;
+            }
+            out.extend_from_slice(element);
+        }
+        if self.prefixed {
+            out[..4].copy_from_slice(&count.to_le_bytes());
+        }
+        Ok(out)
+    }
+
+    /// Locates every element of data (after any schema header), with
+    /// offsets relative to `base`. Elements of a fixed size are not decoded.
+    pub fn index(&self, data: &[u8], base: u64) -> io::Result<OffsetIndex> {
+        let (length, mut buf) = self.split_prefix(data)?;
+        let start = base + (data.len() - buf.len()) as u64;
+
+        if let Some(index) = self.fixed_index(length, start, buf.len() as u64) {
+            return index;
+        }
+
+        let mut offsets = vec![start];
+        let mut element = 0;
+        while length.map_or(!buf.is_empty(), |length| element < length) {
+            deserialize_from_schema(&mut buf, &self.schema)?;
+            offsets.push(base + (data.len() - buf.len()) as u64);
+            element += 1;
+        }
+        if !buf.is_empty() {
+            return Err(invalid("trailing bytes after the last element"));
+        }
+        Ok(OffsetIndex::Offsets(offsets))
+    }
+
+    /// Locates elements of a fixed size from the length prefix, if any, and
+    /// the number of bytes after it, without reading them. Returns `None` if
+    /// the elements do not have a fixed size.
+    pub fn fixed_index(
+        &self,
+        length: Option<u32>,
+        start: u64,
+        available: u64,
+    ) -> Option<io::Result<OffsetIndex>> {
+        let size = fixed_size(&self.schema, &self.schema.declaration)?;
+        let count = match length {
+            Some(length) => u64::from(length),
+            None if size == 0 => return Some(Err(invalid("empty records"))),
+            None => available / size,
+        };
+        if count.checked_mul(size) != Some(available) {
+            return Some(Err(invalid("trailing bytes after the last element")));
+        }
+        Some(Ok(OffsetIndex::Fixed { start, size, count }))
+    }
+
+    /// The encoded bytes of every element of data (after any schema header).
+    pub fn elements<'a>(&self, data: &'a [u8]) -> io::Result<Vec<&'a [u8]>> {
+        let index = self.index(data, 0)?;
+        Ok((0..index.len())
+            .map(|i| {
+                let Range { start, end } = index.range(i).unwrap();
+                &data[start as usize..end as usize]
+            })
+            .collect())
+    }
+
+    /// Decodes every element of data (after any schema header), returning
+    /// its encoded bytes along with its value.
+    pub fn decode<'a>(&self, data: &'a [u8]) -> io::Result<Vec<(&'a [u8], serde_json::Value)>> {
+        let (length, mut buf) = self.split_prefix(data)?;
+        let mut elements = vec![];
+        while length.map_or(!buf.is_empty(), |length| elements.len() < length as usize) {
+            let start = buf;
+            let value = deserialize_from_schema(&mut buf, &self.schema)?;
+            elements.push((&start[..start.len() - buf.len()], value));
+        }
+        if !buf.is_empty() {
+            return Err(invalid("trailing bytes after the last element"));
+        }
+        Ok(elements)
+    }
+}
+
+/// Contents of a sidecar index file.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct IndexFile {
+    /// Length of the indexed file.
+    pub data_len: u64,
+    /// Modification time of the indexed file, in nanoseconds since the Unix
+    /// epoch, if it was indexed from a file.
+    pub modified: Option<u64>,
+    /// SHA-256 of the indexed file.
+    pub checksum: [u8; 32],
+    /// Fingerprint of the element layout, see [`Records::fingerprint`].
+    pub fingerprint: String,
+    /// Whether the elements were indexed as a top-level sequence.
+    pub prefixed: bool,
+    pub offsets: OffsetIndex,
+}
+
+impl IndexFile {
+    /// Whether the index was built for elements laid out like `records`.
+    pub fn describes(&self, records: &Records) -> bool {
+        self.prefixed == records.prefixed && self.fingerprint == records.fingerprint()
+    }
+
+    /// Whether the index still describes some data. The data is only hashed
+    /// if its modification time differs from the indexed file's, so a file
+    /// rewritten to the same length, e.g. by `borsh sort`, is not mistaken
+    /// for the indexed one.
+    pub fn is_fresh(
+        &self,
+        data_len: u64,
+        modified: Option<u64>,
+        data: impl Read,
+    ) -> io::Result<bool> {
+        if data_len != self.data_len {
+            return Ok(false);
+        }
+        if modified.is_some() && modified == self.modified {
+            return Ok(true);
+        }
+        Ok(checksum(data)? == self.checksum)
+    }
+}
+
+/// SHA-256 of some data.
+pub fn checksum(mut data: impl Read) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut data, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Where the elements of some data start and end.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub enum OffsetIndex {
+    /// Elements of the same size, found without decoding them.
+    Fixed { start: u64, size: u64, count: u64 },
+    /// The start of every element, followed by the end of the last one.
+    Offsets(Vec<u64>),
+}
+
+impl OffsetIndex {
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Fixed { count, .. } => *count,
+            Self::Offsets(offsets) => offsets.len().saturating_sub(1) as u64,
+        }
+    }
+
+    /// Byte range of element `i`.
+    pub fn range(&self, i: u64) -> Option<Range<u64>> {
+        if i >= self.len() {
+            return None;
+        }
+        Some(match self {
+            Self::Fixed { start, size, .. } => start + i * size..start + (i + 1) * size,
+            Self::Offsets(offsets) => offsets[i as usize]..offsets[i as usize + 1],
+        })
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn too_many() -> io::Error {
+    invalid("more than u32::MAX elements")
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use super::{OffsetIndex, Records};
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Entry {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn test() {
+        let entries = vec![
+            Entry {
+                id: 1,
+                name: "a".to_string(),
+            },
+            Entry {
+                id: 2,
+                name: "bcd".to_string(),
+            },
+        ];
+        let data = entries.try_to_vec().unwrap();
+
+        let records = Records::new(&Vec::<Entry>::schema_container(), false).unwrap();
+        assert_eq!(records.schema.declaration, "Entry");
+        assert_eq!(
+            records.index(&data, 10).unwrap(),
+            OffsetIndex::Offsets(vec![14, 23, 34]),
+        );
+        let elements = records.decode(&data).unwrap();
+        assert_eq!(elements[1].0, entries[1].try_to_vec().unwrap());
+        assert_eq!(elements[1].1["name"], "bcd");
+        let reversed = records
+            .join(elements.iter().rev().map(|(bytes, _)| *bytes))
+            .unwrap();
+        assert_eq!(reversed[..4], 2u32.to_le_bytes());
+        assert_eq!(reversed.len(), data.len());
+        assert_eq!(records.elements(&data).unwrap()[1], elements[1].0);
+
+        assert!(Records::new(&Entry::schema_container(), false).is_err());
+
+        let ids = Records::new(&u64::schema_container(), true).unwrap();
+        let index = ids.index(&[0; 24], 0).unwrap();
+        assert_eq!(
+            index,
+            OffsetIndex::Fixed {
+                start: 0,
+                size: 8,
+                count: 3
+            },
+        );
+        assert_eq!(index.range(2), Some(16..24));
+        assert!(ids.index(&[0; 25], 0).is_err());
+    }
+}
@@ -0,0 +1,594 @@
+//! Compares two schemas and classifies every change by whether data written
+//! with one schema can still be read with the other.
+//!
+//! Borsh is not self-describing, so only the byte layout matters: renaming a
+//! type or a field is harmless, while widening an integer shifts every byte
+//! after it. Bytes appended at the very end of the data can be ignored by a
+//! reader that does not insist on consuming its whole input.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::schema_util::kind;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// The byte layout is unchanged.
+    Compatible,
+    /// Only the new schema can read data written with the old one.
+    Backward,
+    /// Only the old schema can read data written with the new one.
+    Forward,
+    /// Neither schema can reliably read data written with the other.
+    Breaking,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Compatibility::Compatible => "compatible",
+            Compatibility::Backward => "backward",
+            Compatibility::Forward => "forward",
+            Compatibility::Breaking => "breaking",
+        })
+    }
+}
+
+/// Which readers must keep working after the change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// New readers must read old data, e.g. upgraded code reading existing
+    /// state.
+    Backward,
+    /// Old readers must read new data.
+    Forward,
+    /// Both.
+    Full,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Where the change is, e.g. `Account.balance` or `Status::Frozen`.
+    pub path: String,
+    pub compatibility: Compatibility,
+    pub description: String,
+}
+
+impl Change {
+    pub fn is_breaking(&self, direction: Direction) -> bool {
+        match self.compatibility {
+            Compatibility::Compatible => false,
+            Compatibility::Backward => direction != Direction::Backward,
+            Compatibility::Forward => direction != Direction::Forward,
+            Compatibility::Breaking => true,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<10}  {}: {}",
+            self.compatibility, self.path, self.description,
+        )
+    }
+}
+
+/// Every change between the layouts of `old` and `new`, starting from their
+/// root declarations.
+pub fn compare(old: &BorshSchemaContainer, new: &BorshSchemaContainer) -> Vec<Change> {
+    let mut comparison = Comparison {
+        old,
+        new,
+        in_progress: HashSet::new(),
+        compared: HashMap::new(),
+        changes: Vec::new(),
+    };
+    comparison.compare(
+        &old.declaration,
+        &new.declaration,
+        old.declaration.clone(),
+        true,
+    );
+    comparison.changes
+}
+
+/// Old and new declarations, and whether the value is at the end of the data.
+type Key<'a> = (&'a Declaration, &'a Declaration, bool);
+
+struct Comparison<'a> {
+    old: &'a BorshSchemaContainer,
+    new: &'a BorshSchemaContainer,
+    /// Comparisons being made further up, to stop at recursive types.
+    in_progress: HashSet<Key<'a>>,
+    /// Changes found by earlier comparisons, with paths relative to where
+    /// they were made, so that types used at several paths are reported at
+    /// each of them.
+    compared: HashMap<Key<'a>, Vec<Change>>,
+    changes: Vec<Change>,
+}
+
+impl<'a> Comparison<'a> {
+    fn change(&mut self, path: &str, compatibility: Compatibility, description: String) {
+        self.changes.push(Change {
+            path: path.to_string(),
+            compatibility,
+            description,
+        });
+    }
+
+    /// `at_end` is whether nothing can follow this value in the encoded data,
+    /// which makes appending or removing trailing bytes tolerable.
+    fn compare(
+        &mut self,
+        old_declaration: &'a Declaration,
+        new_declaration: &'a Declaration,
+        path: String,
+        at_end: bool,
+    ) {
+        let key = (old_declaration, new_declaration, at_end);
+        if let Some(changes) = self.compared.get(&key) {
+            let changes = changes
+                .iter()
+                .map(|change| Change {
+                    path: format!("{path}{}", change.path),
+                    ..change.clone()
+                })
+                .collect::<Vec<_>>();
+            self.changes.extend(changes);
+            return;
+        }
+        // Recursive types are compared once
+        if !self.in_progress.insert(key) {
+            return;
+        }
+
+        let start = self.changes.len();
+        self.compare_definitions(old_declaration, new_declaration, &path, at_end);
+        self.in_progress.remove(&key);
+
+        let changes = self.changes[start..]
+            .iter()
+            .map(|change| Change {
+                path: change.path[path.len()..].to_string(),
+                ..change.clone()
+            })
+            .collect();
+        self.compared.insert(key, changes);
+    }
+
+    fn compare_definitions(
+        &mut self,
+        old_declaration: &'a Declaration,
+        new_declaration: &'a Declaration,
+        path: &str,
+        at_end: bool,
+    ) {
+        let (old_definition, new_definition) = match (
+            self.old.definitions.get(old_declaration),
+            self.new.definitions.get(new_declaration),
+        ) {
+            (Some(old), Some(new)) => (old, new),
+            _ => {
+                if old_declaration != new_declaration {
+                    let description = if is_widened(old_declaration, new_declaration) {
+                        format!("integer widened from {old_declaration} to {new_declaration}, which changes its encoded size")
+                    } else {
+                        format!("type changed from {old_declaration} to {new_declaration}")
+                    };
+                    self.change(path, Compatibility::Breaking, description);
+                }
+                return;
+            }
+        };
+
+        if old_declaration != new_declaration
+            && matches!(
+                (old_definition, new_definition),
+                (Definition::Struct { .. }, Definition::Struct { .. })
+                    | (Definition::Enum { .. }, Definition::Enum { .. })
+            )
+        {
+            self.change(
+                path,
+                Compatibility::Compatible,
+                format!("type renamed from {old_declaration} to {new_declaration}"),
+            );
+        }
+
+        match (old_definition, new_definition) {
+            (
+                Definition::Array {
+                    length: old_length,
+                    elements: old_elements,
+                },
+                Definition::Array {
+                    length: new_length,
+                    elements: new_elements,
+                },
+            ) => {
+                if old_length != new_length {
+                    self.change(
+                        path,
+                        Compatibility::Breaking,
+                        format!("array length changed from {old_length} to {new_length}"),
+                    );
+                }
+                self.compare(old_elements, new_elements, format!("{path}[]"), false);
+            }
+            (
+                Definition::Sequence {
+                    elements: old_elements,
+                },
+                Definition::Sequence {
+                    elements: new_elements,
+                },
+            ) => {
+                self.compare(old_elements, new_elements, format!("{path}[]"), false);
+            }
+            (
+                Definition::Tuple {
+                    elements: old_elements,
+                },
+                Definition::Tuple {
+                    elements: new_elements,
+                },
+            ) => {
+                let old_elements = old_elements.iter().collect::<Vec<_>>();
+                let new_elements = new_elements.iter().collect::<Vec<_>>();
+                self.compare_positional(&old_elements, &new_elements, path, at_end);
+            }
+            (Definition::Enum { variants: old }, Definition::Enum { variants: new }) => {
+                self.compare_variants(old, new, path, at_end);
+            }
+            (Definition::Struct { fields: old }, Definition::Struct { fields: new }) => {
+                self.compare_fields(old, new, path, at_end);
+            }
+            (old, new) => self.change(
+                path,
+                Compatibility::Breaking,
+                format!(
+                    "{} {old_declaration} replaced by {} {new_declaration}",
+                    kind(old),
+                    kind(new),
+                ),
+            ),
+        }
+    }
+
+    fn compare_variants(
+        &mut self,
+        old: &'a [(String, Declaration)],
+        new: &'a [(String, Declaration)],
+        path: &str,
+        at_end: bool,
+    ) {
+        let reordered = old.iter().enumerate().any(|(i, (name, _))| {
+            new.iter()
+                .position(|(n, _)| n == name)
+                .is_some_and(|j| j != i)
+        });
+        if reordered {
+            self.change(
+                path,
+                Compatibility::Breaking,
+                "variants reordered, which changes their tags".to_string(),
+            );
+        }
+
+        for (i, ((old_name, old_payload), (new_name, new_payload))) in
+            old.iter().zip(new).enumerate()
+        {
+            if old_name != new_name && !reordered {
+                self.change(
+                    path,
+                    Compatibility::Compatible,
+                    format!("variant {i} renamed from {old_name} to {new_name}"),
+                );
+            }
+            self.compare(
+                old_payload,
+                new_payload,
+                format!("{path}::{new_name}"),
+                at_end,
+            );
+        }
+
+        for (name, _) in new.iter().skip(old.len()) {
+            self.change(
+                path,
+                Compatibility::Backward,
+                format!("variant {name} appended, which old readers cannot decode"),
+            );
+        }
+        for (name, _) in old.iter().skip(new.len()) {
+            self.change(
+                path,
+                Compatibility::Forward,
+                format!("variant {name} removed, so existing data using it cannot be decoded"),
+            );
+        }
+    }
+
+    fn compare_fields(&mut self, old: &'a Fields, new: &'a Fields, path: &str, at_end: bool) {
+        match (old, new) {
+            (Fields::NamedFields(old), Fields::NamedFields(new)) => {
+                let reordered = old.iter().enumerate().any(|(i, (name, _))| {
+                    new.iter()
+                        .position(|(n, _)| n == name)
+                        .is_some_and(|j| j != i)
+                });
+                if reordered {
+                    self.change(
+                        path,
+                        Compatibility::Breaking,
+                        "fields reordered, so values are read into the wrong fields".to_string(),
+                    );
+                }
+
+                for (i, ((old_name, old_field), (new_name, new_field))) in
+                    old.iter().zip(new).enumerate()
+                {
+                    if old_name != new_name && !reordered {
+                        self.change(
+                            path,
+                            Compatibility::Compatible,
+                            format!("field {i} renamed from {old_name} to {new_name}"),
+                        );
+                    }
+                    let last = i + 1 == old.len().max(new.len());
+                    self.compare(
+                        old_field,
+                        new_field,
+                        format!("{path}.{new_name}"),
+                        at_end && last,
+                    );
+                }
+                self.compare_lengths(
+                    &old.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>(),
+                    &new.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>(),
+                    "field",
+                    path,
+                    at_end,
+                );
+            }
+            _ => {
+                let old = unnamed(old);
+                let new = unnamed(new);
+                self.compare_positional(&old, &new, path, at_end);
+            }
+        }
+    }
+
+    fn compare_positional(
+        &mut self,
+        old: &[&'a Declaration],
+        new: &[&'a Declaration],
+        path: &str,
+        at_end: bool,
+    ) {
+        for (i, (old_element, new_element)) in old.iter().zip(new).enumerate() {
+            let last = i + 1 == old.len().max(new.len());
+            self.compare(
+                old_element,
+                new_element,
+                format!("{path}.{i}"),
+                at_end && last,
+            );
+        }
+        let names = |elements: &[&Declaration]| {
+            (0..elements.len())
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+        self.compare_lengths(&names(old), &names(new), "element", path, at_end);
+    }
+
+    fn compare_lengths(
+        &mut self,
+        old: &[String],
+        new: &[String],
+        what: &str,
+        path: &str,
+        at_end: bool,
+    ) {
+        for name in new.iter().skip(old.len()) {
+            let (compatibility, consequence) = if at_end {
+                (
+                    Compatibility::Forward,
+                    "old readers can ignore the trailing bytes, but new readers cannot read old data",
+                )
+            } else {
+                (Compatibility::Breaking, "which shifts every byte after it")
+            };
+            self.change(
+                path,
+                compatibility,
+                format!("{what} {name} appended, {consequence}"),
+            );
+        }
+        for name in old.iter().skip(new.len()) {
+            let (compatibility, consequence) = if at_end {
+                (
+                    Compatibility::Backward,
+                    "new readers can ignore the trailing bytes, but old readers cannot read new data",
+                )
+            } else {
+                (Compatibility::Breaking, "which shifts every byte after it")
+            };
+            self.change(
+                path,
+                compatibility,
+                format!("{what} {name} removed, {consequence}"),
+            );
+        }
+    }
+}
+
+fn unnamed(fields: &Fields) -> Vec<&Declaration> {
+    match fields {
+        Fields::NamedFields(fields) => fields.iter().map(|(_, d)| d).collect(),
+        Fields::UnnamedFields(fields) => fields.iter().collect(),
+        Fields::Empty => vec![],
+    }
+}
+
+fn is_widened(old: &str, new: &str) -> bool {
+    const UNSIGNED: [&str; 5] = ["u8", "u16", "u32", "u64", "u128"];
+    const SIGNED: [&str; 5] = ["i8", "i16", "i32", "i64", "i128"];
+    [UNSIGNED, SIGNED].iter().any(|widths| {
+        match (
+            widths.iter().position(|w| *w == old),
+            widths.iter().position(|w| *w == new),
+        ) {
+            (Some(o), Some(n)) => n > o,
+            _ => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+
+    use super::{compare, Compatibility, Direction};
+
+    #[allow(dead_code)]
+    mod v1 {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Account {
+            pub balance: u32,
+            pub status: Status,
+            pub inner: Inner,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Active,
+            Frozen,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Inner {
+            pub a: u8,
+            pub b: u16,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Points {
+            pub first: Point,
+            pub second: Point,
+            pub last: Point,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Point {
+            pub x: u8,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod v2 {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct AccountV2 {
+            pub amount: u64,
+            pub status: Status,
+            pub inner: Inner,
+            pub memo: String,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Active,
+            Frozen,
+            Closed,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Inner {
+            pub b: u16,
+            pub a: u8,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Points {
+            pub first: Point,
+            pub second: Point,
+            pub last: Point,
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Point {
+            pub x: u8,
+            pub y: u8,
+        }
+    }
+
+    #[test]
+    fn test() {
+        let changes = compare(
+            &v1::Account::schema_container(),
+            &v2::AccountV2::schema_container(),
+        );
+        let summary = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.compatibility))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("Account", Compatibility::Compatible),
+                ("Account", Compatibility::Compatible),
+                ("Account.amount", Compatibility::Breaking),
+                ("Account.status", Compatibility::Backward),
+                ("Account.inner", Compatibility::Breaking),
+                ("Account.inner.b", Compatibility::Breaking),
+                ("Account.inner.a", Compatibility::Breaking),
+                ("Account", Compatibility::Forward),
+            ],
+        );
+        assert!(changes[2].description.contains("widened"));
+
+        // An appended variant only breaks old readers
+        let changes = compare(
+            &v1::Status::schema_container(),
+            &v2::Status::schema_container(),
+        );
+        assert!(!changes[0].is_breaking(Direction::Backward));
+        assert!(changes[0].is_breaking(Direction::Forward));
+        assert!(changes[0].is_breaking(Direction::Full));
+    }
+
+    #[test]
+    fn shared_types() {
+        let changes = compare(
+            &v1::Points::schema_container(),
+            &v2::Points::schema_container(),
+        );
+        let summary = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.compatibility))
+            .collect::<Vec<_>>();
+
+        // A type is reported at every path, only tolerating trailing bytes at
+        // the end
+        assert_eq!(
+            summary,
+            vec![
+                ("Points.first", Compatibility::Breaking),
+                ("Points.second", Compatibility::Breaking),
+                ("Points.last", Compatibility::Forward),
+            ],
+        );
+    }
+}
@@ -0,0 +1,596 @@
+//! Semantic differences between two schemas, definition by definition.
+//!
+//! Unlike [`crate::schema_compat`], every change is listed, including ones
+//! that do not affect the byte layout such as renamed fields.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use serde::Serialize;
+
+use crate::schema_util::kind;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    RootChanged {
+        from: Declaration,
+        to: Declaration,
+    },
+    DefinitionAdded {
+        declaration: Declaration,
+    },
+    DefinitionRemoved {
+        declaration: Declaration,
+    },
+    /// A definition that is identical apart from its declaration.
+    DefinitionRenamed {
+        from: Declaration,
+        to: Declaration,
+    },
+    KindChanged {
+        declaration: Declaration,
+        from: String,
+        to: String,
+    },
+    LengthChanged {
+        declaration: Declaration,
+        from: u32,
+        to: u32,
+    },
+    ElementsRetyped {
+        declaration: Declaration,
+        from: Declaration,
+        to: Declaration,
+    },
+    FieldAdded {
+        declaration: Declaration,
+        field: String,
+        #[serde(rename = "type")]
+        ty: Declaration,
+    },
+    FieldRemoved {
+        declaration: Declaration,
+        field: String,
+        #[serde(rename = "type")]
+        ty: Declaration,
+    },
+    FieldRenamed {
+        declaration: Declaration,
+        from: String,
+        to: String,
+    },
+    FieldRetyped {
+        declaration: Declaration,
+        field: String,
+        from: Declaration,
+        to: Declaration,
+    },
+    FieldsReordered {
+        declaration: Declaration,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    VariantAdded {
+        declaration: Declaration,
+        variant: String,
+        #[serde(rename = "type")]
+        ty: Declaration,
+    },
+    VariantRemoved {
+        declaration: Declaration,
+        variant: String,
+        #[serde(rename = "type")]
+        ty: Declaration,
+    },
+    VariantRenamed {
+        declaration: Declaration,
+        from: String,
+        to: String,
+    },
+    VariantRetyped {
+        declaration: Declaration,
+        variant: String,
+        from: Declaration,
+        to: Declaration,
+    },
+    VariantsReordered {
+        declaration: Declaration,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    /// A variant whose index, and so the tag byte it is encoded with,
+    /// changed, e.g. because a variant before it was removed.
+    VariantTagChanged {
+        declaration: Declaration,
+        variant: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::RootChanged { from, to } => write!(f, "~ root changed from {from} to {to}"),
+            Change::DefinitionAdded { declaration } => write!(f, "+ {declaration}"),
+            Change::DefinitionRemoved { declaration } => write!(f, "- {declaration}"),
+            Change::DefinitionRenamed { from, to } => write!(f, "~ {from} renamed to {to}"),
+            Change::KindChanged {
+                declaration,
+                from,
+                to,
+            } => write!(f, "~ {declaration}: changed from {from} to {to}"),
+            Change::LengthChanged {
+                declaration,
+                from,
+                to,
+            } => write!(f, "~ {declaration}: length changed from {from} to {to}"),
+            Change::ElementsRetyped {
+                declaration,
+                from,
+                to,
+            } => write!(f, "~ {declaration}: elements changed from {from} to {to}"),
+            Change::FieldAdded {
+                declaration,
+                field,
+                ty,
+            } => write!(f, "+ {declaration}.{field}: {ty}"),
+            Change::FieldRemoved {
+                declaration,
+                field,
+                ty,
+            } => write!(f, "- {declaration}.{field}: {ty}"),
+            Change::FieldRenamed {
+                declaration,
+                from,
+                to,
+            } => write!(f, "~ {declaration}.{from} renamed to {to}"),
+            Change::FieldRetyped {
+                declaration,
+                field,
+                from,
+                to,
+            } => write!(f, "~ {declaration}.{field}: {from} -> {to}"),
+            Change::FieldsReordered {
+                declaration,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {declaration}: fields reordered from ({}) to ({})",
+                from.join(", "),
+                to.join(", "),
+            ),
+            Change::VariantAdded {
+                declaration,
+                variant,
+                ty,
+            } => write!(f, "+ {declaration}::{variant}({ty})"),
+            Change::VariantRemoved {
+                declaration,
+                variant,
+                ty,
+            } => write!(f, "- {declaration}::{variant}({ty})"),
+            Change::VariantRenamed {
+                declaration,
+                from,
+                to,
+            } => write!(f, "~ {declaration}::{from} renamed to {to}"),
+            Change::VariantRetyped {
+                declaration,
+                variant,
+                from,
+                to,
+            } => write!(f, "~ {declaration}::{variant}: {from} -> {to}"),
+            Change::VariantsReordered {
+                declaration,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {declaration}: variants reordered from ({}) to ({})",
+                from.join(", "),
+                to.join(", "),
+            ),
+            Change::VariantTagChanged {
+                declaration,
+                variant,
+                from,
+                to,
+            } => write!(f, "~ {declaration}::{variant}: tag {from} -> {to}"),
+        }
+    }
+}
+
+/// Every difference between `a` and `b`. Definitions are matched by
+/// declaration, and listed in sorted order.
+pub fn diff(a: &BorshSchemaContainer, b: &BorshSchemaContainer) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if a.declaration != b.declaration {
+        changes.push(Change::RootChanged {
+            from: a.declaration.clone(),
+            to: b.declaration.clone(),
+        });
+    }
+
+    let mut removed = a
+        .definitions
+        .keys()
+        .filter(|d| !b.definitions.contains_key(*d))
+        .collect::<BTreeSet<_>>();
+    let mut added = b
+        .definitions
+        .keys()
+        .filter(|d| !a.definitions.contains_key(*d))
+        .collect::<BTreeSet<_>>();
+
+    // Pair up identical definitions that only changed declaration
+    let mut renamed = BTreeMap::new();
+    for from in removed.clone() {
+        let to = added
+            .iter()
+            .find(|to| a.definitions[from] == b.definitions[**to])
+            .copied();
+        if let Some(to) = to {
+            removed.remove(from);
+            added.remove(to);
+            renamed.insert(from, to);
+        }
+    }
+
+    let declarations = a
+        .definitions
+        .keys()
+        .chain(b.definitions.keys())
+        .collect::<BTreeSet<_>>();
+    for declaration in declarations {
+        if let Some(to) = renamed.get(declaration) {
+            changes.push(Change::DefinitionRenamed {
+                from: declaration.clone(),
+                to: (*to).clone(),
+            });
+        } else if removed.contains(declaration) {
+            changes.push(Change::DefinitionRemoved {
+                declaration: declaration.clone(),
+            });
+        } else if added.contains(declaration) {
+            changes.push(Change::DefinitionAdded {
+                declaration: declaration.clone(),
+            });
+        } else if let (Some(old), Some(new)) = (
+            a.definitions.get(declaration),
+            b.definitions.get(declaration),
+        ) {
+            diff_definition(declaration, old, new, &mut changes);
+        }
+    }
+
+    changes
+}
+
+fn diff_definition(
+    declaration: &Declaration,
+    old: &Definition,
+    new: &Definition,
+    changes: &mut Vec<Change>,
+) {
+    let elements_retyped = |from: &Declaration, to: &Declaration| Change::ElementsRetyped {
+        declaration: declaration.clone(),
+        from: from.clone(),
+        to: to.clone(),
+    };
+
+    match (old, new) {
+        (
+            Definition::Array {
+                length: old_length,
+                elements: old_elements,
+            },
+            Definition::Array {
+                length: new_length,
+                elements: new_elements,
+            },
+        ) => {
+            if old_length != new_length {
+                changes.push(Change::LengthChanged {
+                    declaration: declaration.clone(),
+                    from: *old_length,
+                    to: *new_length,
+                });
+            }
+            if old_elements != new_elements {
+                changes.push(elements_retyped(old_elements, new_elements));
+            }
+        }
+        (Definition::Sequence { elements: old }, Definition::Sequence { elements: new }) => {
+            if old != new {
+                changes.push(elements_retyped(old, new));
+            }
+        }
+        (Definition::Tuple { elements: old }, Definition::Tuple { elements: new }) => {
+            diff_members(
+                declaration,
+                &indexed(old),
+                &indexed(new),
+                Member::Field,
+                changes,
+            );
+        }
+        (Definition::Enum { variants: old }, Definition::Enum { variants: new }) => {
+            diff_members(declaration, old, new, Member::Variant, changes);
+        }
+        (Definition::Struct { fields: old }, Definition::Struct { fields: new }) => {
+            match (old, new) {
+                (Fields::NamedFields(_), Fields::UnnamedFields(_))
+                | (Fields::UnnamedFields(_), Fields::NamedFields(_)) => {
+                    changes.push(Change::KindChanged {
+                        declaration: declaration.clone(),
+                        from: fields_kind(old).to_string(),
+                        to: fields_kind(new).to_string(),
+                    });
+                }
+                _ => diff_members(
+                    declaration,
+                    &members(old),
+                    &members(new),
+                    Member::Field,
+                    changes,
+                ),
+            }
+        }
+        (old, new) => changes.push(Change::KindChanged {
+            declaration: declaration.clone(),
+            from: kind(old).to_string(),
+            to: kind(new).to_string(),
+        }),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Member {
+    Field,
+    Variant,
+}
+
+/// Compares named members (fields or variants) by name, treating a member
+/// whose name appears on only one side, in the same position, as renamed.
+fn diff_members(
+    declaration: &Declaration,
+    old: &[(String, Declaration)],
+    new: &[(String, Declaration)],
+    member: Member,
+    changes: &mut Vec<Change>,
+) {
+    let in_old = |name: &String| old.iter().any(|(n, _)| n == name);
+    let in_new = |name: &String| new.iter().any(|(n, _)| n == name);
+
+    // Derived variant payloads are named after the variant, so only fields
+    // need to keep their type to count as renamed
+    let mut renamed = Vec::new();
+    for ((old_name, old_type), (new_name, new_type)) in old.iter().zip(new) {
+        let same_type = matches!(member, Member::Variant) || old_type == new_type;
+        if old_name != new_name && !in_new(old_name) && !in_old(new_name) && same_type {
+            renamed.push((old_name, new_name));
+            changes.push(match member {
+                Member::Field => Change::FieldRenamed {
+                    declaration: declaration.clone(),
+                    from: old_name.clone(),
+                    to: new_name.clone(),
+                },
+                Member::Variant => Change::VariantRenamed {
+                    declaration: declaration.clone(),
+                    from: old_name.clone(),
+                    to: new_name.clone(),
+                },
+            });
+        }
+    }
+    let new_name_of = |name: &String| {
+        renamed
+            .iter()
+            .find(|(from, _)| *from == name)
+            .map_or_else(|| name.clone(), |(_, to)| (*to).clone())
+    };
+
+    // Names present on both sides, in their old and new order
+    let old_order = old
+        .iter()
+        .map(|(n, _)| new_name_of(n))
+        .filter(|n| in_new(n))
+        .collect::<Vec<_>>();
+    let new_order = new
+        .iter()
+        .map(|(n, _)| n)
+        .filter(|n| old_order.contains(n))
+        .cloned()
+        .collect::<Vec<_>>();
+    if old_order != new_order {
+        changes.push(match member {
+            Member::Field => Change::FieldsReordered {
+                declaration: declaration.clone(),
+                from: old_order,
+                to: new_order,
+            },
+            Member::Variant => Change::VariantsReordered {
+                declaration: declaration.clone(),
+                from: old_order,
+                to: new_order,
+            },
+        });
+    }
+
+    if let Member::Variant = member {
+        for (from, (old_name, _)) in old.iter().enumerate() {
+            let name = new_name_of(old_name);
+            match new.iter().position(|(n, _)| *n == name) {
+                Some(to) if to != from => changes.push(Change::VariantTagChanged {
+                    declaration: declaration.clone(),
+                    variant: name,
+                    from,
+                    to,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    for (old_name, old_type) in old {
+        let name = new_name_of(old_name);
+        match new.iter().find(|(n, _)| *n == name) {
+            Some((_, new_type)) if new_type != old_type => changes.push(match member {
+                Member::Field => Change::FieldRetyped {
+                    declaration: declaration.clone(),
+                    field: name.clone(),
+                    from: old_type.clone(),
+                    to: new_type.clone(),
+                },
+                Member::Variant => Change::VariantRetyped {
+                    declaration: declaration.clone(),
+                    variant: name.clone(),
+                    from: old_type.clone(),
+                    to: new_type.clone(),
+                },
+            }),
+            Some(_) => {}
+            None => changes.push(match member {
+                Member::Field => Change::FieldRemoved {
+                    declaration: declaration.clone(),
+                    field: old_name.clone(),
+                    ty: old_type.clone(),
+                },
+                Member::Variant => Change::VariantRemoved {
+                    declaration: declaration.clone(),
+                    variant: old_name.clone(),
+                    ty: old_type.clone(),
+                },
+            }),
+        }
+    }
+
+    for (new_name, new_type) in new {
+        let is_new = !in_old(new_name) && !renamed.iter().any(|(_, to)| *to == new_name);
+        if is_new {
+            changes.push(match member {
+                Member::Field => Change::FieldAdded {
+                    declaration: declaration.clone(),
+                    field: new_name.clone(),
+                    ty: new_type.clone(),
+                },
+                Member::Variant => Change::VariantAdded {
+                    declaration: declaration.clone(),
+                    variant: new_name.clone(),
+                    ty: new_type.clone(),
+                },
+            });
+        }
+    }
+}
+
+/// Unnamed fields and tuple elements are named by their index, so they are
+/// never considered renamed or reordered.
+fn indexed(elements: &[Declaration]) -> Vec<(String, Declaration)> {
+    elements
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (i.to_string(), d.clone()))
+        .collect()
+}
+
+fn members(fields: &Fields) -> Vec<(String, Declaration)> {
+    match fields {
+        Fields::NamedFields(fields) => fields.clone(),
+        Fields::UnnamedFields(fields) => indexed(fields),
+        Fields::Empty => vec![],
+    }
+}
+
+fn fields_kind(fields: &Fields) -> &'static str {
+    match fields {
+        Fields::NamedFields(_) => "struct with named fields",
+        Fields::UnnamedFields(_) => "tuple struct",
+        Fields::Empty => "unit struct",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+
+    use super::diff;
+
+    #[allow(dead_code)]
+    mod a {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Account {
+            pub owner: String,
+            pub balance: u32,
+            pub nonce: u64,
+            pub status: Status,
+            pub legacy: bool,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Active,
+            Frozen,
+            Closed,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod b {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Account {
+            pub owner_id: String,
+            pub nonce: u64,
+            pub balance: u64,
+            pub status: Status,
+            pub memo: Option<String>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Active,
+            Closed,
+            Suspended,
+        }
+    }
+
+    #[test]
+    fn test() {
+        let changes = diff(
+            &a::Account::schema_container(),
+            &b::Account::schema_container(),
+        )
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                "~ Account.owner renamed to owner_id",
+                "~ Account: fields reordered from (owner_id, balance, nonce, status) to (owner_id, nonce, balance, status)",
+                "~ Account.balance: u32 -> u64",
+                "- Account.legacy: bool",
+                "+ Account.memo: Option<string>",
+                "+ Option<string>",
+                "~ Status::Closed: tag 2 -> 1",
+                "- Status::Frozen(StatusFrozen)",
+                "+ Status::Suspended(StatusSuspended)",
+                "~ StatusFrozen renamed to StatusSuspended",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>(),
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! Wire-format documentation for a schema, as Markdown or HTML.
+
+use std::{collections::HashMap, fmt::Write};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::{
+    schema_fingerprint::fingerprint,
+    schema_util::{as_map, as_option, dependency_order, fixed_size, primitive_size},
+};
+
+/// Inline text, rendered with the escaping of the output format.
+enum Span {
+    Text(String),
+    Code(String),
+    /// A declaration, linked to its section if it has one.
+    Type(Declaration),
+}
+
+type Line = Vec<Span>;
+
+struct Section {
+    declaration: Declaration,
+    summary: Line,
+    size: Line,
+    table: Option<(Vec<&'static str>, Vec<Vec<Line>>)>,
+}
+
+struct Document {
+    root: Declaration,
+    fingerprint: String,
+    sections: Vec<Section>,
+    /// Section anchors, unique like GitHub's heading anchors.
+    anchors: HashMap<Declaration, String>,
+}
+
+fn text(s: impl Into<String>) -> Span {
+    Span::Text(s.into())
+}
+
+fn code(s: impl Into<String>) -> Span {
+    Span::Code(s.into())
+}
+
+impl Document {
+    fn new(schema: &BorshSchemaContainer) -> Self {
+        let mut order = dependency_order(schema);
+        order.reverse();
+
+        let mut anchors = HashMap::new();
+        let mut counts = HashMap::new();
+        for declaration in &order {
+            let slug = slug(declaration);
+            let count = counts.entry(slug.clone()).or_insert(0);
+            let anchor = match *count {
+                0 => slug,
+                n => format!("{slug}-{n}"),
+            };
+            *count += 1;
+            anchors.insert((*declaration).clone(), anchor);
+        }
+
+        Self {
+            root: schema.declaration.clone(),
+            fingerprint: fingerprint(schema, false),
+            sections: order
+                .into_iter()
+                .map(|declaration| section(schema, declaration))
+                .collect(),
+            anchors,
+        }
+    }
+}
+
+fn section(schema: &BorshSchemaContainer, declaration: &Declaration) -> Section {
+    let definition = &schema.definitions[declaration];
+    let size = |d: &Declaration| match fixed_size(schema, d) {
+        Some(size) => size.to_string(),
+        None => "variable".to_string(),
+    };
+    let field_row = |index: usize, name: Option<&str>, d: &Declaration| {
+        let mut row = vec![vec![text(index.to_string())]];
+        if let Some(name) = name {
+            row.push(vec![code(name)]);
+        }
+        row.extend([
+            vec![Span::Type(d.clone())],
+            encoding(schema, d),
+            vec![text(size(d))],
+        ]);
+        row
+    };
+
+    let (summary, table) = if let Some((key, value)) = as_map(schema, declaration) {
+        (
+            vec![
+                text("Map from "),
+                Span::Type(key.clone()),
+                text(" to "),
+                Span::Type(value.clone()),
+                text(": a u32 little-endian entry count, followed by each key and value, sorted by key."),
+            ],
+            None,
+        )
+    } else if let Some(inner) = as_option(definition) {
+        (
+            vec![
+                text("Optional "),
+                Span::Type(inner.clone()),
+                text(": a 1-byte tag, 0 for None, or 1 for Some followed by the value."),
+            ],
+            None,
+        )
+    } else {
+        match definition {
+            Definition::Array { length, elements } => (
+                vec![
+                    text(format!("Array of {length} ")),
+                    Span::Type(elements.clone()),
+                    text(" elements, one after another, with no length prefix."),
+                ],
+                None,
+            ),
+            Definition::Sequence { elements } => (
+                vec![
+                    text("Sequence of "),
+                    Span::Type(elements.clone()),
+                    text(": a u32 little-endian element count, followed by the elements."),
+                ],
+                None,
+            ),
+            Definition::Tuple { elements } => (
+                vec![text(format!(
+                    "Tuple of {}, encoded in order with no padding.",
+                    count(elements.len() as u64, "element"),
+                ))],
+                Some((
+                    vec!["#", "Type", "Encoding", "Size"],
+                    elements
+                        .iter()
+                        .enumerate()
+                        .map(|(i, d)| field_row(i, None, d))
+                        .collect(),
+                )),
+            ),
+            Definition::Enum { variants } => (
+                vec![text(format!(
+                    "Enum with {}: a 1-byte tag, followed by the payload of that variant.",
+                    count(variants.len() as u64, "variant"),
+                ))],
+                Some((
+                    vec!["Tag", "Variant", "Payload", "Payload size"],
+                    variants
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (name, d))| {
+                            vec![
+                                vec![text(i.to_string())],
+                                vec![code(name)],
+                                vec![Span::Type(d.clone())],
+                                vec![text(size(d))],
+                            ]
+                        })
+                        .collect(),
+                )),
+            ),
+            Definition::Struct {
+                fields: Fields::NamedFields(fields),
+            } => (
+                vec![text(format!(
+                    "Struct with {}, encoded in order with no padding or field names.",
+                    count(fields.len() as u64, "field"),
+                ))],
+                Some((
+                    vec!["#", "Field", "Type", "Encoding", "Size"],
+                    fields
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (name, d))| field_row(i, Some(name), d))
+                        .collect(),
+                )),
+            ),
+            Definition::Struct {
+                fields: Fields::UnnamedFields(elements),
+            } => (
+                vec![text(format!(
+                    "Tuple struct with {}, encoded in order with no padding.",
+                    count(elements.len() as u64, "field"),
+                ))],
+                Some((
+                    vec!["#", "Type", "Encoding", "Size"],
+                    elements
+                        .iter()
+                        .enumerate()
+                        .map(|(i, d)| field_row(i, None, d))
+                        .collect(),
+                )),
+            ),
+            Definition::Struct {
+                fields: Fields::Empty,
+            } => (
+                vec![text("Struct without fields, encoded as 0 bytes.")],
+                None,
+            ),
+        }
+    };
+
+    let size = match fixed_size(schema, declaration) {
+        Some(size) => vec![text(format!("Size: {}.", count(size, "byte")))],
+        None => vec![text("Size: variable.")],
+    };
+
+    Section {
+        declaration: declaration.clone(),
+        summary,
+        size,
+        table,
+    }
+}
+
+fn count(n: impl Into<u64>, noun: &str) -> String {
+    match n.into() {
+        1 => format!("1 {noun}"),
+        n => format!("{n} {noun}s"),
+    }
+}
+
+/// One-line description of how a value of the declaration is encoded.
+fn encoding(schema: &BorshSchemaContainer, declaration: &Declaration) -> Line {
+    let see = |what: &str| {
+        vec![
+            text(format!("{what}, see ")),
+            Span::Type(declaration.clone()),
+        ]
+    };
+
+    match schema.definitions.get(declaration) {
+        None => {
+            let size = primitive_size(declaration).unwrap_or_default();
+            let order = if size > 1 { ", little-endian" } else { "" };
+            vec![text(match declaration.as_str() {
+                "bool" => "1 byte, 0 for false or 1 for true".to_string(),
+                "string" => "u32 little-endian byte length, followed by UTF-8 bytes".to_string(),
+                "nil" => "nothing".to_string(),
+                "f32" | "f64" => format!("{size}-byte IEEE 754 float{order}"),
+                d if d.starts_with('u') => format!("{size}-byte unsigned integer{order}"),
+                d if d.starts_with('i') => format!("{size}-byte two's complement integer{order}"),
+                _ => "unknown".to_string(),
+            })]
+        }
+        Some(_) if as_map(schema, declaration).is_some() => {
+            see("u32 little-endian entry count, followed by entries sorted by key")
+        }
+        Some(definition) if as_option(definition).is_some() => {
+            see("1-byte tag (0 = None, 1 = Some), followed by the value if Some")
+        }
+        Some(Definition::Array { length, .. }) => {
+            see(&format!("{length} elements with no length prefix"))
+        }
+        Some(Definition::Sequence { .. }) => {
+            see("u32 little-endian element count, followed by the elements")
+        }
+        Some(Definition::Enum { .. }) => see("1-byte tag, followed by the variant's payload"),
+        Some(Definition::Tuple { .. } | Definition::Struct { .. }) => see("fields in order"),
+    }
+}
+
+/// GitHub's heading anchor for a heading consisting of `text` in backticks.
+fn slug(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Markdown with a section per definition reachable from the root.
+pub fn markdown(schema: &BorshSchemaContainer) -> String {
+    let document = Document::new(schema);
+    let line = |spans: &Line, in_table: bool| {
+        let mut out = String::new();
+        for span in spans {
+            match span {
+                Span::Text(s) => out.push_str(s),
+                Span::Code(s) => write!(out, "`{s}`").unwrap(),
+                Span::Type(d) => match document.anchors.get(d) {
+                    Some(anchor) => write!(out, "[`{d}`](#{anchor})").unwrap(),
+                    None => write!(out, "`{d}`").unwrap(),
+                },
+            }
+        }
+        if in_table {
+            out.replace('|', "\\|")
+        } else {
+            out
+        }
+    };
+
+    let mut out = String::new();
+    writeln!(out, "# `{}` wire format", document.root).unwrap();
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "Values are encoded with [Borsh](https://borsh.io). Integers are little-endian, and there is no padding between fields.",
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "Schema fingerprint: `{}`", document.fingerprint).unwrap();
+
+    for section in &document.sections {
+        writeln!(out).unwrap();
+        writeln!(out, "## `{}`", section.declaration).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "{}", line(&section.summary, false)).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "{}", line(&section.size, false)).unwrap();
+
+        if let Some((headers, rows)) = &section.table {
+            writeln!(out).unwrap();
+            writeln!(out, "| {} |", headers.join(" | ")).unwrap();
+            writeln!(out, "|{}", "---|".repeat(headers.len())).unwrap();
+            for row in rows {
+                let cells = row.iter().map(|c| line(c, true)).collect::<Vec<_>>();
+                writeln!(out, "| {} |", cells.join(" | ")).unwrap();
+            }
+        }
+    }
+    out
+}
+
+/// Standalone HTML page with the same content as [`markdown`].
+pub fn html(schema: &BorshSchemaContainer) -> String {
+    let document = Document::new(schema);
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    let line = |spans: &Line| {
+        let mut out = String::new();
+        for span in spans {
+            match span {
+                Span::Text(s) => out.push_str(&escape(s)),
+                Span::Code(s) => write!(out, "<code>{}</code>", escape(s)).unwrap(),
+                Span::Type(d) => match document.anchors.get(d) {
+                    Some(anchor) => {
+                        write!(out, "<a href=\"#{anchor}\"><code>{}</code></a>", escape(d),)
+                            .unwrap()
+                    }
+                    None => write!(out, "<code>{}</code>", escape(d)).unwrap(),
+                },
+            }
+        }
+        out
+    };
+
+    let root = escape(&document.root);
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html>").unwrap();
+    writeln!(out, "<head>").unwrap();
+    writeln!(out, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>{root} wire format</title>").unwrap();
+    writeln!(out, "</head>").unwrap();
+    writeln!(out, "<body>").unwrap();
+    writeln!(out, "<h1><code>{root}</code> wire format</h1>").unwrap();
+    writeln!(
+        out,
+        "<p>Values are encoded with <a href=\"https://borsh.io\">Borsh</a>. Integers are little-endian, and there is no padding between fields.</p>",
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<p>Schema fingerprint: <code>{}</code></p>",
+        document.fingerprint,
+    )
+    .unwrap();
+
+    for section in &document.sections {
+        writeln!(
+            out,
+            "<h2 id=\"{}\"><code>{}</code></h2>",
+            document.anchors[&section.declaration],
+            escape(&section.declaration),
+        )
+        .unwrap();
+        writeln!(out, "<p>{}</p>", line(&section.summary)).unwrap();
+        writeln!(out, "<p>{}</p>", line(&section.size)).unwrap();
+
+        if let Some((headers, rows)) = &section.table {
+            writeln!(out, "<table>").unwrap();
+            let headers = headers.iter().map(|h| format!("<th>{}</th>", escape(h)));
+            writeln!(out, "<tr>{}</tr>", headers.collect::<String>()).unwrap();
+            for row in rows {
+                let cells = row.iter().map(|c| format!("<td>{}</td>", line(c)));
+                writeln!(out, "<tr>{}</tr>", cells.collect::<String>()).unwrap();
+            }
+            writeln!(out, "</table>").unwrap();
+        }
+    }
+    writeln!(out, "</body>").unwrap();
+    writeln!(out, "</html>").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+
+    use super::{html, markdown};
+    use fixtures::Account;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Account {
+            owner: [u8; 32],
+            balance: u64,
+            memo: Option<String>,
+            status: Status,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Active,
+            Frozen { until: u64 },
+        }
+    }
+
+    #[test]
+    fn test() {
+        let schema = Account::schema_container();
+        let doc = markdown(&schema);
+
+        for expected in [
+            "## `Account`",
+            "| 0 | `owner` | [`Array<u8, 32>`](#arrayu8-32) | 32 elements with no length prefix, see [`Array<u8, 32>`](#arrayu8-32) | 32 |",
+            "| 1 | `balance` | `u64` | 8-byte unsigned integer, little-endian | 8 |",
+            "| 2 | `memo` | [`Option<string>`](#optionstring) | 1-byte tag (0 = None, 1 = Some), followed by the value if Some, see [`Option<string>`](#optionstring) | variable |",
+            "| 1 | `Frozen` | [`StatusFrozen`](#statusfrozen) | 8 |",
+            "## `StatusFrozen`\n\nStruct with 1 field, encoded in order with no padding or field names.\n\nSize: 8 bytes.",
+        ] {
+            assert!(doc.contains(expected), "missing {expected:?} in:\n{doc}");
+        }
+
+        assert!(html(&schema)
+            .contains("<h2 id=\"optionstring\"><code>Option&lt;string&gt;</code></h2>"));
+    }
+}
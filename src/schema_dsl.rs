@@ -0,0 +1,668 @@
+//! A small Rust-like language for writing Borsh schemas by hand.
+//!
+//! ```text
+//! // The first type is the root, unless another is chosen
+//! struct Account {
+//!     owner: [u8; 32],
+//!     balance: u128,
+//!     tokens: HashMap<String, u64>,
+//!     status: Status,
+//! }
+//!
+//! enum Status {
+//!     Active,
+//!     Frozen { until: Option<u64> },
+//!     Closed(String),
+//! }
+//!
+//! struct Pair(u8, u16);
+//! struct Marker;
+//! ```
+//!
+//! Compiling produces the same declarations and definitions as
+//! `#[derive(BorshSchema)]` would for the equivalent Rust types.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use thiserror::Error;
+
+use crate::{
+    schema_util::{generic_arity, primitive_declaration, SchemaBuilder},
+    schema_validate::{validate, Severity},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("{position}: {message}")]
+pub struct DslError {
+    pub position: Position,
+    pub message: String,
+}
+
+impl DslError {
+    fn new(position: Position, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+/// Compiles schema source into a container. The root declaration is `root`
+/// if given, otherwise the first type in the source.
+pub fn compile(source: &str, root: Option<&str>) -> Result<BorshSchemaContainer, DslError> {
+    let tokens = tokenize(source)?;
+    let items = Parser {
+        tokens: &tokens,
+        index: 0,
+    }
+    .parse_items()?;
+
+    let mut compiler = Compiler {
+        items: HashMap::new(),
+        schema: SchemaBuilder::default(),
+    };
+    for item in &items {
+        if compiler.items.insert(&item.name, item).is_some() {
+            return Err(DslError::new(
+                item.position,
+                format!("type {} is defined more than once", item.name),
+            ));
+        }
+    }
+    for item in &items {
+        compiler.compile_item(item)?;
+    }
+
+    let declaration = match root {
+        Some(root) if compiler.items.contains_key(root) => root.to_string(),
+        Some(root) => {
+            return Err(DslError::new(
+                Position { line: 1, column: 1 },
+                format!("root type {root} is not defined"),
+            ))
+        }
+        None => items
+            .first()
+            .map(|item| item.name.clone())
+            .ok_or_else(|| DslError::new(Position { line: 1, column: 1 }, "no types defined"))?,
+    };
+
+    let schema = BorshSchemaContainer {
+        declaration,
+        definitions: compiler.schema.definitions,
+    };
+    // Problems that only show in the whole schema, like recursion without a
+    // base case
+    if let Some(issue) = validate(&schema)
+        .into_iter()
+        .find(|issue| issue.severity == Severity::Error)
+    {
+        let position = compiler
+            .items
+            .get(&issue.declaration[..])
+            .map_or(Position { line: 1, column: 1 }, |item| item.position);
+        return Err(DslError::new(
+            position,
+            format!("{} {}", issue.declaration, issue.message),
+        ));
+    }
+    Ok(schema)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Number(u32),
+    Punct(char),
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(i) => write!(f, "`{i}`"),
+            TokenKind::Number(n) => write!(f, "`{n}`"),
+            TokenKind::Punct(c) => write!(f, "`{c}`"),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    position: Position,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, DslError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut position = Position { line: 1, column: 1 };
+
+    fn advance(chars: &mut std::iter::Peekable<std::str::Chars>, position: &mut Position) {
+        if chars.next() == Some('\n') {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+
+    while let Some(&c) = chars.peek() {
+        let start = position;
+        match c {
+            _ if c.is_whitespace() => advance(&mut chars, &mut position),
+            '/' => {
+                advance(&mut chars, &mut position);
+                if chars.peek() != Some(&'/') {
+                    return Err(DslError::new(start, "unexpected `/`"));
+                }
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    advance(&mut chars, &mut position);
+                }
+            }
+            '{' | '}' | '(' | ')' | '[' | ']' | '<' | '>' | ',' | ';' | ':' => {
+                advance(&mut chars, &mut position);
+                tokens.push(Token {
+                    kind: TokenKind::Punct(c),
+                    position: start,
+                });
+            }
+            _ if c.is_ascii_digit() => {
+                let mut digits = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '_') {
+                    if c != '_' {
+                        digits.push(c);
+                    }
+                    advance(&mut chars, &mut position);
+                }
+                let n = digits
+                    .parse()
+                    .map_err(|_| DslError::new(start, "number does not fit in u32"))?;
+                tokens.push(Token {
+                    kind: TokenKind::Number(n),
+                    position: start,
+                });
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                    ident.push(c);
+                    advance(&mut chars, &mut position);
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Ident(ident),
+                    position: start,
+                });
+            }
+            _ => return Err(DslError::new(start, format!("unexpected character `{c}`"))),
+        }
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        position,
+    });
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+struct Item {
+    name: String,
+    position: Position,
+    kind: ItemKind,
+}
+
+#[derive(Debug)]
+enum ItemKind {
+    Struct(FieldsAst),
+    Enum(Vec<(String, FieldsAst)>),
+}
+
+#[derive(Debug)]
+enum FieldsAst {
+    Named(Vec<(String, TypeAst)>),
+    Unnamed(Vec<TypeAst>),
+    Empty,
+}
+
+#[derive(Debug)]
+enum TypeAst {
+    Path {
+        name: String,
+        arguments: Vec<TypeAst>,
+        position: Position,
+    },
+    Array(Box<TypeAst>, u32),
+    Tuple(Vec<TypeAst>),
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    index: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index]
+    }
+
+    fn next(&mut self) -> &Token {
+        let token = &self.tokens[self.index];
+        if token.kind != TokenKind::Eof {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> DslError {
+        let token = self.peek();
+        DslError::new(
+            token.position,
+            format!("expected {expected}, found {}", token.kind),
+        )
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        if self.peek().kind == TokenKind::Punct(c) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), DslError> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{c}`")))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(&self.peek().kind, TokenKind::Ident(i) if i == keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<(String, Position), DslError> {
+        match &self.peek().kind {
+            TokenKind::Ident(i) => {
+                let result = (i.clone(), self.peek().position);
+                self.next();
+                Ok(result)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    fn parse_items(&mut self) -> Result<Vec<Item>, DslError> {
+        let mut items = Vec::new();
+        while self.peek().kind != TokenKind::Eof {
+            self.eat_keyword("pub");
+            let (name, position, kind) = if self.eat_keyword("struct") {
+                let (name, position) = self.expect_ident()?;
+                let fields = self.parse_fields()?;
+                if !matches!(fields, FieldsAst::Named(_)) {
+                    self.expect_punct(';')?;
+                }
+                (name, position, ItemKind::Struct(fields))
+            } else if self.eat_keyword("enum") {
+                let (name, position) = self.expect_ident()?;
+                self.expect_punct('{')?;
+                let mut variants = Vec::new();
+                let mut names = HashSet::new();
+                while !self.eat_punct('}') {
+                    let (variant, position) = self.expect_ident()?;
+                    if !names.insert(variant.clone()) {
+                        return Err(DslError::new(
+                            position,
+                            format!("variant {variant} is declared more than once"),
+                        ));
+                    }
+                    variants.push((variant, self.parse_fields()?));
+                    if !self.eat_punct(',') {
+                        self.expect_punct('}')?;
+                        break;
+                    }
+                }
+                (name, position, ItemKind::Enum(variants))
+            } else {
+                return Err(self.unexpected("`struct` or `enum`"));
+            };
+            items.push(Item {
+                name,
+                position,
+                kind,
+            });
+        }
+        Ok(items)
+    }
+
+    fn parse_fields(&mut self) -> Result<FieldsAst, DslError> {
+        if self.eat_punct('{') {
+            let mut fields = Vec::new();
+            let mut names = HashSet::new();
+            while !self.eat_punct('}') {
+                self.eat_keyword("pub");
+                let (name, position) = self.expect_ident()?;
+                if !names.insert(name.clone()) {
+                    return Err(DslError::new(
+                        position,
+                        format!("field {name} is declared more than once"),
+                    ));
+                }
+                self.expect_punct(':')?;
+                fields.push((name, self.parse_type()?));
+                if !self.eat_punct(',') {
+                    self.expect_punct('}')?;
+                    break;
+                }
+            }
+            Ok(FieldsAst::Named(fields))
+        } else if self.eat_punct('(') {
+            let mut fields = Vec::new();
+            while !self.eat_punct(')') {
+                self.eat_keyword("pub");
+                fields.push(self.parse_type()?);
+                if !self.eat_punct(',') {
+                    self.expect_punct(')')?;
+                    break;
+                }
+            }
+            Ok(FieldsAst::Unnamed(fields))
+        } else {
+            Ok(FieldsAst::Empty)
+        }
+    }
+
+    fn parse_type(&mut self) -> Result<TypeAst, DslError> {
+        if self.eat_punct('[') {
+            let element = self.parse_type()?;
+            self.expect_punct(';')?;
+            let length = match self.peek().kind {
+                TokenKind::Number(n) => n,
+                _ => return Err(self.unexpected("array length")),
+            };
+            self.next();
+            self.expect_punct(']')?;
+            Ok(TypeAst::Array(Box::new(element), length))
+        } else if self.eat_punct('(') {
+            let mut elements = Vec::new();
+            let mut trailing_comma = false;
+            while !self.eat_punct(')') {
+                elements.push(self.parse_type()?);
+                trailing_comma = self.eat_punct(',');
+                if !trailing_comma {
+                    self.expect_punct(')')?;
+                    break;
+                }
+            }
+            // `(T)` is just `T`, but `(T,)` is a one-element tuple
+            if elements.len() == 1 && !trailing_comma {
+                Ok(elements.pop().unwrap())
+            } else {
+                Ok(TypeAst::Tuple(elements))
+            }
+        } else {
+            if !matches!(self.peek().kind, TokenKind::Ident(_)) {
+                return Err(self.unexpected("type"));
+            }
+            let (name, position) = self.expect_ident()?;
+            let mut arguments = Vec::new();
+            if self.eat_punct('<') {
+                loop {
+                    arguments.push(self.parse_type()?);
+                    if !self.eat_punct(',') {
+                        break;
+                    }
+                    if self.peek().kind == TokenKind::Punct('>') {
+                        break;
+                    }
+                }
+                self.expect_punct('>')?;
+            }
+            Ok(TypeAst::Path {
+                name,
+                arguments,
+                position,
+            })
+        }
+    }
+}
+
+struct Compiler<'a> {
+    items: HashMap<&'a str, &'a Item>,
+    schema: SchemaBuilder,
+}
+
+impl Compiler<'_> {
+    fn compile_item(&mut self, item: &Item) -> Result<(), DslError> {
+        let definition = match &item.kind {
+            ItemKind::Struct(fields) => Definition::Struct {
+                fields: self.compile_fields(fields)?,
+            },
+            ItemKind::Enum(variants) => {
+                let mut compiled = Vec::with_capacity(variants.len());
+                for (variant, fields) in variants {
+                    // The derive macro defines a struct for each variant
+                    let declaration = format!("{}{variant}", item.name);
+                    if self.items.contains_key(&declaration[..]) {
+                        return Err(DslError::new(
+                            item.position,
+                            format!(
+                                "variant {variant} of {} is defined as a struct named \
+                                 {declaration}, which is also a type",
+                                item.name,
+                            ),
+                        ));
+                    }
+                    let definition = Definition::Struct {
+                        fields: self.compile_fields(fields)?,
+                    };
+                    self.schema.add_definition(&declaration, definition);
+                    compiled.push((variant.clone(), declaration));
+                }
+                if compiled.len() > 256 {
+                    return Err(DslError::new(
+                        item.position,
+                        format!("enum {} has more than 256 variants", item.name),
+                    ));
+                }
+                Definition::Enum { variants: compiled }
+            }
+        };
+        self.schema.add_definition(&item.name, definition);
+        Ok(())
+    }
+
+    fn compile_fields(&mut self, fields: &FieldsAst) -> Result<Fields, DslError> {
+        Ok(match fields {
+            FieldsAst::Named(fields) if !fields.is_empty() => {
+                let mut compiled = Vec::with_capacity(fields.len());
+                for (name, ty) in fields {
+                    compiled.push((name.clone(), self.compile_type(ty)?));
+                }
+                Fields::NamedFields(compiled)
+            }
+            FieldsAst::Unnamed(fields) if !fields.is_empty() => {
+                let mut compiled = Vec::with_capacity(fields.len());
+                for ty in fields {
+                    compiled.push(self.compile_type(ty)?);
+                }
+                Fields::UnnamedFields(compiled)
+            }
+            _ => Fields::Empty,
+        })
+    }
+
+    fn compile_type(&mut self, ty: &TypeAst) -> Result<Declaration, DslError> {
+        match ty {
+            TypeAst::Array(element, length) => {
+                let elements = self.compile_type(element)?;
+                Ok(self.schema.array(elements, *length))
+            }
+            TypeAst::Tuple(elements) if elements.is_empty() => Ok("nil".to_string()),
+            TypeAst::Tuple(elements) => {
+                let mut compiled = Vec::with_capacity(elements.len());
+                for element in elements {
+                    compiled.push(self.compile_type(element)?);
+                }
+                Ok(self.schema.tuple(compiled))
+            }
+            TypeAst::Path {
+                name,
+                arguments,
+                position,
+            } => {
+                let arity = |expected: usize| {
+                    if arguments.len() == expected {
+                        Ok(())
+                    } else {
+                        Err(DslError::new(
+                            *position,
+                            format!(
+                                "{name} takes {expected} type argument(s) but {} were given",
+                                arguments.len(),
+                            ),
+                        ))
+                    }
+                };
+
+                if let Some(expected) = generic_arity(name) {
+                    arity(expected)?;
+                    let mut compiled = Vec::with_capacity(arguments.len());
+                    for argument in arguments {
+                        compiled.push(self.compile_type(argument)?);
+                    }
+                    return Ok(self.schema.generic(name, compiled));
+                }
+                let declaration = match primitive_declaration(name) {
+                    Some(declaration) => declaration,
+                    None if name == "string" => name.clone(),
+                    None if self.items.contains_key(&name[..]) => name.clone(),
+                    None => return Err(DslError::new(*position, format!("unknown type {name}"))),
+                };
+                arity(0)?;
+                Ok(declaration)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+
+    use super::{compile, DslError, Position};
+    use fixtures::First;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use std::collections::HashMap;
+
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct First {
+            a: (u32, u64),
+            b: String,
+            c: Second,
+            d: HashMap<String, Option<bool>>,
+            e: Vec<[u8; 4]>,
+            f: Result<u8, String>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Second {
+            Alpha { field: u32 },
+            Beta(u32),
+            Gamma,
+        }
+    }
+
+    #[test]
+    fn matches_derive() {
+        let source = "
+            // Root type
+            pub struct First {
+                a: (u32, u64),
+                b: String,
+                c: Second,
+                d: HashMap<String, Option<bool>>,
+                pub e: Vec<[u8; 4]>,
+                f: Result<u8, String>,
+            }
+
+            enum Second {
+                Alpha { field: u32 },
+                Beta(u32),
+                Gamma,
+            }
+        ";
+
+        assert_eq!(compile(source, None).unwrap(), First::schema_container());
+        assert_eq!(
+            compile(source, Some("Second")).unwrap().declaration,
+            "Second"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            compile("struct A {\n    b: Missing,\n}", None).unwrap_err(),
+            DslError {
+                position: Position { line: 2, column: 8 },
+                message: "unknown type Missing".to_string(),
+            },
+        );
+        assert_eq!(
+            compile("struct A {\n    b: u8\n    c: u8,\n}", None)
+                .unwrap_err()
+                .to_string(),
+            "3:5: expected `}`, found `c`",
+        );
+        assert_eq!(
+            compile("struct A {\n    x: u8,\n    x: u16,\n}", None)
+                .unwrap_err()
+                .to_string(),
+            "3:5: field x is declared more than once",
+        );
+        assert_eq!(
+            compile("enum A {\n    B,\n    B(u8),\n}", None)
+                .unwrap_err()
+                .to_string(),
+            "3:5: variant B is declared more than once",
+        );
+        assert_eq!(
+            compile("struct B;\nstruct A {\n    a: A,\n}", Some("A"))
+                .unwrap_err()
+                .to_string(),
+            "2:8: A is recursive without a base case, so every value would be infinitely large",
+        );
+        assert!(compile("enum A {\n    B,\n}\nstruct AB;", None).is_err());
+        // Recursion through an option has a base case
+        assert!(compile("struct A {\n    a: Option<Box<A>>,\n}", None).is_ok());
+    }
+}
@@ -0,0 +1,161 @@
+//! Canonical form and hash of a schema, for comparing schemas by identity.
+
+use std::collections::HashMap;
+
+use borsh::{
+    schema::{BorshSchemaContainer, Declaration, Definition, Fields},
+    BorshSerialize,
+};
+use sha2::{Digest, Sha256};
+
+use crate::schema_util::dependency_order;
+
+/// A copy of the schema with only the definitions reachable from the root.
+///
+/// Definitions are always serialized sorted by declaration, so the result
+/// only differs from another normalized schema if the layouts or names
+/// differ. With `erase_names`, types are renamed `T0`, `T1`, … in dependency
+/// order and variants `V0`, `V1`, …, field names are dropped, and tuples
+/// become tuple structs, so that only the layout counts.
+pub fn normalize(schema: &BorshSchemaContainer, erase_names: bool) -> BorshSchemaContainer {
+    let order = dependency_order(schema);
+    let names = order
+        .iter()
+        .enumerate()
+        .filter(|_| erase_names)
+        .map(|(i, d)| (*d, format!("T{i}")))
+        .collect::<HashMap<_, _>>();
+    let rename = |d: &Declaration| names.get(d).cloned().unwrap_or_else(|| d.clone());
+
+    let definitions = order
+        .iter()
+        .map(|declaration| {
+            let definition = match &schema.definitions[*declaration] {
+                Definition::Array { length, elements } => Definition::Array {
+                    length: *length,
+                    elements: rename(elements),
+                },
+                Definition::Sequence { elements } => Definition::Sequence {
+                    elements: rename(elements),
+                },
+                Definition::Enum { variants } => Definition::Enum {
+                    variants: variants
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (name, d))| {
+                            let name = if erase_names {
+                                format!("V{i}")
+                            } else {
+                                name.clone()
+                            };
+                            (name, rename(d))
+                        })
+                        .collect(),
+                },
+                Definition::Tuple { elements } if !erase_names => Definition::Tuple {
+                    elements: elements.clone(),
+                },
+                Definition::Struct {
+                    fields: Fields::NamedFields(fields),
+                } if !erase_names => Definition::Struct {
+                    fields: Fields::NamedFields(fields.clone()),
+                },
+                Definition::Tuple { elements }
+                | Definition::Struct {
+                    fields: Fields::UnnamedFields(elements),
+                } => unnamed_struct(elements.iter().map(rename).collect()),
+                Definition::Struct {
+                    fields: Fields::NamedFields(fields),
+                } => unnamed_struct(fields.iter().map(|(_, d)| rename(d)).collect()),
+                Definition::Struct {
+                    fields: Fields::Empty,
+                } => unnamed_struct(vec![]),
+            };
+            (rename(declaration), definition)
+        })
+        .collect();
+
+    BorshSchemaContainer {
+        declaration: rename(&schema.declaration),
+        definitions,
+    }
+}
+
+fn unnamed_struct(elements: Vec<Declaration>) -> Definition {
+    Definition::Struct {
+        fields: if elements.is_empty() {
+            Fields::Empty
+        } else {
+            Fields::UnnamedFields(elements)
+        },
+    }
+}
+
+/// Hex-encoded SHA-256 of the Borsh-serialized normalized schema.
+pub fn fingerprint(schema: &BorshSchemaContainer, erase_names: bool) -> String {
+    let bytes = normalize(schema, erase_names)
+        .try_to_vec()
+        .expect("serializing to memory cannot fail");
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{schema::Definition, BorshSchema};
+
+    use super::{fingerprint, normalize};
+
+    #[allow(dead_code)]
+    mod a {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Account {
+            pub owner: String,
+            pub balance: u64,
+            pub tags: Vec<Tag>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Tag {
+            Frozen,
+            Limit(u32),
+        }
+    }
+
+    #[allow(dead_code)]
+    mod b {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Wallet(pub String, pub u64, pub Vec<Label>);
+
+        #[derive(BorshSchema)]
+        pub enum Label {
+            Locked,
+            Cap(u32),
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut schema = a::Account::schema_container();
+        let original = fingerprint(&schema, false);
+        schema.definitions.insert(
+            "Unused".to_string(),
+            Definition::Sequence {
+                elements: "u8".to_string(),
+            },
+        );
+        assert_eq!(fingerprint(&schema, false), original);
+        assert_eq!(normalize(&schema, false).definitions.len(), 5);
+
+        let other = b::Wallet::schema_container();
+        assert_ne!(fingerprint(&other, false), original);
+        assert_eq!(fingerprint(&other, true), fingerprint(&schema, true));
+        assert_eq!(normalize(&other, true).declaration, "T4");
+    }
+}
@@ -0,0 +1,573 @@
+//! Builds a `BorshSchemaContainer` from Rust source code, mirroring what
+//! `#[derive(BorshSchema)]` would generate, without compiling anything.
+//!
+//! Types are collected from every struct and enum that derives `BorshSchema`
+//! or `BorshSerialize`. Fields marked `#[borsh_skip]` are left out, and
+//! generic types are instantiated with the type arguments they are used with,
+//! starting from the chosen root type.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use syn::{Attribute, GenericArgument, Item, Meta, NestedMeta, PathArguments, Type, TypePath};
+use thiserror::Error;
+
+use crate::schema_util::{generic_arity, primitive_declaration, SchemaBuilder};
+
+/// Polymorphic recursion, e.g. `struct A<T>(Option<Box<A<Vec<T>>>>)`, would
+/// otherwise instantiate types forever.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum FromRustError {
+    #[error("Failed to read {0}")]
+    Read(String),
+    #[error("Failed to parse {0}: {1}")]
+    Parse(String, String),
+    #[error("Unknown type {0}")]
+    UnknownType(String),
+    #[error("Ambiguous type {0}, qualify it with a module path")]
+    AmbiguousType(String),
+    #[error("Unsupported type {0}")]
+    UnsupportedType(String),
+    #[error("Type {0} expects {1} type argument(s)")]
+    TypeArguments(String, usize),
+    #[error("Type {0} is nested too deeply")]
+    TooDeep(String),
+}
+
+/// A struct or enum found in the source, along with the module it is in.
+pub struct TypeItem {
+    module: Vec<String>,
+    ident: String,
+    type_params: Vec<String>,
+    kind: TypeItemKind,
+}
+
+enum TypeItemKind {
+    Struct(syn::Fields),
+    Enum(Vec<syn::Variant>),
+}
+
+/// Collects types from a single `.rs` file, or from every `.rs` file under a
+/// directory. Module paths are derived from file paths relative to the
+/// directory, so it is best to point this at a crate's `src/`.
+pub fn collect(path: &Path) -> Result<Vec<TypeItem>, FromRustError> {
+    let mut items = Vec::new();
+    if path.is_dir() {
+        collect_dir(path, &mut Vec::new(), &mut items)?;
+    } else {
+        collect_file(path, Vec::new(), &mut items)?;
+    }
+    Ok(items)
+}
+
+fn collect_dir(
+    dir: &Path,
+    module: &mut Vec<String>,
+    items: &mut Vec<TypeItem>,
+) -> Result<(), FromRustError> {
+    let read_error = || FromRustError::Read(dir.display().to_string());
+    let mut entries = fs::read_dir(dir)
+        .map_err(|_| read_error())?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| read_error())?;
+    entries.sort();
+
+    for path in entries {
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        if path.is_dir() {
+            module.push(name);
+            collect_dir(&path, module, items)?;
+            module.pop();
+        } else if let Some(stem) = name.strip_suffix(".rs") {
+            let mut file_module = module.clone();
+            if !matches!(stem, "lib" | "main" | "mod") {
+                file_module.push(stem.to_string());
+            }
+            collect_file(&path, file_module, items)?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_file(
+    path: &Path,
+    module: Vec<String>,
+    items: &mut Vec<TypeItem>,
+) -> Result<(), FromRustError> {
+    let source =
+        fs::read_to_string(path).map_err(|_| FromRustError::Read(path.display().to_string()))?;
+    let file = syn::parse_file(&source)
+        .map_err(|e| FromRustError::Parse(path.display().to_string(), e.to_string()))?;
+    collect_items(&file.items, module, items);
+    Ok(())
+}
+
+/// Collects types from already-parsed items, including inline modules.
+pub fn collect_items(source_items: &[Item], module: Vec<String>, items: &mut Vec<TypeItem>) {
+    for item in source_items {
+        match item {
+            Item::Struct(s) if derives_borsh(&s.attrs) => items.push(TypeItem {
+                module: module.clone(),
+                ident: s.ident.to_string(),
+                type_params: s
+                    .generics
+                    .type_params()
+                    .map(|p| p.ident.to_string())
+                    .collect(),
+                kind: TypeItemKind::Struct(s.fields.clone()),
+            }),
+            Item::Enum(e) if derives_borsh(&e.attrs) => items.push(TypeItem {
+                module: module.clone(),
+                ident: e.ident.to_string(),
+                type_params: e
+                    .generics
+                    .type_params()
+                    .map(|p| p.ident.to_string())
+                    .collect(),
+                kind: TypeItemKind::Enum(e.variants.iter().cloned().collect()),
+            }),
+            Item::Mod(m) => {
+                if let Some((_, content)) = &m.content {
+                    let mut inner = module.clone();
+                    inner.push(m.ident.to_string());
+                    collect_items(content, inner, items);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn derives_borsh(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "BorshSchema" || s.ident == "BorshSerialize"),
+            _ => false,
+        })
+}
+
+fn is_skipped(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident("borsh_skip")
+            || (attr.path.is_ident("borsh") && attr.tokens.to_string().replace(' ', "") == "(skip)")
+    })
+}
+
+/// Builds the schema for `root`, a Rust type such as `Wrapper<u64>` that is
+/// resolved from the crate root module.
+pub fn build(items: &[TypeItem], root: &str) -> Result<BorshSchemaContainer, FromRustError> {
+    let root_type = syn::parse_str::<Type>(root)
+        .map_err(|e| FromRustError::Parse(root.to_string(), e.to_string()))?;
+
+    let mut builder = Builder {
+        items,
+        schema: SchemaBuilder::default(),
+        depth: 0,
+    };
+    let declaration = builder.declaration(&root_type, &[], &HashMap::new())?;
+
+    Ok(BorshSchemaContainer {
+        declaration,
+        definitions: builder.schema.definitions,
+    })
+}
+
+struct Builder<'a> {
+    items: &'a [TypeItem],
+    schema: SchemaBuilder,
+    depth: usize,
+}
+
+impl<'a> Builder<'a> {
+    fn declaration(
+        &mut self,
+        ty: &Type,
+        module: &[String],
+        substitutions: &HashMap<String, Declaration>,
+    ) -> Result<Declaration, FromRustError> {
+        let display = || quote_type(ty);
+        match ty {
+            Type::Paren(inner) => self.declaration(&inner.elem, module, substitutions),
+            Type::Group(inner) => self.declaration(&inner.elem, module, substitutions),
+            Type::Array(array) => {
+                let length = match &array.len {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(n),
+                        ..
+                    }) => n
+                        .base10_parse::<u32>()
+                        .map_err(|_| FromRustError::UnsupportedType(display()))?,
+                    _ => return Err(FromRustError::UnsupportedType(display())),
+                };
+                let elements = self.declaration(&array.elem, module, substitutions)?;
+                Ok(self.schema.array(elements, length))
+            }
+            Type::Tuple(tuple) if tuple.elems.is_empty() => Ok("nil".to_string()),
+            // Borsh only implements `BorshSchema` for tuples of 2 or more
+            Type::Tuple(tuple) if tuple.elems.len() >= 2 => {
+                let mut elements = Vec::with_capacity(tuple.elems.len());
+                for element in &tuple.elems {
+                    elements.push(self.declaration(element, module, substitutions)?);
+                }
+                Ok(self.schema.tuple(elements))
+            }
+            Type::Path(path) => self.path_declaration(path, module, substitutions),
+            _ => Err(FromRustError::UnsupportedType(display())),
+        }
+    }
+
+    fn path_declaration(
+        &mut self,
+        type_path: &TypePath,
+        module: &[String],
+        substitutions: &HashMap<String, Declaration>,
+    ) -> Result<Declaration, FromRustError> {
+        let display = quote_type(&Type::Path(type_path.clone()));
+        if type_path.qself.is_some() {
+            return Err(FromRustError::UnsupportedType(display));
+        }
+        let segments = &type_path.path.segments;
+        let last = segments.last().unwrap();
+        let ident = last.ident.to_string();
+
+        let mut arguments = Vec::new();
+        if let PathArguments::AngleBracketed(args) = &last.arguments {
+            for arg in &args.args {
+                if let GenericArgument::Type(ty) = arg {
+                    arguments.push(ty);
+                }
+            }
+        }
+        let arity = |expected: usize| {
+            if arguments.len() == expected {
+                Ok(())
+            } else {
+                Err(FromRustError::TypeArguments(ident.clone(), expected))
+            }
+        };
+
+        if segments.len() == 1 {
+            if let Some(substitution) = substitutions.get(&ident) {
+                return Ok(substitution.clone());
+            }
+            if let Some(declaration) = primitive_declaration(&ident) {
+                return Ok(declaration);
+            }
+            if ident == "str" {
+                return Ok("string".to_string());
+            }
+        }
+
+        if self.resolve(type_path, module)?.is_none() {
+            let Some(expected) = generic_arity(&ident) else {
+                return Err(FromRustError::UnknownType(display));
+            };
+            arity(expected)?;
+            let mut params = Vec::with_capacity(arguments.len());
+            for argument in arguments {
+                params.push(self.declaration(argument, module, substitutions)?);
+            }
+            return Ok(self.schema.generic(&ident, params));
+        }
+
+        let item = self.resolve(type_path, module)?.unwrap();
+        if arguments.len() != item.type_params.len() {
+            return Err(FromRustError::TypeArguments(ident, item.type_params.len()));
+        }
+        let mut params = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            params.push(self.declaration(argument, module, substitutions)?);
+        }
+        self.instantiate(item, params)
+    }
+
+    /// Finds the user-defined type a path refers to, relative to `module`.
+    fn resolve(
+        &self,
+        type_path: &TypePath,
+        module: &[String],
+    ) -> Result<Option<&'a TypeItem>, FromRustError> {
+        let mut segments = type_path
+            .path
+            .segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect::<Vec<_>>();
+        let ident = segments.pop().unwrap();
+        let named = self
+            .items
+            .iter()
+            .filter(|item| item.ident == ident)
+            .collect::<Vec<_>>();
+
+        if !segments.is_empty() {
+            let mut base = if type_path.path.leading_colon.is_some() || segments[0] == "crate" {
+                vec![]
+            } else {
+                module.to_vec()
+            };
+            for segment in &segments {
+                match &segment[..] {
+                    "crate" | "self" => {}
+                    "super" => {
+                        base.pop();
+                    }
+                    _ => base.push(segment.clone()),
+                }
+            }
+            // Relative to the current module, otherwise to the crate root
+            if let Some(item) = named.iter().find(|item| item.module == base) {
+                return Ok(Some(item));
+            }
+            let from_root = segments
+                .iter()
+                .filter(|s| !matches!(&s[..], "crate" | "self" | "super"))
+                .cloned()
+                .collect::<Vec<_>>();
+            return Ok(named.into_iter().find(|item| item.module == from_root));
+        }
+
+        if let Some(item) = named.iter().find(|item| item.module == module) {
+            return Ok(Some(item));
+        }
+        match &named[..] {
+            [] => Ok(None),
+            [item] => Ok(Some(item)),
+            _ => Err(FromRustError::AmbiguousType(ident)),
+        }
+    }
+
+    fn instantiate(
+        &mut self,
+        item: &'a TypeItem,
+        params: Vec<Declaration>,
+    ) -> Result<Declaration, FromRustError> {
+        let suffix = if params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", params.join(", "))
+        };
+        let declaration = format!("{}{suffix}", item.ident);
+        if self.schema.definitions.contains_key(&declaration) {
+            return Ok(declaration);
+        }
+
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(FromRustError::TooDeep(declaration));
+        }
+
+        let substitutions = item
+            .type_params
+            .iter()
+            .cloned()
+            .zip(params)
+            .collect::<HashMap<_, _>>();
+
+        // Placeholder so that recursive references terminate
+        self.schema.definitions.insert(
+            declaration.clone(),
+            Definition::Struct {
+                fields: Fields::Empty,
+            },
+        );
+
+        let definition = match &item.kind {
+            TypeItemKind::Struct(fields) => Definition::Struct {
+                fields: self.fields(fields, &item.module, &substitutions)?,
+            },
+            TypeItemKind::Enum(variants) => {
+                let mut compiled = Vec::with_capacity(variants.len());
+                for variant in variants {
+                    // The derive macro defines a struct for each variant
+                    let variant_declaration = format!("{}{}{suffix}", item.ident, variant.ident);
+                    let definition = Definition::Struct {
+                        fields: self.fields(&variant.fields, &item.module, &substitutions)?,
+                    };
+                    self.schema.add_definition(&variant_declaration, definition);
+                    compiled.push((variant.ident.to_string(), variant_declaration));
+                }
+                Definition::Enum { variants: compiled }
+            }
+        };
+        self.schema
+            .definitions
+            .insert(declaration.clone(), definition);
+
+        self.depth -= 1;
+        Ok(declaration)
+    }
+
+    fn fields(
+        &mut self,
+        fields: &syn::Fields,
+        module: &[String],
+        substitutions: &HashMap<String, Declaration>,
+    ) -> Result<Fields, FromRustError> {
+        Ok(match fields {
+            syn::Fields::Named(named) => {
+                let mut compiled = Vec::new();
+                for field in named.named.iter().filter(|f| !is_skipped(&f.attrs)) {
+                    compiled.push((
+                        field.ident.as_ref().unwrap().to_string(),
+                        self.declaration(&field.ty, module, substitutions)?,
+                    ));
+                }
+                if compiled.is_empty() {
+                    Fields::Empty
+                } else {
+                    Fields::NamedFields(compiled)
+                }
+            }
+            syn::Fields::Unnamed(unnamed) => {
+                let mut compiled = Vec::new();
+                for field in unnamed.unnamed.iter().filter(|f| !is_skipped(&f.attrs)) {
+                    compiled.push(self.declaration(&field.ty, module, substitutions)?);
+                }
+                if compiled.is_empty() {
+                    Fields::Empty
+                } else {
+                    Fields::UnnamedFields(compiled)
+                }
+            }
+            syn::Fields::Unit => Fields::Empty,
+        })
+    }
+}
+
+/// A type as written in source, e.g. `HashMap<u8, [u8; 4]>`, rather than
+/// with a space between every token.
+fn quote_type(ty: &Type) -> String {
+    use quote::ToTokens;
+    let tokens = ty.to_token_stream().to_string();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut out = String::with_capacity(tokens.len());
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // Only spaces between words, like in `dyn Trait`, are needed
+            ' ' if !(is_word(out.chars().last()) && is_word(chars.peek().copied())) => {}
+            ',' | ';' => out.push_str(&format!("{c} ")),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+
+    use super::{build, collect_items};
+    use fixtures::Outer;
+
+    #[allow(dead_code)]
+    mod nested {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Inner<T> {
+            pub value: T,
+            pub values: Vec<T>,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use std::collections::HashMap;
+
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Outer {
+            pair: (u32, String),
+            inner: super::nested::Inner<u64>,
+            #[borsh_skip]
+            skipped: u8,
+            lookup: HashMap<String, Option<[u8; 4]>>,
+            choice: Choice<bool>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Choice<T> {
+            Alpha { field: T },
+            Beta(u32),
+            Gamma,
+        }
+    }
+
+    const SOURCE: &str = r#"
+        mod nested {
+            #[derive(BorshSerialize, BorshSchema)]
+            pub struct Inner<T> {
+                pub value: T,
+                pub values: Vec<T>,
+            }
+        }
+
+        #[derive(BorshSerialize, borsh::BorshSchema)]
+        struct Outer {
+            pair: (u32, String),
+            inner: nested::Inner<u64>,
+            #[borsh_skip]
+            skipped: u8,
+            lookup: HashMap<String, Option<[u8; 4]>>,
+            choice: Choice<bool>,
+        }
+
+        #[derive(BorshSchema)]
+        enum Choice<T> {
+            Alpha { field: T },
+            Beta(u32),
+            Gamma,
+        }
+
+        struct NotBorsh(u8);
+    "#;
+
+    #[test]
+    fn matches_derive() {
+        let file = syn::parse_file(SOURCE).unwrap();
+        let mut items = Vec::new();
+        collect_items(&file.items, vec![], &mut items);
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(build(&items, "Outer").unwrap(), Outer::schema_container());
+        assert_eq!(
+            build(&items, "nested::Inner<bool>").unwrap(),
+            nested::Inner::<bool>::schema_container(),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |root: &str| build(&[], root).unwrap_err().to_string();
+
+        assert_eq!(
+            error("std::collections::BTreeMap<u8, u8>"),
+            "Unknown type std::collections::BTreeMap<u8, u8>",
+        );
+        assert_eq!(error("Vec<[u8; N]>"), "Unsupported type [u8; N]");
+        assert_eq!(error("&'a dyn Trait"), "Unsupported type &'a dyn Trait");
+        assert_eq!(
+            error("Option<u8, u8>"),
+            "Type Option expects 1 type argument(s)"
+        );
+    }
+}
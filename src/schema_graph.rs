@@ -0,0 +1,318 @@
+//! Renders the type dependency graph of a schema as Graphviz DOT or Mermaid.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::schema_util::{dependency_order, is_primitive, kind, references};
+
+struct Node<'a> {
+    declaration: &'a Declaration,
+    /// `None` for primitives.
+    kind: Option<String>,
+    recursive: bool,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    label: Option<String>,
+    recursive: bool,
+}
+
+/// Definitions reachable from the root, and optionally the primitives they
+/// use, with an edge for every field, variant or element.
+struct Graph<'a> {
+    nodes: Vec<Node<'a>>,
+    edges: Vec<Edge>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(schema: &'a BorshSchemaContainer, primitives: bool) -> Self {
+        let mut nodes = Vec::new();
+        let mut ids = HashMap::new();
+        let mut node = |declaration: &'a Declaration, nodes: &mut Vec<Node<'a>>| {
+            *ids.entry(declaration).or_insert_with(|| {
+                let definition = schema.definitions.get(declaration);
+                nodes.push(Node {
+                    declaration,
+                    kind: definition.map(label_kind),
+                    recursive: definition.is_some() && reaches(schema, declaration, declaration),
+                });
+                nodes.len() - 1
+            })
+        };
+
+        // Root first, so that its node is n0
+        let mut order = dependency_order(schema);
+        order.reverse();
+        let mut edges = Vec::new();
+        for declaration in order {
+            let from = node(declaration, &mut nodes);
+            for (label, reference) in labelled_references(&schema.definitions[declaration]) {
+                let shown = schema.definitions.contains_key(reference)
+                    || (primitives && is_primitive(reference));
+                if !shown {
+                    continue;
+                }
+                let to = node(reference, &mut nodes);
+                edges.push(Edge {
+                    from,
+                    to,
+                    label,
+                    recursive: reaches(schema, reference, declaration),
+                });
+            }
+        }
+
+        Self { nodes, edges }
+    }
+}
+
+fn label_kind(definition: &Definition) -> String {
+    match definition {
+        Definition::Array { length, .. } => format!("array[{length}]"),
+        _ => kind(definition).to_string(),
+    }
+}
+
+/// References of a definition, labelled with the field or variant name,
+/// tuple index, or nothing for sequence and array elements.
+fn labelled_references(definition: &Definition) -> Vec<(Option<String>, &Declaration)> {
+    match definition {
+        Definition::Array { elements, .. } | Definition::Sequence { elements } => {
+            vec![(None, elements)]
+        }
+        Definition::Tuple { elements }
+        | Definition::Struct {
+            fields: Fields::UnnamedFields(elements),
+        } => elements
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (Some(i.to_string()), d))
+            .collect(),
+        Definition::Struct {
+            fields: Fields::NamedFields(fields),
+        }
+        | Definition::Enum { variants: fields } => fields
+            .iter()
+            .map(|(name, d)| (Some(name.clone()), d))
+            .collect(),
+        Definition::Struct {
+            fields: Fields::Empty,
+        } => vec![],
+    }
+}
+
+/// Whether `to` can be reached from `from` in one or more steps.
+fn reaches(schema: &BorshSchemaContainer, from: &Declaration, to: &Declaration) -> bool {
+    let mut stack = vec![from];
+    let mut seen = HashSet::new();
+    while let Some(next) = stack.pop() {
+        if let Some(definition) = schema.definitions.get(next) {
+            for reference in references(definition) {
+                if reference == to {
+                    return true;
+                }
+                if seen.insert(reference) {
+                    stack.push(reference);
+                }
+            }
+        }
+    }
+    false
+}
+
+const CYCLE_COLOR: &str = "#d62728";
+
+/// Graphviz DOT source. Types are boxes labelled with their kind, the root
+/// has a bold border, and cycles are drawn in red.
+pub fn dot(schema: &BorshSchemaContainer, primitives: bool) -> String {
+    let graph = Graph::new(schema, primitives);
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let mut out = String::new();
+    writeln!(out, "digraph schema {{").unwrap();
+    writeln!(out, "    rankdir=LR;").unwrap();
+    writeln!(out, "    node [shape=box, fontname=monospace];").unwrap();
+    writeln!(out, "    edge [fontname=monospace, fontsize=10];").unwrap();
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let mut attributes = vec![];
+        match &node.kind {
+            Some(kind) => {
+                attributes.push(format!("label=\"{}\\n{kind}\"", escape(node.declaration)));
+            }
+            None => attributes.push(format!(
+                "label=\"{}\", shape=plaintext",
+                escape(node.declaration),
+            )),
+        }
+        if node.declaration == &schema.declaration {
+            attributes.push("style=bold".to_string());
+        }
+        if node.recursive {
+            attributes.push(format!("color=\"{CYCLE_COLOR}\""));
+        }
+        writeln!(out, "    n{i} [{}];", attributes.join(", ")).unwrap();
+    }
+    for edge in &graph.edges {
+        let mut attributes = vec![];
+        if let Some(label) = &edge.label {
+            attributes.push(format!("label=\"{}\"", escape(label)));
+        }
+        if edge.recursive {
+            attributes.push(format!(
+                "color=\"{CYCLE_COLOR}\", fontcolor=\"{CYCLE_COLOR}\""
+            ));
+        }
+        write!(out, "    n{} -> n{}", edge.from, edge.to).unwrap();
+        if !attributes.is_empty() {
+            write!(out, " [{}]", attributes.join(", ")).unwrap();
+        }
+        writeln!(out, ";").unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// Mermaid flowchart source, styled like [`dot`].
+pub fn mermaid(schema: &BorshSchemaContainer, primitives: bool) -> String {
+    let graph = Graph::new(schema, primitives);
+    // Mermaid labels are HTML, and `"` ends a quoted label
+    let escape = |s: &str| {
+        s.replace('&', "#amp;")
+            .replace('<', "#lt;")
+            .replace('>', "#gt;")
+            .replace('"', "#quot;")
+            .replace('|', "#124;")
+    };
+
+    let mut out = String::new();
+    writeln!(out, "flowchart LR").unwrap();
+    for (i, node) in graph.nodes.iter().enumerate() {
+        match &node.kind {
+            Some(kind) => writeln!(
+                out,
+                "    n{i}[\"{}<br/><i>{kind}</i>\"]",
+                escape(node.declaration),
+            ),
+            None => writeln!(out, "    n{i}([\"{}\"])", escape(node.declaration)),
+        }
+        .unwrap();
+    }
+    for edge in &graph.edges {
+        match &edge.label {
+            Some(label) => writeln!(
+                out,
+                "    n{} -->|\"{}\"| n{}",
+                edge.from,
+                escape(label),
+                edge.to
+            ),
+            None => writeln!(out, "    n{} --> n{}", edge.from, edge.to),
+        }
+        .unwrap();
+    }
+
+    let root = graph
+        .nodes
+        .iter()
+        .position(|n| n.declaration == &schema.declaration);
+    if let Some(root) = root {
+        writeln!(out, "    style n{root} stroke-width:3px").unwrap();
+    }
+    let recursive = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| n.recursive)
+        .map(|(i, _)| format!("n{i}"))
+        .collect::<Vec<_>>();
+    if !recursive.is_empty() {
+        writeln!(
+            out,
+            "    classDef recursive stroke:{CYCLE_COLOR},color:{CYCLE_COLOR}"
+        )
+        .unwrap();
+        writeln!(out, "    class {} recursive", recursive.join(",")).unwrap();
+    }
+    for (i, edge) in graph.edges.iter().enumerate() {
+        if edge.recursive {
+            writeln!(
+                out,
+                "    linkStyle {i} stroke:{CYCLE_COLOR},color:{CYCLE_COLOR}"
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+
+    use super::{dot, mermaid};
+
+    #[test]
+    fn test() {
+        // The derive macro cannot produce recursive schemas
+        let schema = BorshSchemaContainer {
+            declaration: "Tree".to_string(),
+            definitions: [
+                (
+                    "Tree",
+                    Definition::Struct {
+                        fields: Fields::NamedFields(vec![
+                            ("value".to_string(), "u32".to_string()),
+                            ("children".to_string(), "Vec<Tree>".to_string()),
+                        ]),
+                    },
+                ),
+                (
+                    "Vec<Tree>",
+                    Definition::Sequence {
+                        elements: "Tree".to_string(),
+                    },
+                ),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+        };
+
+        assert_eq!(
+            dot(&schema, false),
+            r##"digraph schema {
+    rankdir=LR;
+    node [shape=box, fontname=monospace];
+    edge [fontname=monospace, fontsize=10];
+    n0 [label="Tree\nstruct", style=bold, color="#d62728"];
+    n1 [label="Vec<Tree>\nsequence", color="#d62728"];
+    n0 -> n1 [label="children", color="#d62728", fontcolor="#d62728"];
+    n1 -> n0 [color="#d62728", fontcolor="#d62728"];
+}
+"##,
+        );
+
+        assert_eq!(
+            mermaid(&schema, true),
+            r##"flowchart LR
+    n0["Tree<br/><i>struct</i>"]
+    n1(["u32"])
+    n2["Vec#lt;Tree#gt;<br/><i>sequence</i>"]
+    n0 -->|"value"| n1
+    n0 -->|"children"| n2
+    n2 --> n0
+    style n0 stroke-width:3px
+    classDef recursive stroke:#d62728,color:#d62728
+    class n0,n2 recursive
+    linkStyle 1 stroke:#d62728,color:#d62728
+    linkStyle 2 stroke:#d62728,color:#d62728
+"##,
+        );
+    }
+}
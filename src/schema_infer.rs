@@ -0,0 +1,505 @@
+//! Infers a `BorshSchemaContainer` from sample JSON documents.
+//!
+//! The shapes of all samples are unified: objects become structs, arrays
+//! become sequences of their unified element shape, and values that are
+//! sometimes `null` or missing become `Option`s. Integers get the narrowest
+//! width that fits every value seen, unless the width is overridden by path.
+
+use std::collections::{HashMap, HashSet};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    query::Integer,
+    schema_util::{is_primitive, SchemaBuilder},
+};
+
+#[derive(Error, Debug)]
+pub enum InferError {
+    #[error("No samples to infer from")]
+    NoSamples,
+    #[error("Conflicting types at `{0}`: {1} and {2}")]
+    Conflict(String, &'static str, &'static str),
+    #[error("Cannot infer the type at `{0}` from only null values or empty arrays, override it with --type")]
+    Unknown(String),
+    #[error("The integers at `{0}` do not fit in any one integer type, override it with --type")]
+    IntegerRange(String),
+    #[error("Invalid type override `{0}`, expected PATH=TYPE with a primitive TYPE")]
+    InvalidOverride(String),
+    #[error("Type override path `{0}` does not match any value")]
+    UnusedOverride(String),
+}
+
+/// A type override, e.g. `accounts[].balance=u64`. Paths use `.` between
+/// field names and `[]` for the elements of an array; the empty path is the
+/// root value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeOverride {
+    pub path: String,
+    pub declaration: Declaration,
+}
+
+impl std::str::FromStr for TypeOverride {
+    type Err = InferError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, declaration) = s
+            .rsplit_once('=')
+            .ok_or_else(|| InferError::InvalidOverride(s.to_string()))?;
+        let declaration = match declaration.trim() {
+            "String" => "string",
+            d => d,
+        };
+        if !is_primitive(declaration) || declaration == "nil" {
+            return Err(InferError::InvalidOverride(s.to_string()));
+        }
+        Ok(Self {
+            path: path.trim().trim_start_matches('.').to_string(),
+            declaration: declaration.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Shape {
+    /// Only `null`s or nothing at all have been seen.
+    Unknown,
+    Bool,
+    Integer {
+        min: Integer,
+        max: Integer,
+        fits_f32: bool,
+    },
+    Float {
+        fits_f32: bool,
+    },
+    String,
+    Array(Box<Observed>),
+    Object(Vec<(String, Observed)>),
+}
+
+impl Shape {
+    fn name(&self) -> &'static str {
+        match self {
+            Shape::Unknown => "null",
+            Shape::Bool => "boolean",
+            Shape::Integer { .. } | Shape::Float { .. } => "number",
+            Shape::String => "string",
+            Shape::Array(_) => "array",
+            Shape::Object(_) => "object",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Observed {
+    shape: Shape,
+    nullable: bool,
+}
+
+impl Observed {
+    fn observe(value: &Value, path: &str) -> Result<Self, InferError> {
+        let shape = match value {
+            Value::Null => Shape::Unknown,
+            Value::Bool(_) => Shape::Bool,
+            // Integers are read from the literal, as they may not fit 64 bits
+            Value::Number(n) => match n.to_string().parse::<Integer>() {
+                Ok(integer) => Shape::Integer {
+                    min: integer,
+                    max: integer,
+                    fits_f32: integer_fits_f32(&n.to_string()),
+                },
+                Err(()) => {
+                    let f = n.as_f64().unwrap_or_default();
+                    Shape::Float {
+                        fits_f32: (f as f32) as f64 == f,
+                    }
+                }
+            },
+            Value::String(_) => Shape::String,
+            Value::Array(elements) => {
+                let path = format!("{path}[]");
+                let mut element = Observed {
+                    shape: Shape::Unknown,
+                    nullable: false,
+                };
+                for e in elements {
+                    element = element.try_merge(Observed::observe(e, &path)?, &path)?;
+                }
+                Shape::Array(Box::new(element))
+            }
+            Value::Object(object) => Shape::Object(
+                object
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), Observed::observe(v, &join(path, k))?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        Ok(Observed {
+            nullable: value.is_null(),
+            shape,
+        })
+    }
+
+    fn try_merge(self, other: Observed, path: &str) -> Result<Observed, InferError> {
+        let nullable = self.nullable || other.nullable;
+        let shape = match (self.shape, other.shape) {
+            (Shape::Unknown, shape) | (shape, Shape::Unknown) => shape,
+            (Shape::Bool, Shape::Bool) => Shape::Bool,
+            (Shape::String, Shape::String) => Shape::String,
+            (
+                Shape::Integer {
+                    min: a,
+                    max: b,
+                    fits_f32: e,
+                },
+                Shape::Integer {
+                    min: c,
+                    max: d,
+                    fits_f32: f,
+                },
+            ) => Shape::Integer {
+                min: a.min(c),
+                max: b.max(d),
+                fits_f32: e && f,
+            },
+            (Shape::Float { fits_f32: a }, Shape::Float { fits_f32: b }) => {
+                Shape::Float { fits_f32: a && b }
+            }
+            (Shape::Float { fits_f32: a }, Shape::Integer { fits_f32: b, .. })
+            | (Shape::Integer { fits_f32: a, .. }, Shape::Float { fits_f32: b }) => {
+                Shape::Float { fits_f32: a && b }
+            }
+            (Shape::Array(a), Shape::Array(b)) => {
+                Shape::Array(Box::new(a.try_merge(*b, &format!("{path}[]"))?))
+            }
+            (Shape::Object(mut a), Shape::Object(b)) => {
+                let mut seen = HashSet::new();
+                for (key, observed) in b {
+                    seen.insert(key.clone());
+                    match a.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, existing)) => {
+                            let merged = existing.clone().try_merge(observed, &join(path, &key))?;
+                            *existing = merged;
+                        }
+                        // Missing from earlier samples
+                        None => a.push((
+                            key,
+                            Observed {
+                                nullable: true,
+                                ..observed
+                            },
+                        )),
+                    }
+                }
+                for (key, observed) in a.iter_mut() {
+                    if !seen.contains(key) {
+                        observed.nullable = true;
+                    }
+                }
+                Shape::Object(a)
+            }
+            (a, b) => return Err(InferError::Conflict(display_path(path), a.name(), b.name())),
+        };
+        Ok(Observed { shape, nullable })
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        ".".to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Unifies the samples and builds a schema whose root struct (if the samples
+/// are objects) is called `root_name`.
+pub fn infer(
+    samples: &[Value],
+    root_name: &str,
+    overrides: &[TypeOverride],
+) -> Result<BorshSchemaContainer, InferError> {
+    let mut samples = samples.iter();
+    let first = samples.next().ok_or(InferError::NoSamples)?;
+    let mut observed = Observed::observe(first, "")?;
+    for sample in samples {
+        observed = observed.try_merge(Observed::observe(sample, "")?, "")?;
+    }
+
+    let mut builder = Builder {
+        overrides: overrides
+            .iter()
+            .map(|o| (o.path.as_str(), o.declaration.as_str()))
+            .collect(),
+        used_overrides: HashSet::new(),
+        schema: SchemaBuilder::default(),
+        names: HashSet::new(),
+    };
+    let declaration = builder.declaration(&observed, "", root_name)?;
+
+    if let Some(unused) = overrides
+        .iter()
+        .find(|o| !builder.used_overrides.contains(o.path.as_str()))
+    {
+        return Err(InferError::UnusedOverride(unused.path.clone()));
+    }
+
+    Ok(BorshSchemaContainer {
+        declaration,
+        definitions: builder.schema.definitions,
+    })
+}
+
+struct Builder<'a> {
+    overrides: HashMap<&'a str, &'a str>,
+    used_overrides: HashSet<&'a str>,
+    schema: SchemaBuilder,
+    names: HashSet<String>,
+}
+
+impl Builder<'_> {
+    fn declaration(
+        &mut self,
+        observed: &Observed,
+        path: &str,
+        name_hint: &str,
+    ) -> Result<Declaration, InferError> {
+        let inner = match self.overrides.get_key_value(path) {
+            Some((&key, &declaration)) => {
+                self.used_overrides.insert(key);
+                declaration.to_string()
+            }
+            None => self.shape_declaration(&observed.shape, path, name_hint)?,
+        };
+        if !observed.nullable {
+            return Ok(inner);
+        }
+        Ok(self.schema.generic("Option", vec![inner]))
+    }
+
+    fn shape_declaration(
+        &mut self,
+        shape: &Shape,
+        path: &str,
+        name_hint: &str,
+    ) -> Result<Declaration, InferError> {
+        Ok(match shape {
+            Shape::Unknown => return Err(InferError::Unknown(display_path(path))),
+            Shape::Bool => "bool".to_string(),
+            Shape::String => "string".to_string(),
+            Shape::Float { fits_f32: true } => "f32".to_string(),
+            Shape::Float { fits_f32: false } => "f64".to_string(),
+            Shape::Integer { min, max, .. } => narrowest_integer(*min, *max)
+                .ok_or_else(|| InferError::IntegerRange(display_path(path)))?
+                .to_string(),
+            Shape::Array(element) => {
+                let elements =
+                    self.declaration(element, &format!("{path}[]"), &format!("{name_hint}Item"))?;
+                self.schema.generic("Vec", vec![elements])
+            }
+            Shape::Object(object) => {
+                let name = self.unique_name(name_hint);
+                let mut fields = Vec::with_capacity(object.len());
+                for (key, observed) in object {
+                    let declaration =
+                        self.declaration(observed, &join(path, key), &pascal_case(key))?;
+                    fields.push((key.clone(), declaration));
+                }
+                let fields = if fields.is_empty() {
+                    Fields::Empty
+                } else {
+                    Fields::NamedFields(fields)
+                };
+                self.schema
+                    .add_definition(&name, Definition::Struct { fields });
+                name
+            }
+        })
+    }
+
+    fn unique_name(&mut self, hint: &str) -> String {
+        let mut name = hint.to_string();
+        let mut suffix = 2;
+        while is_primitive(&name) || !self.names.insert(name.clone()) {
+            name = format!("{hint}{suffix}");
+            suffix += 1;
+        }
+        name
+    }
+}
+
+/// `None` if the values are both negative and above `i64::MAX`.
+fn narrowest_integer(min: Integer, max: Integer) -> Option<&'static str> {
+    const UNSIGNED: [(&str, u128); 5] = [
+        ("u8", u8::MAX as u128),
+        ("u16", u16::MAX as u128),
+        ("u32", u32::MAX as u128),
+        ("u64", u64::MAX as u128),
+        ("u128", u128::MAX),
+    ];
+    const SIGNED: [(&str, i128, i128); 5] = [
+        ("i8", i8::MIN as i128, i8::MAX as i128),
+        ("i16", i16::MIN as i128, i16::MAX as i128),
+        ("i32", i32::MIN as i128, i32::MAX as i128),
+        ("i64", i64::MIN as i128, i64::MAX as i128),
+        ("i128", i128::MIN, i128::MAX),
+    ];
+    if min >= Integer::from(0u128) {
+        UNSIGNED
+            .iter()
+            .find(|(_, hi)| max <= Integer::from(*hi))
+            .map(|(name, _)| *name)
+    } else {
+        SIGNED
+            .iter()
+            .find(|(_, lo, hi)| min >= Integer::from(*lo) && max <= Integer::from(*hi))
+            .map(|(name, ..)| *name)
+    }
+}
+
+/// Whether an integer literal converts to `f32` exactly.
+fn integer_fits_f32(literal: &str) -> bool {
+    match (literal.parse::<i128>(), literal.parse::<u128>()) {
+        (Ok(i), _) => (i as f32) as i128 == i,
+        (_, Ok(u)) => {
+            let f = u as f32;
+            f.is_finite() && f as u128 == u
+        }
+        _ => false,
+    }
+}
+
+/// `token_info` and `tokenInfo` both become `TokenInfo`.
+fn pascal_case(key: &str) -> String {
+    let mut name = String::with_capacity(key.len());
+    for part in key.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.extend(chars);
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, 'T');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::schema::{Definition, Fields};
+    use serde_json::json;
+
+    use crate::dynamic_schema::serialize_with_schema;
+
+    use super::{infer, InferError, TypeOverride};
+
+    #[test]
+    fn test() {
+        let samples = [
+            json!({
+                "name": "alice",
+                "balance": 100,
+                "delta": -3,
+                "tags": ["a", "b"],
+                "profile": { "age": 30, "score": 0.5 },
+                "memo": null,
+            }),
+            json!({
+                "name": "bob",
+                "balance": 70000,
+                "delta": 200,
+                "tags": [],
+                "profile": { "age": 41, "score": 0.1 },
+                "memo": "hi",
+                "accounts": [{ "id": 1 }],
+            }),
+        ];
+
+        let schema = infer(&samples, "Root", &[]).unwrap();
+        assert_eq!(schema.declaration, "Root");
+        assert_eq!(
+            schema.definitions["Root"],
+            Definition::Struct {
+                fields: Fields::NamedFields(
+                    [
+                        ("name", "string"),
+                        ("balance", "u32"),
+                        ("delta", "i16"),
+                        ("tags", "Vec<string>"),
+                        ("profile", "Profile"),
+                        ("memo", "Option<string>"),
+                        ("accounts", "Option<Vec<AccountsItem>>"),
+                    ]
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect()
+                ),
+            },
+        );
+        assert_eq!(
+            schema.definitions["Profile"],
+            Definition::Struct {
+                fields: Fields::NamedFields(vec![
+                    ("age".to_string(), "u8".to_string()),
+                    ("score".to_string(), "f64".to_string()),
+                ]),
+            },
+        );
+
+        // Every sample can be encoded with the inferred schema
+        for sample in &samples {
+            serialize_with_schema(&mut vec![], sample, &schema).unwrap();
+        }
+
+        let overrides = ["balance=u64".parse::<TypeOverride>().unwrap()];
+        let schema = infer(&samples, "Root", &overrides).unwrap();
+        match &schema.definitions["Root"] {
+            Definition::Struct {
+                fields: Fields::NamedFields(fields),
+            } => assert_eq!(fields[1], ("balance".to_string(), "u64".to_string())),
+            _ => panic!(),
+        }
+
+        let samples = [json!(-1), json!(u64::MAX)];
+        let schema = infer(&samples, "Root", &[]).unwrap();
+        assert_eq!(schema.declaration, "i128");
+        for sample in &samples {
+            serialize_with_schema(&mut vec![], sample, &schema).unwrap();
+        }
+
+        // Integers beyond 64 bits are not read as floats
+        let sample = serde_json::from_str(r#"{ "bal": 1000000000000000000000001 }"#).unwrap();
+        let schema = infer(&[sample], "Root", &[]).unwrap();
+        match &schema.definitions["Root"] {
+            Definition::Struct {
+                fields: Fields::NamedFields(fields),
+            } => assert_eq!(fields[0], ("bal".to_string(), "u128".to_string())),
+            _ => panic!(),
+        }
+        assert!(matches!(
+            infer(&[json!(-1), json!(u128::MAX)], "Root", &[]),
+            Err(InferError::IntegerRange(path)) if path == ".",
+        ));
+
+        assert!(matches!(
+            infer(&[json!({ "a": 1 }), json!({ "a": "x" })], "Root", &[]),
+            Err(InferError::Conflict(path, "number", "string")) if path == "a",
+        ));
+        assert!(matches!(
+            infer(&[json!([[1], ["x"]])], "Root", &[]),
+            Err(InferError::Conflict(path, ..)) if path == "[][]",
+        ));
+    }
+}
@@ -0,0 +1,181 @@
+//! A JSON representation of `BorshSchemaContainer` that mirrors its
+//! structure, so that schemas can be reviewed and stored as text.
+//!
+//! ```json
+//! {
+//!   "declaration": "Parent",
+//!   "definitions": {
+//!     "Parent": { "Struct": { "fields": { "NamedFields": [["child", "Vec<u8>"]] } } },
+//!     "Vec<u8>": { "Sequence": { "elements": "u8" } }
+//!   }
+//! }
+//! ```
+//!
+//! Definitions are sorted by declaration so that the output is stable.
+
+use std::collections::BTreeMap;
+
+use borsh::schema::{
+    BorshSchemaContainer, Declaration, Definition, FieldName, Fields, VariantName,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SchemaJson {
+    pub declaration: Declaration,
+    pub definitions: BTreeMap<Declaration, DefinitionJson>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub enum DefinitionJson {
+    Array {
+        length: u32,
+        elements: Declaration,
+    },
+    Sequence {
+        elements: Declaration,
+    },
+    Tuple {
+        elements: Vec<Declaration>,
+    },
+    Enum {
+        variants: Vec<(VariantName, Declaration)>,
+    },
+    Struct {
+        fields: FieldsJson,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum FieldsJson {
+    NamedFields(Vec<(FieldName, Declaration)>),
+    UnnamedFields(Vec<Declaration>),
+    Empty,
+}
+
+impl From<&BorshSchemaContainer> for SchemaJson {
+    fn from(schema: &BorshSchemaContainer) -> Self {
+        Self {
+            declaration: schema.declaration.clone(),
+            definitions: schema
+                .definitions
+                .iter()
+                .map(|(declaration, definition)| (declaration.clone(), definition.into()))
+                .collect(),
+        }
+    }
+}
+
+impl From<SchemaJson> for BorshSchemaContainer {
+    fn from(schema: SchemaJson) -> Self {
+        Self {
+            declaration: schema.declaration,
+            definitions: schema
+                .definitions
+                .into_iter()
+                .map(|(declaration, definition)| (declaration, definition.into()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&Definition> for DefinitionJson {
+    fn from(definition: &Definition) -> Self {
+        match definition {
+            Definition::Array { length, elements } => Self::Array {
+                length: *length,
+                elements: elements.clone(),
+            },
+            Definition::Sequence { elements } => Self::Sequence {
+                elements: elements.clone(),
+            },
+            Definition::Tuple { elements } => Self::Tuple {
+                elements: elements.clone(),
+            },
+            Definition::Enum { variants } => Self::Enum {
+                variants: variants.clone(),
+            },
+            Definition::Struct { fields } => Self::Struct {
+                fields: match fields {
+                    Fields::NamedFields(f) => FieldsJson::NamedFields(f.clone()),
+                    Fields::UnnamedFields(f) => FieldsJson::UnnamedFields(f.clone()),
+                    Fields::Empty => FieldsJson::Empty,
+                },
+            },
+        }
+    }
+}
+
+impl From<DefinitionJson> for Definition {
+    fn from(definition: DefinitionJson) -> Self {
+        match definition {
+            DefinitionJson::Array { length, elements } => Self::Array { length, elements },
+            DefinitionJson::Sequence { elements } => Self::Sequence { elements },
+            DefinitionJson::Tuple { elements } => Self::Tuple { elements },
+            DefinitionJson::Enum { variants } => Self::Enum { variants },
+            DefinitionJson::Struct { fields } => Self::Struct {
+                fields: match fields {
+                    FieldsJson::NamedFields(f) => Fields::NamedFields(f),
+                    FieldsJson::UnnamedFields(f) => Fields::UnnamedFields(f),
+                    FieldsJson::Empty => Fields::Empty,
+                },
+            },
+        }
+    }
+}
+
+/// Whether the input looks like the JSON representation rather than Borsh.
+pub fn is_json(input: &[u8]) -> bool {
+    input
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|&b| b == b'{')
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{schema::BorshSchemaContainer, BorshSchema};
+    use serde_json::json;
+
+    use super::SchemaJson;
+    use fixtures::Parent;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Parent {
+            pair: (u32, u64),
+            bytes: [u8; 4],
+            child: Option<Child>,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Child {
+            Alpha { field: u32 },
+            Beta(u8),
+            Gamma,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let schema = Parent::schema_container();
+        let value = serde_json::to_value(SchemaJson::from(&schema)).unwrap();
+
+        assert_eq!(
+            value["definitions"]["Array<u8, 4>"],
+            json!({ "Array": { "length": 4, "elements": "u8" } }),
+        );
+        assert_eq!(
+            value["definitions"]["ChildGamma"],
+            json!({ "Struct": { "fields": "Empty" } }),
+        );
+
+        let parsed = serde_json::from_value::<SchemaJson>(value).unwrap();
+        assert_eq!(BorshSchemaContainer::from(parsed), schema);
+    }
+}
@@ -0,0 +1,288 @@
+//! Lints for schema patterns that are legal Borsh but often cause trouble.
+
+use std::{fmt, str::FromStr};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use serde::Serialize;
+
+use crate::schema_util::{as_map, as_option, dependency_order, recursive_declarations};
+
+/// Fixed arrays longer than this are flagged.
+const LARGE_ARRAY_LENGTH: u32 = 1024;
+
+/// Enums with at least this many variants are flagged, since at most 256 fit
+/// in the one-byte tag.
+const MANY_VARIANTS: usize = 200;
+
+/// Name fragments suggesting a byte string has a fixed length.
+const FIXED_SIZE_NAMES: [&str; 12] = [
+    "hash",
+    "digest",
+    "checksum",
+    "key",
+    "pubkey",
+    "signature",
+    "sig",
+    "address",
+    "addr",
+    "owner",
+    "root",
+    "id",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    Float,
+    HashMap,
+    NestedOption,
+    LargeArray,
+    ManyVariants,
+    UnboundedRecursion,
+    ByteVec,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 7] = [
+        Rule::Float,
+        Rule::HashMap,
+        Rule::NestedOption,
+        Rule::LargeArray,
+        Rule::ManyVariants,
+        Rule::UnboundedRecursion,
+        Rule::ByteVec,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::Float => "float",
+            Rule::HashMap => "hash-map",
+            Rule::NestedOption => "nested-option",
+            Rule::LargeArray => "large-array",
+            Rule::ManyVariants => "many-variants",
+            Rule::UnboundedRecursion => "unbounded-recursion",
+            Rule::ByteVec => "byte-vec",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.name() == s)
+            .ok_or_else(|| {
+                let names = Rule::ALL.map(|r| r.name()).join(", ");
+                format!("unknown lint `{s}`, expected one of: {names}")
+            })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub rule: Rule,
+    /// Declaration, or declaration and field, e.g. `Account.balance`.
+    pub location: String,
+    pub message: String,
+}
+
+/// Findings for every definition reachable from the root, in dependency
+/// order.
+pub fn lint(schema: &BorshSchemaContainer) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut finding = |rule, location: String, message: String| {
+        findings.push(Finding {
+            rule,
+            location,
+            message,
+        })
+    };
+
+    if is_float(&schema.declaration) {
+        finding(
+            Rule::Float,
+            schema.declaration.clone(),
+            float_message(&schema.declaration),
+        );
+    }
+
+    let recursive = recursive_declarations(schema);
+
+    for declaration in dependency_order(schema) {
+        let definition = &schema.definitions[declaration];
+
+        for (location, name, reference) in members(declaration, definition) {
+            if is_float(reference) {
+                finding(Rule::Float, location.clone(), float_message(reference));
+            }
+            let is_byte_vec = matches!(
+                schema.definitions.get(reference),
+                Some(Definition::Sequence { elements }) if elements == "u8"
+            );
+            if let Some(name) = name.filter(|n| is_byte_vec && suggests_fixed_size(n)) {
+                finding(
+                    Rule::ByteVec,
+                    location,
+                    format!("{name} is a Vec<u8>, but its name suggests a fixed size; consider [u8; N], which has no length prefix and rejects wrong lengths"),
+                );
+            }
+        }
+
+        if as_map(schema, declaration).is_some() {
+            finding(
+                Rule::HashMap,
+                declaration.clone(),
+                "entries are encoded sorted by key, not in iteration order; use a Vec of pairs where order matters".to_string(),
+            );
+        }
+
+        if let Some(inner) = as_option(definition) {
+            if schema.definitions.get(inner).and_then(as_option).is_some() {
+                finding(
+                    Rule::NestedOption,
+                    declaration.clone(),
+                    "None and Some(None) are easy to confuse and cannot be told apart in JSON; use an enum".to_string(),
+                );
+            }
+        }
+
+        match definition {
+            Definition::Array { length, .. } if *length > LARGE_ARRAY_LENGTH => finding(
+                Rule::LargeArray,
+                declaration.clone(),
+                format!(
+                    "fixed array of {length} elements is always encoded in full; consider a Vec"
+                ),
+            ),
+            Definition::Enum { variants } if variants.len() >= MANY_VARIANTS => finding(
+                Rule::ManyVariants,
+                declaration.clone(),
+                format!(
+                    "has {} variants, close to the limit of 256 that fit in a one-byte tag",
+                    variants.len(),
+                ),
+            ),
+            _ => {}
+        }
+
+        if recursive.contains(declaration) {
+            finding(
+                Rule::UnboundedRecursion,
+                declaration.clone(),
+                "is recursive, so nesting depth is unbounded and decoding untrusted data can exhaust the stack".to_string(),
+            );
+        }
+    }
+
+    findings
+}
+
+/// Every declaration a definition refers to, with its location and, for
+/// named fields, the field name.
+fn members<'a>(
+    declaration: &Declaration,
+    definition: &'a Definition,
+) -> Vec<(String, Option<&'a str>, &'a Declaration)> {
+    match definition {
+        Definition::Array { elements, .. } | Definition::Sequence { elements } => {
+            vec![(format!("{declaration}[]"), None, elements)]
+        }
+        Definition::Tuple { elements }
+        | Definition::Struct {
+            fields: Fields::UnnamedFields(elements),
+        } => elements
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (format!("{declaration}.{i}"), None, d))
+            .collect(),
+        Definition::Struct {
+            fields: Fields::NamedFields(fields),
+        } => fields
+            .iter()
+            .map(|(name, d)| (format!("{declaration}.{name}"), Some(name.as_str()), d))
+            .collect(),
+        Definition::Enum { variants } => variants
+            .iter()
+            .map(|(name, d)| (format!("{declaration}::{name}"), None, d))
+            .collect(),
+        Definition::Struct {
+            fields: Fields::Empty,
+        } => vec![],
+    }
+}
+
+fn is_float(declaration: &str) -> bool {
+    declaration == "f32" || declaration == "f64"
+}
+
+fn float_message(declaration: &str) -> String {
+    format!("{declaration} has many NaN encodings and platform-dependent rounding, so equal values may not encode to equal bytes; avoid it in consensus data")
+}
+
+/// Whether any word of a snake_case or camelCase name is in
+/// `FIXED_SIZE_NAMES`.
+fn suggests_fixed_size(name: &str) -> bool {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in name.chars() {
+        if (c == '_' || c.is_ascii_uppercase()) && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if c != '_' {
+            word.push(c.to_ascii_lowercase());
+        }
+    }
+    words.push(word);
+    words.iter().any(|w| FIXED_SIZE_NAMES.contains(&w.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use borsh::BorshSchema;
+
+    use super::{lint, Rule};
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Order {
+        price: f64,
+        book: HashMap<String, u64>,
+        note: Option<Option<String>>,
+        block_hash: Vec<u8>,
+        payload: Vec<u8>,
+        table: [u8; 2048],
+    }
+
+    #[test]
+    fn test() {
+        let findings = lint(&Order::schema_container())
+            .into_iter()
+            .map(|f| (f.rule, f.location))
+            .collect::<Vec<_>>();
+
+        let expected = [
+            (Rule::HashMap, "HashMap<string, u64>"),
+            (Rule::NestedOption, "Option<Option<string>>"),
+            (Rule::LargeArray, "Array<u8, 2048>"),
+            (Rule::Float, "Order.price"),
+            (Rule::ByteVec, "Order.block_hash"),
+        ];
+        for (rule, location) in expected {
+            assert!(
+                findings.contains(&(rule, location.to_string())),
+                "missing {rule} at {location}",
+            );
+        }
+        assert_eq!(findings.len(), expected.len());
+    }
+}
@@ -0,0 +1,483 @@
+//! Static analysis of encoded sizes.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use serde::Serialize;
+
+use crate::schema_util::{as_option, dependency_order, primitive_size, references};
+
+/// Number of elements of a sequence or array.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Count {
+    Fixed(u32),
+    /// Length of the sequence or string at a path, e.g. `.items[].name`.
+    Length(String),
+}
+
+/// Encoded size of a type, as a function of the lengths of the sequences
+/// and strings in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Const(u64),
+    Repeat {
+        count: Count,
+        element: Box<Expr>,
+    },
+    Sum(Vec<Expr>),
+    /// Whichever alternative is present, e.g. the payloads of an enum.
+    Choice(Vec<Expr>),
+    /// A type that contains itself, with its minimum size.
+    Recursive {
+        declaration: Declaration,
+        min: u64,
+    },
+}
+
+impl Expr {
+    fn sum(items: Vec<Expr>) -> Expr {
+        let mut constant = 0u64;
+        let mut terms = vec![];
+        for item in items {
+            match item {
+                Expr::Const(c) => constant = constant.saturating_add(c),
+                Expr::Sum(inner) => {
+                    for item in inner {
+                        match item {
+                            Expr::Const(c) => constant = constant.saturating_add(c),
+                            item => terms.push(item),
+                        }
+                    }
+                }
+                item => terms.push(item),
+            }
+        }
+        if terms.is_empty() {
+            return Expr::Const(constant);
+        }
+        if constant > 0 {
+            terms.insert(0, Expr::Const(constant));
+        }
+        if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Expr::Sum(terms)
+        }
+    }
+
+    fn repeat(count: Count, element: Expr) -> Expr {
+        match (&count, &element) {
+            (_, Expr::Const(0)) | (Count::Fixed(0), _) => Expr::Const(0),
+            (Count::Fixed(n), Expr::Const(c)) => Expr::Const(c.saturating_mul(u64::from(*n))),
+            _ => Expr::Repeat {
+                count,
+                element: Box::new(element),
+            },
+        }
+    }
+
+    fn choice(alternatives: Vec<Expr>) -> Expr {
+        match &alternatives[..] {
+            [] => Expr::Const(0),
+            [first, rest @ ..] if rest.iter().all(|a| a == first) => first.clone(),
+            _ => Expr::Choice(alternatives),
+        }
+    }
+
+    pub fn min(&self) -> u64 {
+        match self {
+            Expr::Const(c) => *c,
+            Expr::Repeat {
+                count: Count::Fixed(n),
+                element,
+            } => element.min().saturating_mul(u64::from(*n)),
+            Expr::Repeat {
+                count: Count::Length(_),
+                ..
+            } => 0,
+            Expr::Sum(items) => items.iter().fold(0, |sum, i| sum.saturating_add(i.min())),
+            Expr::Choice(alternatives) => alternatives.iter().map(Expr::min).min().unwrap_or(0),
+            Expr::Recursive { min, .. } => *min,
+        }
+    }
+
+    /// `None` if the size depends on a length or recursion depth.
+    pub fn max(&self) -> Option<u64> {
+        match self {
+            Expr::Const(c) => Some(*c),
+            Expr::Repeat {
+                count: Count::Fixed(n),
+                element,
+            } => element.max()?.checked_mul(u64::from(*n)),
+            Expr::Repeat {
+                count: Count::Length(_),
+                ..
+            }
+            | Expr::Recursive { .. } => None,
+            Expr::Sum(items) => items
+                .iter()
+                .try_fold(0u64, |sum, i| sum.checked_add(i.max()?)),
+            Expr::Choice(alternatives) => alternatives
+                .iter()
+                .map(Expr::max)
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .max(),
+        }
+    }
+
+    pub fn is_recursive(&self) -> bool {
+        match self {
+            Expr::Const(_) => false,
+            Expr::Repeat { element, .. } => element.is_recursive(),
+            Expr::Sum(items) | Expr::Choice(items) => items.iter().any(Expr::is_recursive),
+            Expr::Recursive { .. } => true,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Const(c) => write!(f, "{c}"),
+            Expr::Repeat { count, element } => {
+                let count = match count {
+                    Count::Fixed(n) => n.to_string(),
+                    Count::Length(path) => format!("len({path})"),
+                };
+                match element.as_ref() {
+                    Expr::Const(1) => write!(f, "{count}"),
+                    Expr::Const(c) => write!(f, "{c}*{count}"),
+                    Expr::Sum(_) => write!(f, "{count}*({element})"),
+                    element => write!(f, "{count}*{element}"),
+                }
+            }
+            Expr::Sum(items) => {
+                let items = items.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", items.join(" + "))
+            }
+            Expr::Choice(alternatives) => {
+                let alternatives = alternatives
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "({})", alternatives.join(" | "))
+            }
+            Expr::Recursive { declaration, .. } => write!(f, "size({declaration})"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeKind {
+    /// Every value has the same size.
+    Fixed,
+    /// The size varies, but has an upper bound, e.g. an enum of fixed-size
+    /// variants.
+    Bounded,
+    /// The size depends on the lengths of sequences or strings.
+    Variable,
+    /// The type contains itself, so the size is unbounded even for bounded
+    /// lengths.
+    Unbounded,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Size {
+    pub declaration: Declaration,
+    pub kind: SizeKind,
+    pub min: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<u64>,
+    /// Size in terms of the lengths of sequences and strings, unless fixed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formula: Option<String>,
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Size {
+            declaration,
+            kind,
+            min,
+            max,
+            formula,
+        } = self;
+        let bytes = |n: u64| if n == 1 { "byte" } else { "bytes" };
+        write!(f, "{declaration}: ")?;
+        match (kind, max, formula) {
+            (SizeKind::Fixed, _, _) => write!(f, "{min} {}", bytes(*min)),
+            (SizeKind::Bounded, Some(max), Some(formula)) => {
+                write!(f, "{min} to {max} bytes, {formula}")
+            }
+            (SizeKind::Unbounded, _, Some(formula)) => write!(
+                f,
+                "at least {min} {}, unbounded (recursive), {formula}",
+                bytes(*min),
+            ),
+            (_, _, Some(formula)) => write!(f, "at least {min} {}, {formula}", bytes(*min)),
+            (_, _, None) => write!(f, "at least {min} {}", bytes(*min)),
+        }
+    }
+}
+
+/// Sizes of every definition reachable from the root, root first.
+pub fn sizes(schema: &BorshSchemaContainer) -> Vec<Size> {
+    let mins = min_sizes(schema);
+    let mut order = dependency_order(schema);
+    order.reverse();
+
+    order
+        .into_iter()
+        .map(|declaration| {
+            let expr = expr(schema, declaration, ".", &mut HashSet::new(), &mins);
+            let min = expr.min();
+            let max = expr.max();
+            let kind = match max {
+                _ if expr.is_recursive() => SizeKind::Unbounded,
+                Some(max) if max == min => SizeKind::Fixed,
+                Some(_) => SizeKind::Bounded,
+                None => SizeKind::Variable,
+            };
+            Size {
+                declaration: declaration.clone(),
+                kind,
+                min,
+                max,
+                formula: (kind != SizeKind::Fixed).then(|| expr.to_string()),
+            }
+        })
+        .collect()
+}
+
+fn child(path: &str, segment: &str) -> String {
+    match path {
+        "." => format!(".{segment}"),
+        _ => format!("{path}.{segment}"),
+    }
+}
+
+fn elements(path: &str) -> String {
+    match path {
+        "." => ".[]".to_string(),
+        _ => format!("{path}[]"),
+    }
+}
+
+/// Size of a declaration, with length variables named by their path from
+/// `path`.
+fn expr<'a>(
+    schema: &'a BorshSchemaContainer,
+    declaration: &'a Declaration,
+    path: &str,
+    visiting: &mut HashSet<&'a Declaration>,
+    mins: &HashMap<&Declaration, u64>,
+) -> Expr {
+    let definition = match schema.definitions.get(declaration) {
+        Some(definition) => definition,
+        None if declaration == "string" => {
+            return Expr::sum(vec![
+                Expr::Const(4),
+                Expr::repeat(Count::Length(path.to_string()), Expr::Const(1)),
+            ])
+        }
+        None => return Expr::Const(primitive_size(declaration).unwrap_or(0)),
+    };
+
+    if !visiting.insert(declaration) {
+        return Expr::Recursive {
+            declaration: declaration.clone(),
+            min: mins.get(declaration).copied().unwrap_or(0),
+        };
+    }
+
+    let result = if let Some(inner) = as_option(definition) {
+        Expr::sum(vec![
+            Expr::Const(1),
+            Expr::choice(vec![
+                Expr::Const(0),
+                expr(schema, inner, path, visiting, mins),
+            ]),
+        ])
+    } else {
+        match definition {
+            Definition::Array {
+                length,
+                elements: e,
+            } => Expr::repeat(
+                Count::Fixed(*length),
+                expr(schema, e, &elements(path), visiting, mins),
+            ),
+            Definition::Sequence { elements: e } => Expr::sum(vec![
+                Expr::Const(4),
+                Expr::repeat(
+                    Count::Length(path.to_string()),
+                    expr(schema, e, &elements(path), visiting, mins),
+                ),
+            ]),
+            Definition::Enum { variants } => Expr::sum(vec![
+                Expr::Const(1),
+                Expr::choice(
+                    variants
+                        .iter()
+                        .map(|(name, d)| expr(schema, d, &child(path, name), visiting, mins))
+                        .collect(),
+                ),
+            ]),
+            Definition::Struct {
+                fields: Fields::NamedFields(fields),
+            } => Expr::sum(
+                fields
+                    .iter()
+                    .map(|(name, d)| expr(schema, d, &child(path, name), visiting, mins))
+                    .collect(),
+            ),
+            Definition::Tuple { elements: fields }
+            | Definition::Struct {
+                fields: Fields::UnnamedFields(fields),
+            } => Expr::sum(
+                fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, d)| expr(schema, d, &child(path, &i.to_string()), visiting, mins))
+                    .collect(),
+            ),
+            Definition::Struct {
+                fields: Fields::Empty,
+            } => Expr::Const(0),
+        }
+    };
+
+    visiting.remove(declaration);
+    result
+}
+
+/// Minimum encoded size of every definition, found by iterating to a fixed
+/// point so that recursive types get the size of their smallest value.
+fn min_sizes(schema: &BorshSchemaContainer) -> HashMap<&Declaration, u64> {
+    let mut mins = schema
+        .definitions
+        .keys()
+        .map(|d| (d, u64::MAX))
+        .collect::<HashMap<_, _>>();
+
+    let min_of = |mins: &HashMap<&Declaration, u64>, d: &Declaration| match mins.get(d) {
+        Some(min) => *min,
+        None if d == "string" => 4,
+        None => primitive_size(d).unwrap_or(0),
+    };
+
+    loop {
+        let mut changed = false;
+        for (declaration, definition) in &schema.definitions {
+            let min = match definition {
+                Definition::Array { length, elements } => {
+                    min_of(&mins, elements).saturating_mul(u64::from(*length))
+                }
+                Definition::Sequence { .. } => 4,
+                Definition::Enum { variants } => variants
+                    .iter()
+                    .map(|(_, d)| min_of(&mins, d))
+                    .min()
+                    .unwrap_or(0)
+                    .saturating_add(1),
+                Definition::Tuple { .. } | Definition::Struct { .. } => references(definition)
+                    .into_iter()
+                    .fold(0u64, |sum, d| sum.saturating_add(min_of(&mins, d))),
+            };
+            if min < mins[declaration] {
+                mins.insert(declaration, min);
+                changed = true;
+            }
+        }
+        if !changed {
+            return mins;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{
+        schema::{BorshSchemaContainer, Definition, Fields},
+        BorshSchema,
+    };
+
+    use super::{sizes, SizeKind};
+    use fixtures::Account;
+
+    #[allow(dead_code)]
+    mod fixtures {
+        use borsh::BorshSchema;
+
+        #[derive(BorshSchema)]
+        pub struct Account {
+            owner: [u8; 32],
+            balance: u64,
+            status: Status,
+            entries: Vec<Entry>,
+            name: String,
+        }
+
+        #[derive(BorshSchema)]
+        pub enum Status {
+            Active,
+            Frozen { until: u64 },
+        }
+
+        #[derive(BorshSchema)]
+        pub struct Entry(u8, [u8; 32]);
+    }
+
+    #[test]
+    fn test() {
+        let sizes = sizes(&Account::schema_container());
+        let lines = sizes.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "Account: at least 49 bytes, 49 + (0 | 8) + 33*len(.entries) + len(.name)",
+        );
+        assert!(lines.contains(&"Status: 1 to 9 bytes, 1 + (0 | 8)".to_string()));
+        assert!(lines.contains(&"Entry: 33 bytes".to_string()));
+        assert!(lines.contains(&"Vec<Entry>: at least 4 bytes, 4 + 33*len(.)".to_string()));
+    }
+
+    #[test]
+    fn recursive() {
+        let schema = BorshSchemaContainer {
+            declaration: "Tree".to_string(),
+            definitions: [
+                (
+                    "Tree",
+                    Definition::Struct {
+                        fields: Fields::NamedFields(vec![
+                            ("value".to_string(), "u32".to_string()),
+                            ("children".to_string(), "Vec<Tree>".to_string()),
+                        ]),
+                    },
+                ),
+                (
+                    "Vec<Tree>",
+                    Definition::Sequence {
+                        elements: "Tree".to_string(),
+                    },
+                ),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+        };
+
+        let sizes = sizes(&schema);
+        assert_eq!(sizes[0].kind, SizeKind::Unbounded);
+        assert_eq!(sizes[0].min, 8);
+        assert_eq!(
+            sizes[0].formula.as_deref(),
+            Some("8 + len(.children)*size(Tree)"),
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::Write;
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::schema_util::kind;
+
+/// Renders the schema as a tree rooted at its declaration, e.g.
+///
+/// ```text
+/// Parent (struct)
+/// ├── integer: u32
+/// └── child: Option<u8> (enum)
+///     ├── None: nil
+///     └── Some: u8
+/// ```
+///
+/// A declaration that refers back to one of its ancestors is marked as
+/// recursive and not expanded again.
+pub fn tree(schema: &BorshSchemaContainer) -> String {
+    let mut out = String::new();
+    let mut path = Vec::new();
+    writeln!(out, "{}", node_label(schema, &schema.declaration, &path)).unwrap();
+    write_children(&mut out, schema, &schema.declaration, &mut path, "");
+    out
+}
+
+fn node_label(
+    schema: &BorshSchemaContainer,
+    declaration: &Declaration,
+    path: &[&Declaration],
+) -> String {
+    match schema.definitions.get(declaration) {
+        _ if path.contains(&declaration) => format!("{declaration} (recursive)"),
+        Some(Definition::Array { length, .. }) => format!("{declaration} (array of {length})"),
+        Some(definition) => format!("{declaration} ({})", kind(definition)),
+        None => declaration.to_string(),
+    }
+}
+
+fn children(definition: &Definition) -> Vec<(String, &Declaration)> {
+    match definition {
+        Definition::Array { elements, .. } | Definition::Sequence { elements } => {
+            vec![("items".to_string(), elements)]
+        }
+        Definition::Tuple { elements } => elements
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (i.to_string(), d))
+            .collect(),
+        Definition::Enum { variants } => variants.iter().map(|(v, d)| (v.clone(), d)).collect(),
+        Definition::Struct { fields } => match fields {
+            Fields::NamedFields(fields) => fields.iter().map(|(f, d)| (f.clone(), d)).collect(),
+            Fields::UnnamedFields(fields) => fields
+                .iter()
+                .enumerate()
+                .map(|(i, d)| (i.to_string(), d))
+                .collect(),
+            Fields::Empty => vec![],
+        },
+    }
+}
+
+fn write_children<'a>(
+    out: &mut String,
+    schema: &'a BorshSchemaContainer,
+    declaration: &'a Declaration,
+    path: &mut Vec<&'a Declaration>,
+    prefix: &str,
+) {
+    let definition = match schema.definitions.get(declaration) {
+        Some(definition) if !path.contains(&declaration) => definition,
+        _ => return,
+    };
+
+    path.push(declaration);
+    let children = children(definition);
+    for (i, (label, child)) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        writeln!(
+            out,
+            "{prefix}{branch}{label}: {}",
+            node_label(schema, child, path),
+        )
+        .unwrap();
+        write_children(out, schema, child, path, &format!("{prefix}{indent}"));
+    }
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSchema;
+
+    use super::tree;
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Parent {
+        pair: (u8, u16),
+        child: Option<u8>,
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(
+            tree(&Parent::schema_container()),
+            "Parent (struct)
+├── pair: Tuple<u8, u16> (tuple)
+│   ├── 0: u8
+│   └── 1: u16
+└── child: Option<u8> (enum)
+    ├── None: nil
+    └── Some: u8
+",
+        );
+    }
+}
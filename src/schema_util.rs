@@ -0,0 +1,390 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+use borsh::{
+    schema::{BorshSchemaContainer, Declaration, Definition, Fields},
+    BorshDeserialize, BorshSerialize,
+};
+
+/// Declarations that are built in rather than defined in a schema.
+pub const PRIMITIVE_DECLARATIONS: [&str; 15] = [
+    "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "f32", "f64", "bool",
+    "string", "nil",
+];
+
+pub fn is_primitive(declaration: &str) -> bool {
+    PRIMITIVE_DECLARATIONS.contains(&declaration)
+}
+
+/// Whether a declaration is one of the integer primitives, `u8` to `i128`.
+pub(crate) fn is_integer(declaration: &str) -> bool {
+    matches!(
+        declaration,
+        "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128"
+    )
+}
+
+/// A copy of a schema, since containers are not `Clone`.
+pub fn clone_schema(schema: &BorshSchemaContainer) -> BorshSchemaContainer {
+    let bytes = schema.try_to_vec().expect("schema serializes");
+    BorshSchemaContainer::try_from_slice(&bytes).expect("schema round-trips")
+}
+
+/// Declarations directly referenced by a definition, in serialization order.
+pub fn references(definition: &Definition) -> Vec<&Declaration> {
+    match definition {
+        Definition::Array { elements, .. } | Definition::Sequence { elements } => vec![elements],
+        Definition::Tuple { elements } => elements.iter().collect(),
+        Definition::Enum { variants } => variants.iter().map(|(_, d)| d).collect(),
+        Definition::Struct { fields } => match fields {
+            Fields::NamedFields(fields) => fields.iter().map(|(_, d)| d).collect(),
+            Fields::UnnamedFields(fields) => fields.iter().collect(),
+            Fields::Empty => vec![],
+        },
+    }
+}
+
+/// Defined declarations reachable from the root declaration, with every
+/// definition listed after the definitions it depends on. Cycles are broken
+/// at the first declaration revisited.
+pub fn dependency_order(schema: &BorshSchemaContainer) -> Vec<&Declaration> {
+    fn visit<'a>(
+        schema: &'a BorshSchemaContainer,
+        declaration: &'a Declaration,
+        visited: &mut HashSet<&'a Declaration>,
+        order: &mut Vec<&'a Declaration>,
+    ) {
+        let definition = match schema.definitions.get_key_value(declaration) {
+            Some((key, definition)) if visited.insert(key) => definition,
+            _ => return,
+        };
+        for reference in references(definition) {
+            visit(schema, reference, visited, order);
+        }
+        order.push(declaration);
+    }
+
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    visit(schema, &schema.declaration, &mut visited, &mut order);
+    order
+}
+
+/// Defined declarations that cannot be reached from the root declaration,
+/// sorted by name.
+pub fn unreachable_definitions(schema: &BorshSchemaContainer) -> Vec<&Declaration> {
+    let reachable = dependency_order(schema).into_iter().collect::<HashSet<_>>();
+    let mut unreachable = schema
+        .definitions
+        .keys()
+        .filter(|d| !reachable.contains(d))
+        .collect::<Vec<_>>();
+    unreachable.sort();
+    unreachable
+}
+
+/// Short name for the kind of a definition, e.g. `struct`.
+pub fn kind(definition: &Definition) -> &'static str {
+    match definition {
+        Definition::Array { .. } => "array",
+        Definition::Sequence { .. } => "sequence",
+        Definition::Tuple { .. } => "tuple",
+        Definition::Enum { .. } => "enum",
+        Definition::Struct { .. } => "struct",
+    }
+}
+
+/// Defined declarations that (directly or indirectly) refer to themselves.
+pub fn recursive_declarations(schema: &BorshSchemaContainer) -> HashSet<&Declaration> {
+    let edges = schema
+        .definitions
+        .iter()
+        .map(|(declaration, definition)| (declaration, references(definition)))
+        .collect::<HashMap<_, _>>();
+
+    edges
+        .keys()
+        .filter(|&&start| {
+            let mut stack = edges[start].clone();
+            let mut seen = HashSet::new();
+            while let Some(next) = stack.pop() {
+                if next == start {
+                    return true;
+                }
+                if seen.insert(next) {
+                    if let Some(children) = edges.get(next) {
+                        stack.extend(children);
+                    }
+                }
+            }
+            false
+        })
+        .copied()
+        .collect()
+}
+
+/// If the definition has the shape Borsh generates for `Option<T>`, returns
+/// the declaration of `T`.
+pub fn as_option(definition: &Definition) -> Option<&Declaration> {
+    match definition {
+        Definition::Enum { variants } => match &variants[..] {
+            [(none, nil), (some, inner)] if none == "None" && nil == "nil" && some == "Some" => {
+                Some(inner)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// If the declaration is a `Result<T, E>`, i.e. an enum with variants `Ok`
+/// and `Err`, returns the declarations of `T` and `E`.
+pub fn as_result<'a>(
+    declaration: &Declaration,
+    definition: &'a Definition,
+) -> Option<(&'a Declaration, &'a Declaration)> {
+    if !declaration.starts_with("Result<") {
+        return None;
+    }
+    match definition {
+        Definition::Enum { variants } => match &variants[..] {
+            [(ok, t), (err, e)] if ok == "Ok" && err == "Err" => Some((t, e)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// If the declaration is a `HashMap<K, V>`, i.e. a sequence of key-value
+/// tuples, returns the declarations of `K` and `V`.
+pub fn as_map<'a>(
+    schema: &'a BorshSchemaContainer,
+    declaration: &Declaration,
+) -> Option<(&'a Declaration, &'a Declaration)> {
+    if !declaration.starts_with("HashMap<") {
+        return None;
+    }
+    match schema.definitions.get(declaration)? {
+        Definition::Sequence { elements } => match schema.definitions.get(elements)? {
+            Definition::Tuple { elements } if elements.len() == 2 => {
+                Some((&elements[0], &elements[1]))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Size in bytes of a primitive declaration, or `None` for `string`.
+pub fn primitive_size(declaration: &str) -> Option<u64> {
+    match declaration {
+        "nil" => Some(0),
+        "u8" | "i8" | "bool" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" | "f32" => Some(4),
+        "u64" | "i64" | "f64" => Some(8),
+        "u128" | "i128" => Some(16),
+        _ => None,
+    }
+}
+
+/// Encoded size in bytes if every value of the declaration has the same
+/// size, e.g. a struct of integers, or an enum whose variants all have
+/// payloads of the same fixed size.
+pub fn fixed_size(schema: &BorshSchemaContainer, declaration: &Declaration) -> Option<u64> {
+    fn visit<'a>(
+        schema: &'a BorshSchemaContainer,
+        declaration: &'a Declaration,
+        visiting: &mut HashSet<&'a Declaration>,
+    ) -> Option<u64> {
+        let definition = match schema.definitions.get(declaration) {
+            Some(definition) => definition,
+            None => return primitive_size(declaration),
+        };
+        // A recursive type always has values of different sizes
+        if !visiting.insert(declaration) {
+            return None;
+        }
+        let size = match definition {
+            Definition::Array { length, elements } => {
+                visit(schema, elements, visiting)?.checked_mul(u64::from(*length))
+            }
+            Definition::Sequence { .. } => None,
+            Definition::Enum { variants } => {
+                let mut sizes = variants.iter().map(|(_, d)| visit(schema, d, visiting));
+                let first = sizes.next().unwrap_or(Some(0))?;
+                sizes.all(|size| size == Some(first)).then_some(1 + first)
+            }
+            Definition::Tuple { .. } | Definition::Struct { .. } => references(definition)
+                .into_iter()
+                .try_fold(0u64, |sum, d| sum.checked_add(visit(schema, d, visiting)?)),
+        };
+        visiting.remove(declaration);
+        size
+    }
+
+    visit(schema, declaration, &mut HashSet::new())
+}
+
+/// The declaration of a built-in Rust type, e.g. `string` for `String`.
+pub fn primitive_declaration(name: &str) -> Option<Declaration> {
+    match name {
+        "String" => Some("string".to_string()),
+        "string" | "nil" => None,
+        _ => is_primitive(name).then(|| name.to_string()),
+    }
+}
+
+/// Number of type arguments of a generic Rust type that Borsh defines a
+/// schema for, e.g. 1 for `Vec`.
+pub fn generic_arity(name: &str) -> Option<usize> {
+    match name {
+        "Box" | "Vec" | "Option" => Some(1),
+        "Result" | "HashMap" => Some(2),
+        _ => None,
+    }
+}
+
+/// Definitions of a schema being built from Rust-like types, named the way
+/// `#[derive(BorshSchema)]` names them.
+#[derive(Default)]
+pub struct SchemaBuilder {
+    pub definitions: HashMap<Declaration, Definition>,
+}
+
+impl SchemaBuilder {
+    /// Adds a definition, unless the declaration is already defined.
+    pub fn add_definition(&mut self, declaration: &Declaration, definition: Definition) {
+        if let Entry::Vacant(entry) = self.definitions.entry(declaration.clone()) {
+            entry.insert(definition);
+        }
+    }
+
+    pub fn array(&mut self, elements: Declaration, length: u32) -> Declaration {
+        let declaration = format!("Array<{elements}, {length}>");
+        self.add_definition(&declaration, Definition::Array { length, elements });
+        declaration
+    }
+
+    pub fn tuple(&mut self, elements: Vec<Declaration>) -> Declaration {
+        let declaration = format!("Tuple<{}>", elements.join(", "));
+        self.add_definition(&declaration, Definition::Tuple { elements });
+        declaration
+    }
+
+    /// A generic type applied to type arguments, whose number must match
+    /// [`generic_arity`]. `Box<T>` is `T` itself.
+    pub fn generic(&mut self, name: &str, arguments: Vec<Declaration>) -> Declaration {
+        let (declaration, definition) = match (name, &arguments[..]) {
+            ("Box", [inner]) => return inner.clone(),
+            ("Vec", [elements]) => (
+                format!("Vec<{elements}>"),
+                Definition::Sequence {
+                    elements: elements.clone(),
+                },
+            ),
+            ("Option", [inner]) => (
+                format!("Option<{inner}>"),
+                Definition::Enum {
+                    variants: vec![
+                        ("None".to_string(), "nil".to_string()),
+                        ("Some".to_string(), inner.clone()),
+                    ],
+                },
+            ),
+            ("Result", [ok, err]) => (
+                format!("Result<{ok}, {err}>"),
+                Definition::Enum {
+                    variants: vec![
+                        ("Ok".to_string(), ok.clone()),
+                        ("Err".to_string(), err.clone()),
+                    ],
+                },
+            ),
+            ("HashMap", [key, value]) => (
+                format!("HashMap<{key}, {value}>"),
+                Definition::Sequence {
+                    elements: self.tuple(arguments.clone()),
+                },
+            ),
+            _ => panic!("{name} does not take {} type argument(s)", arguments.len()),
+        };
+        self.add_definition(&declaration, definition);
+        declaration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+
+    use super::{dependency_order, fixed_size, recursive_declarations, unreachable_definitions};
+
+    #[test]
+    fn test() {
+        // The derive macro cannot produce recursive schemas
+        let schema = BorshSchemaContainer {
+            declaration: "Parent".to_string(),
+            definitions: [
+                (
+                    "Parent",
+                    Definition::Struct {
+                        fields: Fields::NamedFields(vec![
+                            ("children".to_string(), "Vec<Child>".to_string()),
+                            ("tuple".to_string(), "Tuple<u8, Child>".to_string()),
+                        ]),
+                    },
+                ),
+                (
+                    "Child",
+                    Definition::Struct {
+                        fields: Fields::UnnamedFields(vec!["Option<Parent>".to_string()]),
+                    },
+                ),
+                (
+                    "Option<Parent>",
+                    Definition::Enum {
+                        variants: vec![
+                            ("None".to_string(), "nil".to_string()),
+                            ("Some".to_string(), "Parent".to_string()),
+                        ],
+                    },
+                ),
+                (
+                    "Vec<Child>",
+                    Definition::Sequence {
+                        elements: "Child".to_string(),
+                    },
+                ),
+                (
+                    "Tuple<u8, Child>",
+                    Definition::Tuple {
+                        elements: vec!["u8".to_string(), "Child".to_string()],
+                    },
+                ),
+                (
+                    "Unused",
+                    Definition::Sequence {
+                        elements: "u8".to_string(),
+                    },
+                ),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+        };
+
+        assert_eq!(
+            dependency_order(&schema),
+            vec![
+                "Option<Parent>",
+                "Child",
+                "Vec<Child>",
+                "Tuple<u8, Child>",
+                "Parent",
+            ],
+        );
+        assert_eq!(recursive_declarations(&schema).len(), 5);
+        assert_eq!(unreachable_definitions(&schema), vec!["Unused"]);
+        assert_eq!(fixed_size(&schema, &"Parent".to_string()), None);
+    }
+}
@@ -0,0 +1,295 @@
+//! Structural checks for schema containers, so that malformed schemas are
+//! rejected up front instead of failing (or never finishing) mid-decode.
+
+use std::{collections::HashSet, fmt};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+
+use crate::schema_util::{
+    is_primitive, recursive_declarations, references, unreachable_definitions,
+};
+
+/// Borsh encodes enum tags as a single byte.
+const MAX_VARIANTS: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// Data cannot be reliably encoded or decoded with the schema.
+    Error,
+    /// The schema works, but is probably not what was intended.
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub declaration: Declaration,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}: {}: {}", self.declaration, self.message)
+    }
+}
+
+/// Every issue found in the schema, errors first, each group sorted by
+/// declaration.
+pub fn validate(schema: &BorshSchemaContainer) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut issue = |severity, declaration: &Declaration, message: String| {
+        issues.push(Issue {
+            severity,
+            declaration: declaration.clone(),
+            message,
+        })
+    };
+
+    if !is_defined(schema, &schema.declaration) {
+        issue(
+            Severity::Error,
+            &schema.declaration,
+            "root declaration is not defined".to_string(),
+        );
+    }
+
+    let mut definitions = schema.definitions.iter().collect::<Vec<_>>();
+    definitions.sort_by_key(|(declaration, _)| *declaration);
+
+    for (declaration, definition) in &definitions {
+        for reference in references(definition) {
+            if !is_defined(schema, reference) {
+                issue(
+                    Severity::Error,
+                    declaration,
+                    format!("refers to {reference}, which is not defined"),
+                );
+            }
+        }
+
+        let names = match definition {
+            Definition::Enum { variants } => {
+                if variants.len() > MAX_VARIANTS {
+                    issue(
+                        Severity::Error,
+                        declaration,
+                        format!(
+                            "has {} variants, but at most {MAX_VARIANTS} fit in a one-byte tag",
+                            variants.len(),
+                        ),
+                    );
+                }
+                if variants.is_empty() {
+                    issue(
+                        Severity::Warning,
+                        declaration,
+                        "has no variants, so no value of it can be encoded".to_string(),
+                    );
+                }
+                variants.iter().map(|(name, _)| name).collect()
+            }
+            Definition::Struct {
+                fields: Fields::NamedFields(fields),
+            } => fields.iter().map(|(name, _)| name).collect(),
+            _ => vec![],
+        };
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        for name in names {
+            if seen.insert(name) || !reported.insert(name) {
+                continue;
+            }
+            let what = match definition {
+                Definition::Enum { .. } => "variant",
+                _ => "field",
+            };
+            issue(
+                Severity::Error,
+                declaration,
+                format!("has more than one {what} named {name}"),
+            );
+        }
+    }
+
+    let finite = finite_declarations(schema);
+    let mut infinite = recursive_declarations(schema)
+        .into_iter()
+        .filter(|d| !finite.contains(d))
+        .collect::<Vec<_>>();
+    infinite.sort();
+    for declaration in infinite {
+        issue(
+            Severity::Error,
+            declaration,
+            "is recursive without a base case, so every value would be infinitely large"
+                .to_string(),
+        );
+    }
+
+    for declaration in unreachable_definitions(schema) {
+        issue(
+            Severity::Warning,
+            declaration,
+            "is not reachable from the root declaration".to_string(),
+        );
+    }
+
+    issues.sort_by(|a, b| {
+        (a.severity != Severity::Error, &a.declaration)
+            .cmp(&(b.severity != Severity::Error, &b.declaration))
+    });
+    issues
+}
+
+fn is_defined(schema: &BorshSchemaContainer, declaration: &Declaration) -> bool {
+    is_primitive(declaration) || schema.definitions.contains_key(declaration)
+}
+
+/// Declarations that have at least one finite value, found by fixed-point
+/// iteration. Undefined declarations and empty enums are counted as finite,
+/// since they are reported separately.
+fn finite_declarations(schema: &BorshSchemaContainer) -> HashSet<&Declaration> {
+    let mut finite = HashSet::new();
+    let is_finite = |finite: &HashSet<&Declaration>, declaration: &Declaration| {
+        !schema.definitions.contains_key(declaration) || finite.contains(declaration)
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (declaration, definition) in &schema.definitions {
+            if finite.contains(declaration) {
+                continue;
+            }
+            let has_finite_value = match definition {
+                Definition::Sequence { .. } => true,
+                Definition::Array { length: 0, .. } => true,
+                Definition::Enum { variants } => {
+                    variants.is_empty() || variants.iter().any(|(_, d)| is_finite(&finite, d))
+                }
+                definition => references(definition)
+                    .into_iter()
+                    .all(|d| is_finite(&finite, d)),
+            };
+            if has_finite_value {
+                finite.insert(declaration);
+                changed = true;
+            }
+        }
+    }
+
+    finite
+}
+
+/// Errors from [`validate`], one per line (after the first, indented).
+pub fn errors(schema: &BorshSchemaContainer) -> Option<String> {
+    let errors = validate(schema)
+        .into_iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| format!("{}: {}", issue.declaration, issue.message))
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors.join("\n  "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{
+        schema::{BorshSchemaContainer, Definition, Fields},
+        BorshSchema,
+    };
+
+    use super::{validate, Severity};
+
+    #[derive(BorshSchema)]
+    #[allow(dead_code)]
+    struct Valid {
+        a: Option<u8>,
+        b: Vec<String>,
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(validate(&Valid::schema_container()), vec![]);
+
+        let definition = |fields: &[(&str, &str)]| Definition::Struct {
+            fields: Fields::NamedFields(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+        };
+        let schema = BorshSchemaContainer {
+            declaration: "Root".to_string(),
+            definitions: [
+                ("Root", definition(&[("a", "Missing"), ("a", "Loop")])),
+                ("Loop", definition(&[("next", "Loop")])),
+                (
+                    "Tree",
+                    definition(&[("children", "Vec<Tree>"), ("b", "Option<Tree>")]),
+                ),
+                (
+                    "Vec<Tree>",
+                    Definition::Sequence {
+                        elements: "Tree".to_string(),
+                    },
+                ),
+                (
+                    "Option<Tree>",
+                    Definition::Enum {
+                        variants: vec![
+                            ("None".to_string(), "nil".to_string()),
+                            ("Some".to_string(), "Tree".to_string()),
+                        ],
+                    },
+                ),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+        };
+
+        let issues = validate(&schema)
+            .into_iter()
+            .map(|i| (i.severity, i.declaration, i.message))
+            .collect::<Vec<_>>();
+        let expected = [
+            (
+                Severity::Error,
+                "Loop",
+                "is recursive without a base case, so every value would be infinitely large",
+            ),
+            (
+                Severity::Error,
+                "Root",
+                "refers to Missing, which is not defined",
+            ),
+            (Severity::Error, "Root", "has more than one field named a"),
+            (
+                Severity::Warning,
+                "Option<Tree>",
+                "is not reachable from the root declaration",
+            ),
+            (
+                Severity::Warning,
+                "Tree",
+                "is not reachable from the root declaration",
+            ),
+            (
+                Severity::Warning,
+                "Vec<Tree>",
+                "is not reachable from the root declaration",
+            ),
+        ]
+        .map(|(s, d, m)| (s, d.to_string(), m.to_string()));
+        assert_eq!(issues, expected);
+    }
+}
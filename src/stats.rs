@@ -0,0 +1,517 @@
+//! Statistics collected while decoding data, see [`crate::dynamic_schema::Visitor`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition};
+use serde::Serialize;
+
+use crate::{dynamic_schema::Visitor, query::Integer, schema_util::as_option};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    /// Number of values.
+    pub count: u64,
+    /// Encoded size of those values, including everything nested in them.
+    pub bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, size: usize) {
+        self.count += 1;
+        self.bytes += size as u64;
+    }
+}
+
+/// Encoded bytes per field path and per type.
+#[derive(Debug, Default)]
+pub struct ByteUsage {
+    pub paths: BTreeMap<String, Usage>,
+    pub types: HashMap<Declaration, Usage>,
+}
+
+impl ByteUsage {
+    /// Bytes of all root values.
+    pub fn total(&self) -> u64 {
+        self.paths.get(".").map_or(0, |usage| usage.bytes)
+    }
+
+    /// Types sorted by bytes, largest first.
+    pub fn types_by_size(&self) -> Vec<(&Declaration, &Usage)> {
+        let mut types = self.types.iter().collect::<Vec<_>>();
+        types.sort_by(|(a, x), (b, y)| y.bytes.cmp(&x.bytes).then(a.cmp(b)));
+        types
+    }
+}
+
+impl Visitor for ByteUsage {
+    fn visit(
+        &mut self,
+        path: &str,
+        declaration: &Declaration,
+        _: &serde_json::Value,
+        _: usize,
+        size: usize,
+    ) {
+        // Avoid allocating for paths and types seen before
+        match self.paths.get_mut(path) {
+            Some(usage) => usage.add(size),
+            None => self.paths.entry(path.to_string()).or_default().add(size),
+        }
+        match self.types.get_mut(declaration) {
+            Some(usage) => usage.add(size),
+            None => self.types.entry(declaration.clone()).or_default().add(size),
+        }
+    }
+}
+
+/// Running summary of the values at one path.
+#[derive(Debug)]
+enum Accumulator {
+    Number {
+        min: serde_json::Value,
+        max: serde_json::Value,
+        sum: f64,
+    },
+    /// Strings, booleans and enum variant names.
+    Category(HashMap<String, u64>),
+    Option {
+        none: u64,
+    },
+    Sequence {
+        min: u64,
+        max: u64,
+        total: u64,
+        /// Counts by [`bucket`] of the length.
+        histogram: BTreeMap<u32, u64>,
+    },
+}
+
+/// Index of the power-of-two bucket a length falls into: 0, 1, 2-3, 4-7, …
+fn bucket(length: u64) -> u32 {
+    64 - length.leading_zeros()
+}
+
+fn bucket_label(bucket: u32) -> String {
+    match bucket {
+        0 => "0".to_string(),
+        1 => "1".to_string(),
+        b => format!("{}-{}", 1u128 << (b - 1), (1u128 << b) - 1),
+    }
+}
+
+fn as_f64(value: &serde_json::Value) -> f64 {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+        // 128-bit integers
+        serde_json::Value::String(s) => s.parse().unwrap_or(f64::NAN),
+        _ => f64::NAN,
+    }
+}
+
+fn as_integer(value: &serde_json::Value) -> Option<Integer> {
+    match value {
+        serde_json::Value::Number(n) => n.to_string().parse().ok(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Compares integers exactly, as 64-bit and 128-bit integers can be too large
+/// for `f64` to tell apart, and other numbers as `f64`.
+fn is_less(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    match (as_integer(a), as_integer(b)) {
+        (Some(a), Some(b)) => a < b,
+        _ => as_f64(a) < as_f64(b),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Count {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Summary {
+    Number {
+        min: serde_json::Value,
+        max: serde_json::Value,
+        mean: f64,
+    },
+    Category {
+        distinct: usize,
+        top: Vec<Count>,
+    },
+    Option {
+        none_ratio: f64,
+    },
+    Sequence {
+        min_length: u64,
+        max_length: u64,
+        mean_length: f64,
+        histogram: Vec<Count>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldSummary {
+    pub path: String,
+    pub r#type: Declaration,
+    pub count: u64,
+    #[serde(flatten)]
+    pub summary: Summary,
+}
+
+/// Summaries of the numbers, strings, enums, options and sequences at every
+/// field path. Structs, tuples and fixed-size arrays are only traversed.
+#[derive(Debug)]
+pub struct ValueStats<'a> {
+    schema: &'a BorshSchemaContainer,
+    fields: BTreeMap<String, (Declaration, u64, Accumulator)>,
+}
+
+impl<'a> ValueStats<'a> {
+    pub fn new(schema: &'a BorshSchemaContainer) -> Self {
+        Self {
+            schema,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    fn accumulator(&self, declaration: &Declaration) -> Option<Accumulator> {
+        match self.schema.definitions.get(declaration) {
+            None => match declaration.as_str() {
+                "string" | "bool" => Some(Accumulator::Category(HashMap::new())),
+                "nil" => None,
+                _ => Some(Accumulator::Number {
+                    min: serde_json::Value::Null,
+                    max: serde_json::Value::Null,
+                    sum: 0.0,
+                }),
+            },
+            Some(definition) if as_option(definition).is_some() => {
+                Some(Accumulator::Option { none: 0 })
+            }
+            Some(Definition::Enum { .. }) => Some(Accumulator::Category(HashMap::new())),
+            Some(Definition::Sequence { .. }) => Some(Accumulator::Sequence {
+                min: u64::MAX,
+                max: 0,
+                total: 0,
+                histogram: BTreeMap::new(),
+            }),
+            Some(_) => None,
+        }
+    }
+
+    /// Summaries in path order, with at most `top` values for strings and
+    /// enums.
+    pub fn summaries(&self, top: usize) -> Vec<FieldSummary> {
+        self.fields
+            .iter()
+            .map(|(path, (declaration, count, accumulator))| {
+                let summary = match accumulator {
+                    Accumulator::Number { min, max, sum } => Summary::Number {
+                        min: min.clone(),
+                        max: max.clone(),
+                        mean: sum / *count as f64,
+                    },
+                    Accumulator::Category(values) => {
+                        let mut counts = values
+                            .iter()
+                            .map(|(value, count)| Count {
+                                value: value.clone(),
+                                count: *count,
+                            })
+                            .collect::<Vec<_>>();
+                        counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+                        counts.truncate(top);
+                        Summary::Category {
+                            distinct: values.len(),
+                            top: counts,
+                        }
+                    }
+                    Accumulator::Option { none } => Summary::Option {
+                        none_ratio: *none as f64 / *count as f64,
+                    },
+                    Accumulator::Sequence {
+                        min,
+                        max,
+                        total,
+                        histogram,
+                    } => Summary::Sequence {
+                        min_length: *min,
+                        max_length: *max,
+                        mean_length: *total as f64 / *count as f64,
+                        histogram: histogram
+                            .iter()
+                            .map(|(bucket, count)| Count {
+                                value: bucket_label(*bucket),
+                                count: *count,
+                            })
+                            .collect(),
+                    },
+                };
+                FieldSummary {
+                    path: path.clone(),
+                    r#type: declaration.clone(),
+                    count: *count,
+                    summary,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Visitor for ValueStats<'_> {
+    fn visit(
+        &mut self,
+        path: &str,
+        declaration: &Declaration,
+        value: &serde_json::Value,
+        _: usize,
+        _: usize,
+    ) {
+        if !self.fields.contains_key(path) {
+            match self.accumulator(declaration) {
+                Some(accumulator) => {
+                    self.fields
+                        .insert(path.to_string(), (declaration.clone(), 0, accumulator));
+                }
+                None => return,
+            }
+        }
+        let (_, count, accumulator) = self.fields.get_mut(path).unwrap();
+        *count += 1;
+
+        match accumulator {
+            Accumulator::Number { min, max, sum } => {
+                if min.is_null() || is_less(value, min) {
+                    *min = value.clone();
+                }
+                if max.is_null() || is_less(max, value) {
+                    *max = value.clone();
+                }
+                *sum += as_f64(value);
+            }
+            Accumulator::Category(values) => {
+                let key = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    // Enum variant
+                    serde_json::Value::Object(o) => o.keys().next().cloned().unwrap_or_default(),
+                    v => v.to_string(),
+                };
+                *values.entry(key).or_default() += 1;
+            }
+            Accumulator::Option { none } => {
+                if value.get("None").is_some() {
+                    *none += 1;
+                }
+            }
+            Accumulator::Sequence {
+                min,
+                max,
+                total,
+                histogram,
+            } => {
+                let length = value.as_array().map_or(0, |a| a.len() as u64);
+                *min = (*min).min(length);
+                *max = (*max).max(length);
+                *total += length;
+                *histogram.entry(bucket(length)).or_default() += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshSchema, BorshSerialize};
+
+    use crate::dynamic_schema::walk_from_schema;
+
+    use super::{ByteUsage, Summary, Usage, ValueStats};
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Account {
+        id: u32,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test() {
+        let schema = Account::schema_container();
+        let mut data = vec![];
+        for tags in [vec!["a", "bcd"], vec![]] {
+            Account {
+                id: 1,
+                tags: tags.into_iter().map(String::from).collect(),
+            }
+            .serialize(&mut data)
+            .unwrap();
+        }
+
+        let mut usage = ByteUsage::default();
+        let mut buf = &data as &[u8];
+        while !buf.is_empty() {
+            walk_from_schema(&mut buf, &schema, &mut usage).unwrap();
+        }
+
+        assert_eq!(usage.total(), 28);
+        assert_eq!(
+            usage.paths["."],
+            Usage {
+                count: 2,
+                bytes: 28
+            }
+        );
+        assert_eq!(usage.paths[".id"], Usage { count: 2, bytes: 8 });
+        assert_eq!(
+            usage.paths[".tags"],
+            Usage {
+                count: 2,
+                bytes: 20
+            }
+        );
+        assert_eq!(
+            usage.paths[".tags[]"],
+            Usage {
+                count: 2,
+                bytes: 12
+            }
+        );
+        assert_eq!(
+            usage.types["string"],
+            Usage {
+                count: 2,
+                bytes: 12
+            }
+        );
+        assert_eq!(usage.types_by_size()[0].0, "Account");
+    }
+
+    #[derive(BorshSerialize, BorshSchema)]
+    enum Kind {
+        Transfer,
+        Mint,
+    }
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Event {
+        amount: u64,
+        kind: Kind,
+        memo: Option<String>,
+    }
+
+    #[test]
+    fn values() {
+        let events = vec![
+            Event {
+                amount: 10,
+                kind: Kind::Transfer,
+                memo: None,
+            },
+            Event {
+                amount: 30,
+                kind: Kind::Transfer,
+                memo: Some("x".to_string()),
+            },
+            Event {
+                amount: 20,
+                kind: Kind::Mint,
+                memo: None,
+            },
+            Event {
+                amount: 0,
+                kind: Kind::Transfer,
+                memo: None,
+            },
+        ];
+        let schema = Vec::<Event>::schema_container();
+        let data = events.try_to_vec().unwrap();
+
+        let mut stats = ValueStats::new(&schema);
+        walk_from_schema(&mut (&data as &[u8]), &schema, &mut stats).unwrap();
+        let summaries = stats.summaries(1);
+        let paths = summaries
+            .iter()
+            .map(|s| s.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [".", ".[].amount", ".[].kind", ".[].memo", ".[].memo.Some"],
+        );
+
+        assert!(matches!(
+            summaries[0].summary,
+            Summary::Sequence { max_length: 4, .. },
+        ));
+        match &summaries[1].summary {
+            Summary::Number { min, max, mean } => {
+                assert_eq!(
+                    (min.as_u64(), max.as_u64(), *mean),
+                    (Some(0), Some(30), 15.0)
+                );
+            }
+            s => panic!("unexpected {s:?}"),
+        }
+        match &summaries[2].summary {
+            Summary::Category { distinct, top } => {
+                assert_eq!(*distinct, 2);
+                assert_eq!((top[0].value.as_str(), top[0].count), ("Transfer", 3));
+                assert_eq!(top.len(), 1);
+            }
+            s => panic!("unexpected {s:?}"),
+        }
+        assert!(matches!(
+            summaries[3].summary,
+            Summary::Option { none_ratio } if none_ratio == 0.75,
+        ));
+    }
+
+    #[derive(BorshSerialize, BorshSchema)]
+    struct Large {
+        a: u64,
+        b: i128,
+    }
+
+    #[test]
+    fn large_integers() {
+        // Too close together for `f64` to tell apart
+        let values = vec![
+            Large {
+                a: (1 << 60) + 1,
+                b: -(1 << 100),
+            },
+            Large {
+                a: 1 << 60,
+                b: -(1 << 100) - 1,
+            },
+            Large {
+                a: (1 << 60) + 2,
+                b: -(1 << 100) + 1,
+            },
+        ];
+        let schema = Vec::<Large>::schema_container();
+        let data = values.try_to_vec().unwrap();
+
+        let mut stats = ValueStats::new(&schema);
+        walk_from_schema(&mut (&data as &[u8]), &schema, &mut stats).unwrap();
+        let summaries = stats.summaries(1);
+
+        match &summaries[1].summary {
+            Summary::Number { min, max, .. } => {
+                assert_eq!(min.as_u64(), Some(1 << 60));
+                assert_eq!(max.as_u64(), Some((1 << 60) + 2));
+            }
+            s => panic!("unexpected {s:?}"),
+        }
+        match &summaries[2].summary {
+            Summary::Number { min, max, .. } => {
+                assert_eq!(min, &(-(1i128 << 100) - 1).to_string());
+                assert_eq!(max, &(-(1i128 << 100) + 1).to_string());
+            }
+            s => panic!("unexpected {s:?}"),
+        }
+    }
+}